@@ -0,0 +1,72 @@
+/*
+
+Compares CowString and Rope with the eager `clone()` + `push_str` pattern used in `main`.
+
+Run with `cargo bench --bench cow`. Each case reports time and the heap traffic measured by CountingAllocator.
+
+*/
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use ownership::allocations::{measure, Counts, CountingAllocator};
+use ownership::cow::{CowString, Rope};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const COPIES: usize = 100_000;
+const PIECES: usize = 100_000;
+
+fn report<F: FnOnce()>(name: &str, f: F) {
+    let start = Instant::now();
+    let ((), counts): ((), Counts) = measure(f);
+    println!("{:<40} {:>10.2?}  {}", name, start.elapsed(), counts);
+}
+
+fn main() {
+    let s = String::from("hello, world. It's a great day to be here.");
+
+    // Copies of which only one in ten is ever changed.
+    report("String: clone() then push_str", || {
+        let mut copies = Vec::with_capacity(COPIES);
+        for i in 0..COPIES {
+            let mut s2 = s.clone();
+            if i % 10 == 0 {
+                s2.push_str(" Aloha friends!");
+            }
+            copies.push(s2);
+        }
+        black_box(copies);
+    });
+
+    let shared = CowString::from(s.as_str());
+    report("CowString: clone() then push_str", || {
+        let mut copies = Vec::with_capacity(COPIES);
+        for i in 0..COPIES {
+            let mut s2 = shared.clone();
+            if i % 10 == 0 {
+                s2.push_str(" Aloha friends!");
+            }
+            copies.push(s2);
+        }
+        black_box(copies);
+    });
+
+    // A long concatenation chain.
+    report("String: repeated push_str", || {
+        let mut joined = String::new();
+        for _ in 0..PIECES {
+            joined.push_str(" Aloha friends!");
+        }
+        black_box(joined);
+    });
+
+    report("Rope: append then join", || {
+        let mut rope = Rope::new();
+        for _ in 0..PIECES {
+            rope.append(" Aloha friends!");
+        }
+        black_box(String::from(rope));
+    });
+}
@@ -0,0 +1,118 @@
+/*
+
+Counting heap allocations.
+
+A String's ptr/len/capacity triple points at a buffer handed out by the global allocator. CountingAllocator wraps
+the system allocator and counts every request it passes through, so we can see exactly which operations touch the
+heap: `String::from` allocates, `push_str` reallocates once capacity runs out, `clone()` allocates a whole new
+buffer, and a move allocates nothing at all.
+
+To use it, a binary installs it as the global allocator:
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+and then wraps the code it wants to audit in `measure`:
+
+let (s, counts) = measure(|| String::from("hello"));
+
+The counters are process wide, so anything running on another thread during `measure` is counted too.
+
+*/
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::ops::Sub;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static BYTES_FREED: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES_ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        BYTES_FREED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    // A realloc is counted on its own rather than as an alloc plus a dealloc. Growth counts as newly allocated
+    // bytes and shrinking as freed bytes, so the byte totals still balance.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        if new_size > layout.size() {
+            BYTES_ALLOCATED.fetch_add(new_size - layout.size(), Ordering::Relaxed);
+        } else {
+            BYTES_FREED.fetch_add(layout.size() - new_size, Ordering::Relaxed);
+        }
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+// Counts is a snapshot of the allocator's counters. Subtracting an earlier snapshot from a later one gives the
+// counts for whatever ran in between.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Counts {
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub reallocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+}
+
+impl Counts {
+    pub fn now() -> Counts {
+        Counts {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+            bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+            bytes_freed: BYTES_FREED.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Sub for Counts {
+    type Output = Counts;
+
+    fn sub(self, earlier: Counts) -> Counts {
+        Counts {
+            allocations: self.allocations - earlier.allocations,
+            deallocations: self.deallocations - earlier.deallocations,
+            reallocations: self.reallocations - earlier.reallocations,
+            bytes_allocated: self.bytes_allocated - earlier.bytes_allocated,
+            bytes_freed: self.bytes_freed - earlier.bytes_freed,
+        }
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} allocs, {} reallocs, {} frees, {} bytes allocated, {} bytes freed",
+            self.allocations, self.reallocations, self.deallocations, self.bytes_allocated, self.bytes_freed
+        )
+    }
+}
+
+// measure runs `f` and returns its result along with the allocations it made. Only counts anything when
+// CountingAllocator is installed as the global allocator.
+pub fn measure<T, F>(f: F) -> (T, Counts)
+where
+    F: FnOnce() -> T,
+{
+    let before = Counts::now();
+    let result = f();
+    (result, Counts::now() - before)
+}
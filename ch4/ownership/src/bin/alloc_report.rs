@@ -0,0 +1,72 @@
+/*
+
+Reports the heap traffic of the String operations walked through in `main.rs`.
+
+Each step is measured on its own, so the output shows which operations allocate (`String::from`, `clone`), which
+may reallocate (`push_str`), which free (`drop`) and which cost nothing on the heap (a move).
+
+*/
+
+use std::hint::black_box;
+
+use ownership::allocations::{measure, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn takes_ownership(some_string: String) -> usize {
+    some_string.len()
+} // some_string is dropped here, freeing its buffer.
+
+fn main() {
+    let (mut s, counts) = measure(|| String::from("hello"));
+    println!("{:<36} {}", "String::from(\"hello\")", counts);
+
+    let (_, counts) = measure(|| s.push_str(", world."));
+    println!("{:<36} {}", "s.push_str(\", world.\")", counts);
+
+    let (_, counts) = measure(|| s.push_str(" It's a great day to be here."));
+    println!("{:<36} {}", "s.push_str(\" It's a great day...\")", counts);
+
+    let (s2, counts) = measure(|| s.clone());
+    println!("{:<36} {}", "let s2 = s.clone()", counts);
+
+    // A move only copies the ptr/len/capacity triple on the stack; the heap buffer stays where it is.
+    let (s3, counts) = measure(|| black_box(s2));
+    println!("{:<36} {}", "let s3 = s2 (move)", counts);
+
+    let (_, counts) = measure(|| takes_ownership(s3));
+    println!("{:<36} {}", "takes_ownership(s3)", counts);
+
+    let (_, counts) = measure(|| drop(s));
+    println!("{:<36} {}", "drop(s)", counts);
+
+    // Capacity growth: push_str only reallocates when len would exceed capacity, and capacity grows geometrically,
+    // so most pushes are free.
+    println!();
+    println!("{:>6} {:>6} {:>9} {:>8}", "push", "len", "capacity", "reallocs");
+    let mut grown = String::new();
+    let mut previous_capacity = grown.capacity();
+    for push in 1..=64 {
+        let (_, counts) = measure(|| grown.push_str("abcdefgh"));
+        if grown.capacity() != previous_capacity {
+            println!(
+                "{:>6} {:>6} {:>9} {:>8}",
+                push,
+                grown.len(),
+                grown.capacity(),
+                counts.allocations + counts.reallocations
+            );
+            previous_capacity = grown.capacity();
+        }
+    }
+
+    let (_, counts) = measure(|| {
+        let mut s = String::with_capacity(64 * 8);
+        for _ in 0..64 {
+            s.push_str("abcdefgh");
+        }
+        black_box(s)
+    });
+    println!("String::with_capacity(512) + 64 pushes: {}", counts);
+}
@@ -0,0 +1,21 @@
+/*
+
+Prints the ownership timeline of each scenario in `ownership::scenarios`: when every value is created, cloned,
+moved into or returned from a function, and dropped.
+
+*/
+
+use ownership::events::drop_order;
+use ownership::scenarios::scenarios;
+
+fn main() {
+    for (name, scenario) in scenarios() {
+        println!("{}:", name);
+        let events = scenario();
+        for (step, event) in events.iter().enumerate() {
+            println!("{:>4}. {}", step + 1, event);
+        }
+        println!("  drop order: {:?}", drop_order(&events));
+        println!();
+    }
+}
@@ -0,0 +1,236 @@
+/*
+
+Copy-on-write strings.
+
+`main` clones `s` into `s2` just to append to it, which copies the whole heap buffer up front. That's the right
+call when the copy is always changed, but wasteful when most copies are only read.
+
+CowString defers the copy instead. Cloning a CowString clones an `Rc`, so both values point at the same buffer and
+no bytes are copied. The first mutation of a shared CowString copies the buffer (`Rc::make_mut`), and after that
+the value owns its buffer outright and mutates it in place like a plain String.
+
+Rope covers the other expensive pattern, long chains of concatenation. Each append just records a piece, borrowing
+it when possible (`Cow::Borrowed`), and the pieces are joined into a single allocation of exactly the right size
+when the final String is needed.
+
+*/
+
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CowString {
+    buffer: Rc<String>,
+}
+
+impl CowString {
+    pub fn new() -> CowString {
+        CowString::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.buffer
+    }
+
+    // is_shared reports whether another CowString currently points at the same buffer, i.e. whether the next
+    // mutation will have to copy it.
+    pub fn is_shared(&self) -> bool {
+        Rc::strong_count(&self.buffer) > 1
+    }
+
+    // make_mut gives mutable access to an unshared buffer, copying it first if it is shared.
+    fn make_mut(&mut self) -> &mut String {
+        Rc::make_mut(&mut self.buffer)
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.make_mut().push_str(s);
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.make_mut().push(c);
+    }
+
+    pub fn clear(&mut self) {
+        // A shared buffer doesn't need copying just to be emptied.
+        if self.is_shared() {
+            *self = CowString::new();
+        } else {
+            self.make_mut().clear();
+        }
+    }
+
+    // into_string returns the buffer, only copying it when it is still shared.
+    pub fn into_string(self) -> String {
+        Rc::try_unwrap(self.buffer).unwrap_or_else(|shared| (*shared).clone())
+    }
+}
+
+impl From<String> for CowString {
+    fn from(s: String) -> CowString {
+        CowString { buffer: Rc::new(s) }
+    }
+}
+
+impl From<&str> for CowString {
+    fn from(s: &str) -> CowString {
+        CowString::from(String::from(s))
+    }
+}
+
+impl Deref for CowString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Display for CowString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Rope collects string pieces without joining them. Borrowed pieces cost nothing to append; owned pieces are moved
+// in, never copied.
+#[derive(Debug, Clone, Default)]
+pub struct Rope<'a> {
+    pieces: Vec<Cow<'a, str>>,
+    len: usize,
+}
+
+impl<'a> Rope<'a> {
+    pub fn new() -> Rope<'a> {
+        Rope::default()
+    }
+
+    // append adds a piece to the end, e.g. `rope.append("literal")` or `rope.append(format!("{}", n))`.
+    pub fn append<S>(&mut self, piece: S) -> &mut Rope<'a>
+    where
+        S: Into<Cow<'a, str>>,
+    {
+        let piece = piece.into();
+        if !piece.is_empty() {
+            self.len += piece.len();
+            self.pieces.push(piece);
+        }
+        self
+    }
+
+    // len is the length in bytes of the joined string.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn pieces(&self) -> impl Iterator<Item = &str> {
+        self.pieces.iter().map(|piece| piece.as_ref())
+    }
+}
+
+// Joining the pieces allocates once, with the exact capacity needed.
+impl<'a> From<Rope<'a>> for String {
+    fn from(rope: Rope<'a>) -> String {
+        let mut joined = String::with_capacity(rope.len);
+        for piece in &rope.pieces {
+            joined.push_str(piece);
+        }
+        joined
+    }
+}
+
+impl<'a> From<Rope<'a>> for CowString {
+    fn from(rope: Rope<'a>) -> CowString {
+        CowString::from(String::from(rope))
+    }
+}
+
+impl<'a> fmt::Display for Rope<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for piece in &self.pieces {
+            f.write_str(piece)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_the_buffer_until_changed() {
+        let original = CowString::from("Hello");
+        let copy = original.clone();
+        assert!(original.is_shared() && copy.is_shared());
+        assert!(Rc::ptr_eq(&original.buffer, &copy.buffer));
+
+        // Reading a copy doesn't copy anything.
+        assert_eq!(copy.len(), 5);
+        assert_eq!(copy.to_uppercase(), "HELLO");
+        assert!(Rc::ptr_eq(&original.buffer, &copy.buffer));
+
+        let mut changed = copy.clone();
+        changed.push_str(", world");
+        assert!(!Rc::ptr_eq(&original.buffer, &changed.buffer));
+        assert!(!changed.is_shared());
+        assert_eq!(changed, CowString::from("Hello, world"));
+        assert_eq!(original.as_str(), "Hello");
+        assert_eq!(copy.as_str(), "Hello");
+
+        // Once it owns its buffer, a CowString changes it in place.
+        let buffer = Rc::as_ptr(&changed.buffer);
+        changed.push('!');
+        assert_eq!(Rc::as_ptr(&changed.buffer), buffer);
+        assert_eq!(changed.to_string(), "Hello, world!");
+    }
+
+    #[test]
+    fn clearing_and_unwrapping() {
+        let original = CowString::from("shared");
+        let mut copy = original.clone();
+        copy.clear();
+        assert_eq!(copy.as_str(), "");
+        assert_eq!(original.as_str(), "shared");
+        assert!(!original.is_shared());
+
+        let mut alone = CowString::from(String::with_capacity(64));
+        alone.push_str("kept");
+        alone.clear();
+        assert_eq!(alone.buffer.capacity(), 64);
+
+        let copy = original.clone();
+        assert_eq!(copy.into_string(), "shared");
+        assert_eq!(original.into_string(), "shared");
+        assert_eq!(CowString::new().into_string(), "");
+    }
+
+    #[test]
+    fn rope_borrows_what_it_can() {
+        let name = String::from("friends");
+        let mut rope = Rope::new();
+        rope.append("Aloha ").append(name.as_str()).append(format!("{}", '!')).append("");
+        assert!(matches!(rope.pieces[0], Cow::Borrowed("Aloha ")));
+        assert!(matches!(rope.pieces[1], Cow::Borrowed("friends")));
+        assert!(matches!(rope.pieces[2], Cow::Owned(ref piece) if piece == "!"));
+        // Empty pieces aren't kept.
+        assert_eq!(rope.pieces().collect::<Vec<_>>(), ["Aloha ", "friends", "!"]);
+        assert_eq!(rope.len(), 14);
+        assert!(!rope.is_empty());
+        assert_eq!(rope.to_string(), "Aloha friends!");
+
+        let joined = String::from(rope);
+        assert_eq!(joined, "Aloha friends!");
+        assert_eq!(joined.capacity(), joined.len());
+
+        let empty = Rope::new();
+        assert!(empty.is_empty());
+        assert_eq!(CowString::from(empty), CowString::new());
+    }
+}
@@ -0,0 +1,136 @@
+/*
+
+Instrumented values for watching ownership happen.
+
+A Tracked value records an Event when it is created, cloned and dropped. Moves are invisible at runtime (a move is
+just a memcpy of the value the compiler then forgets about), so functions record them by passing the value
+through `moved_into` and `returned_from`. Anything else worth showing goes on the timeline with `note`. Events
+are appended to a thread-local timeline that `take_events` drains.
+
+Because Rust won't let a type implement both `Copy` and `Drop`, Tracked can't be `Copy`. Copies of plain
+integers are recorded by calling `copied_into` by hand instead.
+
+*/
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Created { id: usize, label: &'static str },
+    Cloned { from: usize, to: usize },
+    MovedInto { id: usize, function: &'static str },
+    ReturnedFrom { id: usize, function: &'static str },
+    Copied { value: i64, function: &'static str },
+    Dropped { id: usize },
+    // Note is anything else a scenario wants on its timeline, such as a function saying what it received.
+    Note(String),
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Event::Created { id, label } => write!(f, "#{} created ({})", id, label),
+            Event::Cloned { from, to } => write!(f, "#{} cloned into #{}", from, to),
+            Event::MovedInto { id, function } => write!(f, "#{} moved into {}()", id, function),
+            Event::ReturnedFrom { id, function } => write!(f, "#{} returned from {}()", id, function),
+            Event::Copied { value, function } => write!(f, "{} copied into {}()", value, function),
+            Event::Dropped { id } => write!(f, "#{} dropped", id),
+            Event::Note(text) => f.write_str(text),
+        }
+    }
+}
+
+thread_local! {
+    static NEXT_ID: Cell<usize> = const { Cell::new(1) };
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(event: Event) {
+    EVENTS.with(|events| events.borrow_mut().push(event));
+}
+
+fn next_id() -> usize {
+    NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    })
+}
+
+// take_events drains the timeline recorded so far on this thread and resets ids to start again at 1.
+pub fn take_events() -> Vec<Event> {
+    NEXT_ID.with(|next| next.set(1));
+    EVENTS.with(|events| events.borrow_mut().split_off(0))
+}
+
+// drop_order picks the ids out of the Dropped events, in the order they were dropped.
+pub fn drop_order(events: &[Event]) -> Vec<usize> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Dropped { id } => Some(*id),
+            _ => None,
+        })
+        .collect()
+}
+
+// copied_into records a Copy value being passed to a function.
+pub fn copied_into(value: i64, function: &'static str) {
+    record(Event::Copied { value, function });
+}
+
+// note records a line of commentary on the timeline, in order with the other events.
+pub fn note<S: Into<String>>(text: S) {
+    record(Event::Note(text.into()));
+}
+
+// Tracked stands in for a heap-owning value like String. Each instance, including each clone, gets a unique id.
+#[derive(Debug)]
+pub struct Tracked {
+    id: usize,
+    label: &'static str,
+}
+
+impl Tracked {
+    pub fn new(label: &'static str) -> Tracked {
+        let id = next_id();
+        record(Event::Created { id, label });
+        Tracked { id, label }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    // moved_into records that ownership of this value passed into `function`. Call it on the parameter as the first
+    // thing the function does.
+    pub fn moved_into(self, function: &'static str) -> Tracked {
+        record(Event::MovedInto { id: self.id, function });
+        self
+    }
+
+    // returned_from records that ownership of this value passed back out of `function` to its caller.
+    pub fn returned_from(self, function: &'static str) -> Tracked {
+        record(Event::ReturnedFrom { id: self.id, function });
+        self
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Tracked {
+        let id = next_id();
+        record(Event::Cloned { from: self.id, to: id });
+        Tracked { id, label: self.label }
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        record(Event::Dropped { id: self.id });
+    }
+}
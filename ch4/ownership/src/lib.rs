@@ -0,0 +1,4 @@
+pub mod allocations;
+pub mod cow;
+pub mod events;
+pub mod scenarios;
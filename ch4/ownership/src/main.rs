@@ -95,6 +95,7 @@ When a variable goes "out of scope", Rust calls the `drop` method on it, and com
 
 */
 
+use ownership::cow::CowString;
 
 fn main() {
     let mut s = String::from("hello");
@@ -135,4 +136,17 @@ fn main() {
     s2.push_str(" Aloha friends!");
 
     println!("The value of s2 is: {}", s2);
+
+    /*
+        CowString (see `src/cow.rs`) defers that copy: cloning shares the heap buffer, and the buffer is only copied
+        the first time one of the sharing values is changed.
+    */
+
+    let s3 = CowString::from(s.as_str());
+    let mut s4 = s3.clone();
+    println!("Does s4 share its buffer with s3? {}", s4.is_shared());
+
+    s4.push_str(" Aloha friends!");
+    println!("After push_str, does s4 still share its buffer? {}", s4.is_shared());
+    println!("The value of s4 is: {}", s4);
 }
@@ -0,0 +1,177 @@
+/*
+
+The examples from the comments in `main.rs`, rebuilt with Tracked values standing in for Strings.
+
+Each scenario runs to completion (so every value has gone out of scope) and returns the timeline it recorded.
+
+*/
+
+use crate::events::{copied_into, note, take_events, Event, Tracked};
+
+// A Scenario runs one example and returns the events it recorded.
+pub type Scenario = fn() -> Vec<Event>;
+
+fn takes_ownership(some_string: Tracked) {
+    let some_string = some_string.moved_into("takes_ownership");
+    note(format!("takes_ownership got #{}", some_string.id()));
+} // some_string goes out of scope and is dropped here.
+
+fn makes_copy(some_integer: i64) {
+    copied_into(some_integer, "makes_copy");
+    note(format!("makes_copy got {}", some_integer));
+} // some_integer goes out of scope, but it's Copy so nothing is dropped.
+
+fn gives_ownership() -> Tracked {
+    let some_string = Tracked::new("some_string");
+    some_string.returned_from("gives_ownership")
+}
+
+fn takes_and_gives_back(a_string: Tracked) -> Tracked {
+    let a_string = a_string.moved_into("takes_and_gives_back");
+    a_string.returned_from("takes_and_gives_back")
+}
+
+// takes_ownership_and_makes_copy: s is dropped inside takes_ownership, long before main's scope ends, while x is
+// copied and stays usable.
+pub fn takes_ownership_and_makes_copy() -> Vec<Event> {
+    take_events();
+    {
+        let s = Tracked::new("s");
+        takes_ownership(s);
+
+        let x = 5;
+        makes_copy(x);
+        note(format!("x is still usable: {}", x));
+    }
+    take_events()
+}
+
+// giving_and_taking_back: values that are moved back out of functions live on in the caller, and are dropped in
+// reverse order of declaration when the caller's scope ends. s2 was moved into s3, so it is never dropped itself.
+pub fn giving_and_taking_back() -> Vec<Event> {
+    take_events();
+    {
+        let _s1 = gives_ownership();
+        let s2 = Tracked::new("s2");
+        let _s3 = takes_and_gives_back(s2);
+    }
+    take_events()
+}
+
+// clone_then_drop: a clone is a separate value with its own id and its own drop.
+pub fn clone_then_drop() -> Vec<Event> {
+    take_events();
+    {
+        let s = Tracked::new("s");
+        let s2 = s.clone();
+        drop(s);
+        note(format!("s2 (#{}) outlives s", s2.id()));
+    }
+    take_events()
+}
+
+// scenarios lists every scenario by name, in the order the comments in `main.rs` introduce them.
+pub fn scenarios() -> Vec<(&'static str, Scenario)> {
+    vec![
+        ("takes_ownership and makes_copy", takes_ownership_and_makes_copy as Scenario),
+        ("gives_ownership and takes_and_gives_back", giving_and_taking_back),
+        ("clone then drop", clone_then_drop),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::drop_order;
+
+    #[test]
+    fn takes_ownership_drops_inside_the_function() {
+        let events = takes_ownership_and_makes_copy();
+        assert_eq!(
+            events,
+            [
+                Event::Created { id: 1, label: "s" },
+                Event::MovedInto {
+                    id: 1,
+                    function: "takes_ownership"
+                },
+                Event::Note(String::from("takes_ownership got #1")),
+                Event::Dropped { id: 1 },
+                Event::Copied {
+                    value: 5,
+                    function: "makes_copy"
+                },
+                Event::Note(String::from("makes_copy got 5")),
+                Event::Note(String::from("x is still usable: 5")),
+            ]
+        );
+        assert_eq!(drop_order(&events), [1]);
+    }
+
+    #[test]
+    fn returned_values_drop_in_reverse_order_of_declaration() {
+        let events = giving_and_taking_back();
+        assert_eq!(
+            events,
+            [
+                Event::Created {
+                    id: 1,
+                    label: "some_string"
+                },
+                Event::ReturnedFrom {
+                    id: 1,
+                    function: "gives_ownership"
+                },
+                Event::Created { id: 2, label: "s2" },
+                Event::MovedInto {
+                    id: 2,
+                    function: "takes_and_gives_back"
+                },
+                Event::ReturnedFrom {
+                    id: 2,
+                    function: "takes_and_gives_back"
+                },
+                // _s3 holds #2 and was declared after _s1, so it is dropped first.
+                Event::Dropped { id: 2 },
+                Event::Dropped { id: 1 },
+            ]
+        );
+        assert_eq!(drop_order(&events), [2, 1]);
+    }
+
+    #[test]
+    fn a_clone_is_dropped_separately() {
+        let events = clone_then_drop();
+        assert_eq!(
+            events,
+            [
+                Event::Created { id: 1, label: "s" },
+                Event::Cloned { from: 1, to: 2 },
+                Event::Dropped { id: 1 },
+                Event::Note(String::from("s2 (#2) outlives s")),
+                Event::Dropped { id: 2 },
+            ]
+        );
+        assert_eq!(drop_order(&events), [1, 2]);
+    }
+
+    #[test]
+    fn scenarios_leave_nothing_behind() {
+        for (name, scenario) in scenarios() {
+            let events = scenario();
+            // Everything created or cloned is dropped exactly once by the time a scenario returns.
+            let mut created: Vec<usize> = events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Created { id, .. } | Event::Cloned { to: id, .. } => Some(*id),
+                    _ => None,
+                })
+                .collect();
+            let mut dropped = drop_order(&events);
+            created.sort_unstable();
+            dropped.sort_unstable();
+            assert_eq!(created, dropped, "{}", name);
+            assert!(take_events().is_empty(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+/*
+
+ed [FILE]
+
+Runs the line editor from `references_and_borrowing::ed`, reading commands from stdin. Scripts can be piped in:
+
+printf '2d\n,s/foo/bar/g\nw\nq\n' | ed notes.txt
+
+*/
+
+use std::env;
+use std::io;
+use std::process;
+
+use references_and_borrowing::ed::Editor;
+
+fn main() {
+    let editor = match env::args().nth(1) {
+        Some(path) => Editor::open(&path).unwrap_or_else(|e| {
+            eprintln!("ed: {}: {}", path, e);
+            process::exit(1);
+        }),
+        None => Editor::new(),
+    };
+
+    let mut editor = editor;
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    if let Err(e) = editor.run(stdin.lock(), stdout.lock()) {
+        eprintln!("ed: {}", e);
+        process::exit(1);
+    }
+}
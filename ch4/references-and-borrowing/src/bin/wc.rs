@@ -0,0 +1,88 @@
+/*
+
+A `wc`-like tool built on `references_and_borrowing::stats`.
+
+wc [--top N] FILE...
+
+Prints lines, words, chars, graphemes, bytes and max display width for each file, then a total when more than one
+file is given. With `--top N` it also lists the N most common chars of each file.
+
+A single String buffer is reused for every file: `read_to_string` appends into it through a `&mut String`, and
+the statistics only ever borrow it as `&str`, so no file contents are cloned.
+
+*/
+
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::process;
+
+use references_and_borrowing::stats::{most_common_chars, Stats};
+
+fn print_row(stats: &Stats, name: &str) {
+    println!(
+        "{:>8} {:>8} {:>8} {:>9} {:>8} {:>6} {}",
+        stats.lines, stats.words, stats.chars, stats.graphemes, stats.bytes, stats.width, name
+    );
+}
+
+// read_into replaces the contents of `buffer` with the file at `path`, reusing its allocation.
+fn read_into(path: &str, buffer: &mut String) -> io::Result<()> {
+    buffer.clear();
+    File::open(path)?.read_to_string(buffer)?;
+    Ok(())
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    let mut top = 0;
+    if let Some(i) = args.iter().position(|arg| arg == "--top") {
+        top = match args.get(i + 1).and_then(|n| n.parse().ok()) {
+            Some(n) => n,
+            None => {
+                eprintln!("wc: --top needs a number");
+                process::exit(2);
+            }
+        };
+        args.drain(i..i + 2);
+    }
+
+    if args.is_empty() {
+        eprintln!("usage: wc [--top N] FILE...");
+        process::exit(2);
+    }
+
+    println!(
+        "{:>8} {:>8} {:>8} {:>9} {:>8} {:>6}",
+        "lines", "words", "chars", "graphemes", "bytes", "width"
+    );
+
+    let mut buffer = String::new();
+    let mut total = Stats::default();
+    let mut failed = false;
+
+    for path in &args {
+        if let Err(e) = read_into(path, &mut buffer) {
+            eprintln!("wc: {}: {}", path, e);
+            failed = true;
+            continue;
+        }
+
+        let stats = Stats::of(&buffer);
+        print_row(&stats, path);
+        total.add(&stats);
+
+        for (c, count) in most_common_chars(&buffer, top) {
+            println!("{:>8} {:?}", count, c);
+        }
+    }
+
+    if args.len() > 1 {
+        print_row(&total, "total");
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,333 @@
+/*
+
+A small `ed`-style line editor.
+
+The buffer is a `Vec<String>`, one String per line. Commands that change text borrow a single line at a time with
+`&mut self.lines[i]` and hand that borrow to the functions in `edit`, so only the lines being changed are touched.
+
+Supported commands, where a range is `N`, `N,M`, `.` (the current line), `$` (the last line) or `,` (every line):
+
+[range]p            print lines
+[range]n            print lines with line numbers
+[line]a             append text after the line, ending with a line containing only `.`
+[line]i             insert text before the line, ending with a line containing only `.`
+[range]d            delete lines
+[range]s/old/new/   substitute the first `old` on each line, or every `old` with a trailing `g`
+w [file]            write the buffer to a file, which then becomes the default file
+q                   quit
+
+Line numbers start at 1, as in ed. Commands are read from any BufRead, so a script file works the same way as a
+terminal session.
+
+*/
+
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::edit;
+
+#[derive(Debug)]
+pub enum EdError {
+    InvalidAddress(String),
+    InvalidCommand(String),
+    NoFileName,
+    NoMatch,
+    // The file couldn't be written. The buffer is still there, so it can be written somewhere else.
+    Write(PathBuf, io::Error),
+    Io(io::Error),
+}
+
+impl fmt::Display for EdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EdError::InvalidAddress(a) => write!(f, "invalid address `{}`", a),
+            EdError::InvalidCommand(c) => write!(f, "invalid command `{}`", c),
+            EdError::NoFileName => write!(f, "no file name"),
+            EdError::NoMatch => write!(f, "no match"),
+            EdError::Write(path, e) => write!(f, "can't write {}: {}", path.display(), e),
+            EdError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<io::Error> for EdError {
+    fn from(e: io::Error) -> EdError {
+        EdError::Io(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Print { from: usize, to: usize, numbered: bool },
+    Append(usize),
+    Insert(usize),
+    Delete { from: usize, to: usize },
+    Substitute { from: usize, to: usize, old: String, new: String, global: bool },
+    Write(Option<PathBuf>),
+    Quit,
+}
+
+pub struct Editor {
+    lines: Vec<String>,
+    // The current line, starting at 1. It is 0 only when the buffer is empty.
+    current: usize,
+    path: Option<PathBuf>,
+}
+
+impl Editor {
+    pub fn new() -> Editor {
+        Editor {
+            lines: Vec::new(),
+            current: 0,
+            path: None,
+        }
+    }
+
+    // open loads a file into the buffer. A file that doesn't exist yet starts out empty, like `ed newfile`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Editor> {
+        let path = path.as_ref();
+        let lines: Vec<String> = match fs::read_to_string(path) {
+            Ok(contents) => contents.lines().map(String::from).collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Editor {
+            current: lines.len(),
+            lines,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    // parse_line parses a single address into a line number, where 0 means "before the first line".
+    fn parse_line(&self, s: &str) -> Result<usize, EdError> {
+        let line = match s {
+            "." => self.current,
+            "$" => self.lines.len(),
+            _ => s.parse().map_err(|_| EdError::InvalidAddress(s.to_string()))?,
+        };
+
+        if line > self.lines.len() {
+            return Err(EdError::InvalidAddress(s.to_string()));
+        }
+        Ok(line)
+    }
+
+    // parse_address parses the single line address of `a` and `i`, defaulting to the current line.
+    fn parse_address(&self, s: &str) -> Result<usize, EdError> {
+        self.parse_line(if s.is_empty() { "." } else { s })
+    }
+
+    // parse_range parses the address part of a command, defaulting to the current line.
+    fn parse_range(&self, s: &str) -> Result<(usize, usize), EdError> {
+        let (from, to) = match s {
+            "" => (self.current, self.current),
+            "," => (1.min(self.lines.len()), self.lines.len()),
+            _ => match s.find(',') {
+                Some(i) => (self.parse_line(&s[..i])?, self.parse_line(&s[i + 1..])?),
+                None => {
+                    let line = self.parse_line(s)?;
+                    (line, line)
+                }
+            },
+        };
+
+        if from > to {
+            return Err(EdError::InvalidAddress(s.to_string()));
+        }
+        Ok((from, to))
+    }
+
+    // parse_lines is parse_range for commands that need at least one existing line.
+    fn parse_lines(&self, s: &str) -> Result<(usize, usize), EdError> {
+        let (from, to) = self.parse_range(s)?;
+        if from == 0 {
+            return Err(EdError::InvalidAddress(s.to_string()));
+        }
+        Ok((from, to))
+    }
+
+    pub fn parse(&self, line: &str) -> Result<Command, EdError> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let split = line
+            .find(|c: char| c.is_ascii_alphabetic())
+            .ok_or_else(|| EdError::InvalidCommand(line.to_string()))?;
+        let (address, rest) = line.split_at(split);
+        let name = rest.as_bytes()[0];
+        let argument = &rest[1..];
+
+        let command = match name {
+            b'p' | b'n' if argument.is_empty() => {
+                let (from, to) = self.parse_lines(address)?;
+                Command::Print { from, to, numbered: name == b'n' }
+            }
+            b'a' if argument.is_empty() => Command::Append(self.parse_address(address)?),
+            // Inserting before line 0 (an empty buffer) is the same as inserting before line 1.
+            b'i' if argument.is_empty() => Command::Insert(self.parse_address(address)?.max(1)),
+            b'd' if argument.is_empty() => {
+                let (from, to) = self.parse_lines(address)?;
+                Command::Delete { from, to }
+            }
+            b's' => {
+                let (from, to) = self.parse_lines(address)?;
+                let (old, new, global) = parse_substitution(argument)
+                    .ok_or_else(|| EdError::InvalidCommand(line.to_string()))?;
+                Command::Substitute { from, to, old, new, global }
+            }
+            b'w' if address.is_empty() => {
+                let path = argument.trim();
+                Command::Write(if path.is_empty() { None } else { Some(PathBuf::from(path)) })
+            }
+            b'q' if address.is_empty() && argument.is_empty() => Command::Quit,
+            _ => return Err(EdError::InvalidCommand(line.to_string())),
+        };
+
+        Ok(command)
+    }
+
+    // read_text reads lines of input up to a line containing only `.`, for the append and insert commands.
+    fn read_text<R: BufRead>(input: &mut R) -> io::Result<Vec<String>> {
+        let mut text = Vec::new();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(text);
+            }
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            if trimmed == "." {
+                return Ok(text);
+            }
+            text.push(trimmed.to_string());
+        }
+    }
+
+    // execute runs one command, returning false when the editor should quit.
+    pub fn execute<R, W>(&mut self, command: Command, input: &mut R, out: &mut W) -> Result<bool, EdError>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        match command {
+            Command::Print { from, to, numbered } => {
+                for (i, line) in self.lines[from - 1..to].iter().enumerate() {
+                    if numbered {
+                        write!(out, "{}\t", from + i)?;
+                    }
+                    writeln!(out, "{}", line)?;
+                }
+                self.current = to;
+            }
+            Command::Append(after) => {
+                let text = Editor::read_text(input)?;
+                self.current = after + text.len();
+                self.lines.splice(after..after, text);
+            }
+            Command::Insert(before) => {
+                let text = Editor::read_text(input)?;
+                self.current = before - 1 + text.len();
+                self.lines.splice(before - 1..before - 1, text);
+            }
+            Command::Delete { from, to } => {
+                self.lines.drain(from - 1..to);
+                self.current = from.min(self.lines.len());
+            }
+            Command::Substitute { from, to, old, new, global } => {
+                let mut last_changed = None;
+                for (i, line) in self.lines[from - 1..to].iter_mut().enumerate() {
+                    // `line` is a `&mut String` borrowing just this one line of the buffer. parse_substitution never
+                    // returns an empty pattern, so the edit functions can't fail here.
+                    let changed = if global {
+                        !edit::replace_all(line, &old, &new).unwrap_or_default().is_empty()
+                    } else {
+                        edit::replace_first(line, &old, &new).unwrap_or_default().is_some()
+                    };
+                    if changed {
+                        last_changed = Some(from + i);
+                    }
+                }
+                self.current = last_changed.ok_or(EdError::NoMatch)?;
+            }
+            Command::Write(path) => {
+                // A new file name only becomes the default once something has been written to it.
+                let path = path.or_else(|| self.path.clone()).ok_or(EdError::NoFileName)?;
+                let bytes = self.write(&path).map_err(|e| EdError::Write(path.clone(), e))?;
+                writeln!(out, "{}", bytes)?;
+                self.path = Some(path);
+            }
+            Command::Quit => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    // write saves the buffer, returning the number of bytes written like ed does.
+    fn write(&self, path: &Path) -> io::Result<usize> {
+        let mut contents = String::new();
+        for line in &self.lines {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        fs::write(path, &contents)?;
+        Ok(contents.len())
+    }
+
+    // run reads and executes commands until `q` or the end of input. Errors are reported as `? message` and
+    // editing carries on, so a script keeps going past a bad command the way an interactive session would. That
+    // includes failing to write the file. Only failing to read commands or print output ends the session.
+    pub fn run<R, W>(&mut self, mut input: R, mut out: W) -> io::Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+
+            let result = self
+                .parse(&line)
+                .and_then(|command| self.execute(command, &mut input, &mut out));
+
+            match result {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(EdError::Io(e)) => return Err(e),
+                Err(e) => writeln!(out, "? {}", e)?,
+            }
+        }
+    }
+}
+
+impl Default for Editor {
+    fn default() -> Editor {
+        Editor::new()
+    }
+}
+
+// parse_substitution splits `/old/new/` or `/old/new/g` into its parts. Any char may stand in for `/`.
+fn parse_substitution(s: &str) -> Option<(String, String, bool)> {
+    let delimiter = s.chars().next()?;
+    let mut parts = s[delimiter.len_utf8()..].split(delimiter);
+
+    let old = parts.next()?;
+    let new = parts.next()?;
+    let global = match parts.next() {
+        None | Some("") => false,
+        Some("g") => true,
+        Some(_) => return None,
+    };
+
+    if old.is_empty() || parts.next().is_some() {
+        return None;
+    }
+    Some((old.to_string(), new.to_string(), global))
+}
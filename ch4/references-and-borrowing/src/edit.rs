@@ -0,0 +1,369 @@
+/*
+
+In-place text editing through `&mut String`.
+
+Every function here borrows the buffer mutably for the length of the call and changes it in place, the same way
+`change(&mut String)` calls `push_str`. Nothing is returned by moving the buffer in and out; the caller keeps
+ownership the whole time.
+
+Positions are counted in chars rather than bytes, so callers can't accidentally cut a multi-byte char in half.
+
+Each change is described by an Edit recording only the text that was removed and inserted at one offset, which is
+enough to reverse it. History keeps a stack of those edits for undo and redo without ever cloning the whole buffer.
+
+*/
+
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    // Byte offset into the buffer, as it was just before this edit was applied.
+    pub at: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Edit {
+    // undo reverses this edit. It must be the most recent edit applied to `s`.
+    pub fn undo(&self, s: &mut String) {
+        s.replace_range(self.at..self.at + self.inserted.len(), &self.removed);
+    }
+
+    // redo applies this edit again after it was undone.
+    pub fn redo(&self, s: &mut String) {
+        s.replace_range(self.at..self.at + self.removed.len(), &self.inserted);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EditError {
+    OutOfBounds { position: usize, len: usize },
+    EmptyPattern,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::OutOfBounds { position, len } => {
+                write!(f, "position {} is past the end of a {} char string", position, len)
+            }
+            EditError::EmptyPattern => write!(f, "the pattern to replace must not be empty"),
+        }
+    }
+}
+
+// byte_offset converts a char position into a byte offset. The position just past the last char is allowed.
+fn byte_offset(s: &str, position: usize) -> Result<usize, EditError> {
+    s.char_indices()
+        .map(|(i, _)| i)
+        .chain(Some(s.len()))
+        .nth(position)
+        .ok_or_else(|| EditError::OutOfBounds {
+            position,
+            len: s.chars().count(),
+        })
+}
+
+// splice replaces a byte range and records what changed.
+fn splice(s: &mut String, range: Range<usize>, text: &str) -> Edit {
+    let removed = s[range.clone()].to_string();
+    s.replace_range(range.clone(), text);
+    Edit {
+        at: range.start,
+        removed,
+        inserted: text.to_string(),
+    }
+}
+
+// replace replaces the chars in `range` with `text`.
+pub fn replace(s: &mut String, range: Range<usize>, text: &str) -> Result<Edit, EditError> {
+    let start = byte_offset(s, range.start)?;
+    let end = byte_offset(s, range.end.max(range.start))?;
+    Ok(splice(s, start..end, text))
+}
+
+// insert inserts `text` before the char at `position`.
+pub fn insert(s: &mut String, position: usize, text: &str) -> Result<Edit, EditError> {
+    replace(s, position..position, text)
+}
+
+// delete removes the chars in `range`.
+pub fn delete(s: &mut String, range: Range<usize>) -> Result<Edit, EditError> {
+    replace(s, range, "")
+}
+
+// trim removes leading and trailing whitespace. The trailing edit comes first so the leading edit's offset of 0 is
+// unaffected by it.
+pub fn trim(s: &mut String) -> Vec<Edit> {
+    let mut edits = Vec::new();
+
+    let end = s.trim_end().len();
+    if end < s.len() {
+        edits.push(splice(s, end..s.len(), ""));
+    }
+
+    let start = s.len() - s.trim_start().len();
+    if start > 0 {
+        edits.push(splice(s, 0..start, ""));
+    }
+
+    edits
+}
+
+// convert replaces `s` with `converted`, recording only the span between their common prefix and suffix.
+fn convert(s: &mut String, converted: &str) -> Option<Edit> {
+    if s == converted {
+        return None;
+    }
+
+    let prefix: usize = s
+        .chars()
+        .zip(converted.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+    let suffix: usize = s[prefix..]
+        .chars()
+        .rev()
+        .zip(converted[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum();
+
+    let inserted = &converted[prefix..converted.len() - suffix];
+    let end = s.len() - suffix;
+    Some(splice(s, prefix..end, inserted))
+}
+
+// to_uppercase upper cases `s` in place, returning None when nothing changed.
+pub fn to_uppercase(s: &mut String) -> Option<Edit> {
+    let converted = s.to_uppercase();
+    convert(s, &converted)
+}
+
+// to_lowercase lower cases `s` in place, returning None when nothing changed.
+pub fn to_lowercase(s: &mut String) -> Option<Edit> {
+    let converted = s.to_lowercase();
+    convert(s, &converted)
+}
+
+// replace_first replaces the first occurrence of `from` with `to`, returning None when there is no occurrence.
+pub fn replace_first(s: &mut String, from: &str, to: &str) -> Result<Option<Edit>, EditError> {
+    if from.is_empty() {
+        return Err(EditError::EmptyPattern);
+    }
+
+    Ok(s.find(from).map(|start| splice(s, start..start + from.len(), to)))
+}
+
+// replace_all replaces every non-overlapping occurrence of `from` with `to`, left to right.
+pub fn replace_all(s: &mut String, from: &str, to: &str) -> Result<Vec<Edit>, EditError> {
+    if from.is_empty() {
+        return Err(EditError::EmptyPattern);
+    }
+
+    let mut edits = Vec::new();
+    let mut search_from = 0;
+    while let Some(found) = s[search_from..].find(from) {
+        let start = search_from + found;
+        edits.push(splice(s, start..start + from.len(), to));
+        // Skip past the replacement so it is never matched again.
+        search_from = start + to.len();
+    }
+
+    Ok(edits)
+}
+
+// History is an undo/redo stack of edit groups. A group is every edit made by one operation, e.g. all the edits
+// from a single `replace_all`, so that one undo reverses the whole operation.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    // record pushes a group of edits that have already been applied. Recording new edits clears the redo stack.
+    pub fn record<I>(&mut self, edits: I)
+    where
+        I: IntoIterator<Item = Edit>,
+    {
+        let group: Vec<Edit> = edits.into_iter().collect();
+        if !group.is_empty() {
+            self.undo.push(group);
+            self.redo.clear();
+        }
+    }
+
+    // undo reverses the most recent group of edits, returning false when there is nothing to undo.
+    pub fn undo(&mut self, s: &mut String) -> bool {
+        match self.undo.pop() {
+            Some(group) => {
+                for edit in group.iter().rev() {
+                    edit.undo(s);
+                }
+                self.redo.push(group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // redo re-applies the most recently undone group of edits, returning false when there is nothing to redo.
+    pub fn redo(&mut self, s: &mut String) -> bool {
+        match self.redo.pop() {
+            Some(group) => {
+                for edit in &group {
+                    edit.redo(s);
+                }
+                self.undo.push(group);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_inserts_and_deletes() {
+        let mut s = String::from("hello world");
+        let mut history = History::new();
+        assert!(!history.can_undo() && !history.can_redo());
+        assert!(!history.undo(&mut s));
+        assert!(!history.redo(&mut s));
+
+        history.record(insert(&mut s, 5, ", big").ok());
+        assert_eq!(s, "hello, big world");
+        history.record(delete(&mut s, 0..7).ok());
+        assert_eq!(s, "big world");
+        history.record(insert(&mut s, 9, "!").ok());
+        assert_eq!(s, "big world!");
+
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "big world");
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "hello, big world");
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "hello world");
+        assert!(!history.undo(&mut s));
+        assert!(history.can_redo());
+
+        assert!(history.redo(&mut s));
+        assert_eq!(s, "hello, big world");
+        assert!(history.redo(&mut s));
+        assert_eq!(s, "big world");
+        assert!(history.redo(&mut s));
+        assert_eq!(s, "big world!");
+        assert!(!history.redo(&mut s));
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut s = String::from("abc");
+        let mut history = History::new();
+        history.record(insert(&mut s, 3, "d").ok());
+        history.record(insert(&mut s, 4, "e").ok());
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "abcd");
+
+        history.record(delete(&mut s, 0..1).ok());
+        assert_eq!(s, "bcd");
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut s));
+
+        // Recording a group with no edits in it changes nothing, so redo survives it.
+        assert!(history.undo(&mut s));
+        history.record(Vec::new());
+        assert!(history.redo(&mut s));
+        assert_eq!(s, "bcd");
+    }
+
+    #[test]
+    fn one_undo_reverses_a_whole_group() {
+        let mut s = String::from("  banana  ");
+        let mut history = History::new();
+        history.record(trim(&mut s));
+        assert_eq!(s, "banana");
+        // Replacing "a" with "aa" must not find the inserted text again.
+        history.record(replace_all(&mut s, "a", "aa").unwrap());
+        assert_eq!(s, "baanaanaa");
+        history.record(to_uppercase(&mut s));
+        assert_eq!(s, "BAANAANAA");
+
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "baanaanaa");
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "banana");
+        assert!(history.undo(&mut s));
+        assert_eq!(s, "  banana  ");
+        while history.redo(&mut s) {}
+        assert_eq!(s, "BAANAANAA");
+    }
+
+    #[test]
+    fn positions_count_chars() {
+        let mut s = String::from("naïve café 日本");
+        let edit = insert(&mut s, 3, "-").unwrap();
+        assert_eq!(s, "naï-ve café 日本");
+        // ï takes two bytes, so the third char starts at byte 4.
+        assert_eq!(edit.at, 4);
+
+        delete(&mut s, 10..11).unwrap();
+        assert_eq!(s, "naï-ve caf 日本");
+        replace(&mut s, 11..13, "🦀").unwrap();
+        assert_eq!(s, "naï-ve caf 🦀");
+        // The position just past the last char is the end.
+        insert(&mut s, 12, "!").unwrap();
+        assert_eq!(s, "naï-ve caf 🦀!");
+
+        let mut s = String::from("straße");
+        let mut history = History::new();
+        history.record(to_uppercase(&mut s));
+        assert_eq!(s, "STRASSE");
+        history.record(delete(&mut s, 4..6).ok());
+        assert_eq!(s, "STRAE");
+        history.undo(&mut s);
+        history.undo(&mut s);
+        assert_eq!(s, "straße");
+        assert_eq!(to_lowercase(&mut s), None);
+    }
+
+    #[test]
+    fn out_of_range_positions() {
+        let mut s = String::from("日本語");
+        assert_eq!(insert(&mut s, 4, "x"), Err(EditError::OutOfBounds { position: 4, len: 3 }));
+        assert_eq!(delete(&mut s, 1..9), Err(EditError::OutOfBounds { position: 9, len: 3 }));
+        assert_eq!(replace(&mut s, 7..8, "x"), Err(EditError::OutOfBounds { position: 7, len: 3 }));
+        assert_eq!(s, "日本語");
+        assert_eq!(
+            EditError::OutOfBounds { position: 4, len: 3 }.to_string(),
+            "position 4 is past the end of a 3 char string"
+        );
+
+        // A range that ends before it starts is empty.
+        let (start, end) = (2, 1);
+        assert_eq!(replace(&mut s, start..end, "x").unwrap().removed, "");
+        assert_eq!(s, "日本x語");
+
+        assert_eq!(replace_first(&mut s, "", "x"), Err(EditError::EmptyPattern));
+        assert_eq!(replace_all(&mut s, "", "x"), Err(EditError::EmptyPattern));
+        assert_eq!(replace_first(&mut s, "y", "x"), Ok(None));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod ed;
+pub mod edit;
+pub mod stats;
@@ -116,14 +116,41 @@ This makes the compiler happy. Now `s` is created with `dangle()`, but instead o
 
 */
 
+use references_and_borrowing::edit::{self, History};
+use references_and_borrowing::stats::{display_width, most_common_chars, Stats};
+
 fn main() {
     let s1 = String::from("hello");
 
     let len = calculate_length(&s1); // The & indicates passing a reference in this function call.
 
     println!("The length of '{}' is {}.", s1, len);
+
+    // len() counts bytes, so it disagrees with the number of characters as soon as the text isn't ASCII.
+    // Stats borrows the string and reports each kind of length separately.
+    let s2 = String::from("cafe\u{301} \u{4E16}\u{754C}\nna\u{EF}ve");
+    println!("The length of '{}' is {}.", s2, calculate_length(&s2));
+    println!("Stats of '{}': {:?}", s2, Stats::of(&s2));
+    println!("Display width of '\u{4E16}\u{754C}' is {}.", display_width("\u{4E16}\u{754C}"));
+    println!("Most common chars: {:?}", most_common_chars(&s2, 3));
+
+    // Each edit borrows the String mutably only for the length of the call, so s3 stays usable in between.
+    let mut s3 = String::from("  hello world  ");
+    let mut history = History::new();
+
+    history.record(edit::trim(&mut s3));
+    history.record(edit::insert(&mut s3, 5, ",").ok());
+    history.record(edit::replace_all(&mut s3, "o", "0").unwrap_or_default());
+    history.record(edit::to_uppercase(&mut s3));
+    println!("After editing: {:?}", s3);
+
+    while history.undo(&mut s3) {
+        println!("Undo: {:?}", s3);
+    }
 }
 
+// The chapter's example borrows a &String on purpose, so clippy's advice to take &str doesn't apply here.
+#[allow(clippy::ptr_arg)]
 fn calculate_length(s: &String) -> usize {
     s.len()
 }
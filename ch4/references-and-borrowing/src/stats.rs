@@ -0,0 +1,255 @@
+/*
+
+String statistics over borrowed text.
+
+`calculate_length(&String)` returns `s.len()`, which is the number of bytes, not the number of characters. For
+"héllo" that is 6, not 5. Which count is "the length" depends on the question being asked, so Stats reports
+all of them:
+
+* bytes: how much memory the UTF-8 encoding takes
+* chars: how many Unicode scalar values there are
+* graphemes: how many user-perceived characters there are, i.e. extended grapheme clusters ("e" followed by a
+  combining accent is one, and so is a flag, an emoji sequence joined by zero width joiners, or a "\r\n")
+* width: how many terminal columns the widest line takes (wide CJK chars take two, combining marks none)
+
+Grapheme clusters come from the unicode-segmentation crate. Widths are an approximation: each cluster takes as
+many columns as its widest char, which is what most terminals do.
+
+Everything takes `&str`, so callers can pass a String, a string literal, or a slice of either without giving
+up ownership or making a copy.
+
+*/
+
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub bytes: usize,
+    pub chars: usize,
+    pub graphemes: usize,
+    pub width: usize,
+    pub lines: usize,
+    pub words: usize,
+}
+
+impl Stats {
+    // of computes every statistic in a single pass over the grapheme clusters of `s`.
+    pub fn of(s: &str) -> Stats {
+        let mut stats = Stats {
+            bytes: s.len(),
+            ..Stats::default()
+        };
+
+        let mut line_width = 0;
+        let mut in_word = false;
+
+        for cluster in s.graphemes(true) {
+            stats.graphemes += 1;
+            stats.chars += cluster.chars().count();
+
+            // A line break is always a cluster of its own, either "\n" or "\r\n".
+            if cluster.ends_with('\n') {
+                stats.lines += 1;
+                stats.width = stats.width.max(line_width);
+                line_width = 0;
+            } else {
+                line_width += cluster_width(cluster);
+            }
+
+            // A space carrying a combining mark is not whitespace any more, the same as in ch4/slice-type.
+            if cluster.chars().all(char::is_whitespace) {
+                in_word = false;
+            } else if !in_word {
+                in_word = true;
+                stats.words += 1;
+            }
+        }
+        stats.width = stats.width.max(line_width);
+
+        stats
+    }
+
+    // add accumulates another file's statistics into a running total, like the last line of `wc`.
+    pub fn add(&mut self, other: &Stats) {
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.graphemes += other.graphemes;
+        self.width = self.width.max(other.width);
+        self.lines += other.lines;
+        self.words += other.words;
+    }
+}
+
+// is_zero_width reports whether `c` takes no column of its own because it modifies the char before it.
+fn is_zero_width(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'   // Combining Diacritical Marks
+        | '\u{1AB0}'..='\u{1AFF}' // Combining Diacritical Marks Extended
+        | '\u{1DC0}'..='\u{1DFF}' // Combining Diacritical Marks Supplement
+        | '\u{200C}'..='\u{200D}' // Zero width non-joiner and joiner
+        | '\u{20D0}'..='\u{20FF}' // Combining Diacritical Marks for Symbols
+        | '\u{FE00}'..='\u{FE0F}' // Variation Selectors
+        | '\u{FE20}'..='\u{FE2F}' // Combining Half Marks
+        | '\u{1F3FB}'..='\u{1F3FF}' // Emoji skin tone modifiers
+        | '\u{E0100}'..='\u{E01EF}' // Variation Selectors Supplement
+    )
+}
+
+// is_wide reports whether `c` takes two terminal columns (East Asian Wide and Fullwidth chars, and most emoji).
+fn is_wide(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{115F}'   // Hangul Jamo initial consonants
+        | '\u{2E80}'..='\u{303E}' // CJK radicals, Kangxi radicals, CJK symbols and punctuation
+        | '\u{3041}'..='\u{33FF}' // Hiragana, Katakana, Bopomofo, CJK compatibility
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{A000}'..='\u{A4CF}' // Yi
+        | '\u{AC00}'..='\u{D7A3}' // Hangul syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FE30}'..='\u{FE4F}' // CJK Compatibility Forms
+        | '\u{FF00}'..='\u{FF60}' // Fullwidth forms
+        | '\u{FFE0}'..='\u{FFE6}' // Fullwidth signs
+        | '\u{1F300}'..='\u{1F64F}' // Miscellaneous symbols and pictographs, emoticons
+        | '\u{1F900}'..='\u{1F9FF}' // Supplemental symbols and pictographs
+        | '\u{20000}'..='\u{3FFFD}' // CJK Unified Ideographs Extensions B and beyond
+    )
+}
+
+// char_width returns how many terminal columns `c` takes.
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || is_zero_width(c) {
+        0
+    } else if is_wide(c) {
+        2
+    } else {
+        1
+    }
+}
+
+// cluster_width returns how many terminal columns a grapheme cluster takes, e.g. two for a family emoji made of
+// several wide emoji and zero width joiners.
+fn cluster_width(cluster: &str) -> usize {
+    cluster.chars().map(char_width).max().unwrap_or(0)
+}
+
+// display_width returns how many terminal columns `s` takes when printed on a single line.
+pub fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(cluster_width).sum()
+}
+
+// most_common_chars returns the `n` most frequent non-whitespace chars in `s`, most frequent first. Ties are broken
+// by char order so the result is stable between runs.
+pub fn most_common_chars(s: &str, n: usize) -> Vec<(char, usize)> {
+    let mut counts = HashMap::new();
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+
+    let mut counts: Vec<(char, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts.truncate(n);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii() {
+        let stats = Stats::of("hello world\nbye\n");
+        assert_eq!(
+            stats,
+            Stats {
+                bytes: 16,
+                chars: 16,
+                graphemes: 16,
+                width: 11,
+                lines: 2,
+                words: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn multi_byte_chars() {
+        // "é" as one precomposed char is two bytes; each CJK char is three bytes and two columns wide.
+        let stats = Stats::of("h\u{E9}llo \u{4E16}\u{754C}");
+        assert_eq!(stats.bytes, 13);
+        assert_eq!(stats.chars, 8);
+        assert_eq!(stats.graphemes, 8);
+        assert_eq!(stats.width, 10);
+        assert_eq!(stats.words, 2);
+
+        // An emoji is four bytes, one char and two columns.
+        let stats = Stats::of("\u{1F600}");
+        assert_eq!((stats.bytes, stats.chars, stats.graphemes, stats.width), (4, 1, 1, 2));
+    }
+
+    #[test]
+    fn combining_chars() {
+        // "e" followed by a combining acute accent is two chars but one grapheme and one column.
+        let stats = Stats::of("cafe\u{301}");
+        assert_eq!((stats.bytes, stats.chars, stats.graphemes, stats.width), (6, 5, 4, 4));
+
+        // A skin tone modifier attaches to the emoji before it.
+        let stats = Stats::of("\u{1F44D}\u{1F3FD}");
+        assert_eq!((stats.bytes, stats.chars, stats.graphemes), (8, 2, 1));
+
+        // Precomposed and decomposed forms look the same, and have the same graphemes and width.
+        let (precomposed, decomposed) = (Stats::of("na\u{EF}ve"), Stats::of("nai\u{308}ve"));
+        assert_ne!(precomposed.bytes, decomposed.bytes);
+        assert_eq!(precomposed.graphemes, decomposed.graphemes);
+        assert_eq!(precomposed.width, decomposed.width);
+    }
+
+    #[test]
+    fn joined_emoji_flags_and_crlf() {
+        // A family emoji is three emoji joined by two zero width joiners: five chars, one grapheme.
+        let stats = Stats::of("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}");
+        assert_eq!((stats.bytes, stats.chars, stats.graphemes, stats.width), (18, 5, 1, 2));
+
+        // A flag is a pair of regional indicators, so four of them make two flags.
+        let stats = Stats::of("\u{1F1FA}\u{1F1F8}\u{1F1EB}\u{1F1F7}");
+        assert_eq!((stats.chars, stats.graphemes, stats.words), (4, 2, 1));
+        assert_eq!(Stats::of("\u{1F1FA}\u{1F1F8}\u{1F1EB}").graphemes, 2);
+
+        // "\r\n" is one grapheme and ends one line.
+        let stats = Stats::of("ab\r\ncd\r\n");
+        assert_eq!((stats.bytes, stats.chars, stats.graphemes, stats.lines, stats.words), (8, 8, 6, 2, 2));
+        assert_eq!(stats.width, 2);
+    }
+
+    #[test]
+    fn width_is_the_widest_line() {
+        assert_eq!(Stats::of("ab\n\u{4E16}\u{754C}\u{4E16}\nc").width, 6);
+        assert_eq!(display_width("\u{FF21}\u{FF22}"), 4);
+        assert_eq!(display_width("a\tb"), 2);
+        assert_eq!(Stats::of(""), Stats::default());
+    }
+
+    #[test]
+    fn unicode_whitespace_separates_words() {
+        assert_eq!(Stats::of("\u{6771}\u{4EAC}\u{3000}\u{5927}\u{962A}").words, 2);
+        assert_eq!(Stats::of("a\u{A0}b\tc").words, 3);
+    }
+
+    #[test]
+    fn add_keeps_the_widest_width() {
+        let mut total = Stats::of("abc\n");
+        total.add(&Stats::of("\u{4E16}\u{754C}\n"));
+        assert_eq!((total.bytes, total.chars, total.lines, total.width), (11, 7, 2, 4));
+    }
+
+    #[test]
+    fn most_common_chars_counts_chars_not_bytes() {
+        assert_eq!(
+            most_common_chars("\u{E9}t\u{E9} \u{E9}t\u{E9}", 2),
+            [('\u{E9}', 4), ('t', 2)]
+        );
+        // Ties are broken by char order.
+        assert_eq!(most_common_chars("ba", 5), [('a', 1), ('b', 1)]);
+    }
+}
@@ -0,0 +1,65 @@
+/*
+
+Scripted tests for the ed binary.
+
+Each directory under tests/ed is one case:
+
+input.txt     the file being edited, if it exists before the script runs
+script.ed     the commands piped to ed on stdin
+expected.txt  what the file must hold after the script has run
+output.txt    what ed must print
+
+*/
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+fn run_case(case: &Path) {
+    let name = case.file_name().unwrap().to_string_lossy();
+    let dir = env::temp_dir().join(format!("ed-test-{}-{}", std::process::id(), name));
+    fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("file.txt");
+    let _ = fs::remove_file(&file);
+    if case.join("input.txt").exists() {
+        fs::copy(case.join("input.txt"), &file).unwrap();
+    }
+
+    let mut ed = Command::new(env!("CARGO_BIN_EXE_ed"))
+        .arg(&file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let script = fs::read(case.join("script.ed")).unwrap();
+    ed.stdin.take().unwrap().write_all(&script).unwrap();
+    let output = ed.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}: ed exited with {}", name, output.status);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        fs::read_to_string(case.join("output.txt")).unwrap(),
+        "{}: output",
+        name
+    );
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        fs::read_to_string(case.join("expected.txt")).unwrap(),
+        "{}: file",
+        name
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn scripts() {
+    let cases = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("ed");
+    let mut count = 0;
+    for entry in fs::read_dir(cases).unwrap() {
+        run_case(&entry.unwrap().path());
+        count += 1;
+    }
+    assert!(count > 0, "no cases found");
+}
@@ -0,0 +1,53 @@
+/*
+
+Compares the zero-copy tokenizer against the naive approach of allocating a String for every word.
+
+Run with `cargo bench --bench tokenizer [-- path/to/large/file]`. Without a path a synthetic input is generated.
+
+*/
+
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use slice_type::tokens::tokenize;
+
+const RUNS: u32 = 10;
+
+fn synthetic_input() -> String {
+    let line = "let total = price * 1.25 + \"quoted, text\" ; // caf\u{e9} na\u{ef}ve 42\n";
+    line.repeat(200_000)
+}
+
+fn time<F>(mut f: F) -> Duration
+where
+    F: FnMut() -> usize,
+{
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+    start.elapsed() / RUNS
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` to the harness, so skip flags when looking for a path.
+    let input = match env::args().skip(1).find(|arg| !arg.starts_with("--")) {
+        Some(path) => fs::read_to_string(&path).expect("could not read input file"),
+        None => synthetic_input(),
+    };
+
+    let zero_copy = time(|| tokenize(&input).count());
+    let naive = time(|| {
+        input
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<String>>()
+            .len()
+    });
+
+    println!("input: {} bytes, {} runs each", input.len(), RUNS);
+    println!("tokenize:                            {:?} per run", zero_copy);
+    println!("split_whitespace().map(String::from): {:?} per run", naive);
+}
@@ -0,0 +1,393 @@
+/*
+
+slice applies a Rust range to the lines, chars or bytes of a file (or stdin), a bit like `head`, `tail` and `cut`
+rolled into one:
+
+slice --lines 10..20 file.txt    // lines 10 through 19, counting from 0
+slice --chars ..80 file.txt      // the first 80 chars
+slice --bytes -16.. file.bin     // the last 16 bytes
+cat file.txt | slice --lines -5.. // the last 5 lines of stdin
+
+Input is streamed rather than read into memory. Ranges with negative bounds need one extra pass to count the
+items first, so stdin is spooled to a temporary file in that case.
+
+Byte ranges that would start or end in the middle of a UTF-8 char are refused unless `--allow-partial` is given.
+
+*/
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::process;
+
+use slice_type::ranges::SliceRange;
+
+const USAGE: &str = "usage: slice (--lines | --chars | --bytes) RANGE [--allow-partial] [FILE]";
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Lines,
+    Chars,
+    Bytes,
+}
+
+struct Options {
+    unit: Unit,
+    range: SliceRange,
+    allow_partial: bool,
+    path: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut unit = None;
+    let mut range = None;
+    let mut allow_partial = false;
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let selected = match arg.as_str() {
+            "--lines" => Unit::Lines,
+            "--chars" => Unit::Chars,
+            "--bytes" => Unit::Bytes,
+            "--allow-partial" => {
+                allow_partial = true;
+                continue;
+            }
+            _ if path.is_none() => {
+                path = Some(arg.clone());
+                continue;
+            }
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        };
+
+        if unit.is_some() {
+            return Err(String::from("only one of --lines, --chars or --bytes may be given"));
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a range", arg))?;
+        range = Some(value.parse::<SliceRange>().map_err(|e| e.to_string())?);
+        unit = Some(selected);
+    }
+
+    Ok(Options {
+        unit: unit.ok_or("one of --lines, --chars or --bytes is required")?,
+        range: range.expect("range is set together with unit"),
+        allow_partial,
+        path,
+    })
+}
+
+// How many names `spool` tries before giving up.
+const SPOOL_ATTEMPTS: u32 = 100;
+
+// open_file opens the input as a seekable file, spooling stdin to a temporary file when there is no path.
+fn open_file(path: Option<&str>) -> io::Result<File> {
+    match path {
+        Some(path) => File::open(path),
+        None => spool(&mut io::stdin().lock()),
+    }
+}
+
+// spool copies `input` into a new temporary file and rewinds it. A file left behind by an earlier run, or made by
+// someone else, may already have the name tried first, so names are tried in turn until one can be created.
+fn spool<R: Read>(input: &mut R) -> io::Result<File> {
+    let mut attempt = 0;
+    let (mut file, path) = loop {
+        let path = env::temp_dir().join(format!("slice-{}-{}", process::id(), attempt));
+        match File::options().read(true).write(true).create_new(true).open(&path) {
+            Ok(file) => break (file, path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempt + 1 < SPOOL_ATTEMPTS => attempt += 1,
+            Err(e) => return Err(e),
+        }
+    };
+    // Unlinking straight away means the spool file is cleaned up when it is closed, even on error.
+    fs::remove_file(&path)?;
+    io::copy(input, &mut file)?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+// for_each_line feeds every line, including its trailing newline, to `f` until `f` returns false.
+fn for_each_line<R, F>(reader: R, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(&[u8]) -> io::Result<bool>,
+{
+    let mut reader = reader;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 || !f(&line)? {
+            return Ok(());
+        }
+    }
+}
+
+// utf8 decodes a line for char slicing. Splitting on '\n' never cuts a UTF-8 sequence, so each line decodes alone.
+fn utf8(line: &[u8]) -> io::Result<&str> {
+    std::str::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn count(unit: Unit, file: &mut File) -> io::Result<usize> {
+    let mut total = 0;
+    match unit {
+        Unit::Lines => for_each_line(BufReader::new(&mut *file), |_| {
+            total += 1;
+            Ok(true)
+        })?,
+        Unit::Chars => for_each_line(BufReader::new(&mut *file), |line| {
+            total += utf8(line)?.chars().count();
+            Ok(true)
+        })?,
+        Unit::Bytes => total = file.metadata()?.len() as usize,
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(total)
+}
+
+fn slice_lines<R: BufRead, W: Write>(reader: R, range: Range<usize>, out: &mut W) -> io::Result<()> {
+    let mut index = 0;
+    for_each_line(reader, |line| {
+        if index >= range.end {
+            return Ok(false);
+        }
+        if index >= range.start {
+            out.write_all(line)?;
+        }
+        index += 1;
+        Ok(true)
+    })
+}
+
+fn slice_chars<R: BufRead, W: Write>(reader: R, range: Range<usize>, out: &mut W) -> io::Result<()> {
+    let mut index = 0;
+    for_each_line(reader, |line| {
+        let line = utf8(line)?;
+        let count = line.chars().count();
+
+        if index + count > range.start {
+            // Convert the char range within this line into byte offsets so the line can be sliced directly.
+            let skip = range.start.saturating_sub(index);
+            let take = range.end.saturating_sub(index).min(count) - skip;
+            let mut offsets = line.char_indices().map(|(i, _)| i).chain(Some(line.len()));
+            let start = offsets.nth(skip).unwrap_or(line.len());
+            let end = if take == 0 { start } else { offsets.nth(take - 1).unwrap_or(line.len()) };
+            out.write_all(&line.as_bytes()[start..end])?;
+        }
+
+        index += count;
+        Ok(index < range.end)
+    })
+}
+
+// is_continuation reports whether the byte at `pos` sits inside a multi-byte UTF-8 char.
+fn is_continuation(file: &mut File, pos: usize) -> io::Result<bool> {
+    file.seek(SeekFrom::Start(pos as u64))?;
+    let mut byte = [0];
+    let read = file.read(&mut byte)?;
+    Ok(read == 1 && byte[0] & 0b1100_0000 == 0b1000_0000)
+}
+
+fn slice_bytes<W: Write>(file: &mut File, range: Range<usize>, allow_partial: bool, out: &mut W) -> io::Result<()> {
+    let len = file.metadata()?.len() as usize;
+    let range = range.start.min(len)..range.end.min(len);
+
+    if !allow_partial && (is_continuation(file, range.start)? || is_continuation(file, range.end)?) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("byte range {:?} cuts a UTF-8 char (pass --allow-partial to allow this)", range),
+        ));
+    }
+
+    file.seek(SeekFrom::Start(range.start as u64))?;
+    io::copy(&mut file.take((range.end - range.start) as u64), out)?;
+    Ok(())
+}
+
+fn run(options: Options) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+
+    // Lines and chars stream straight from stdin unless we have to count them first. Bytes always need to seek.
+    let needs_file = options.range.needs_len() || matches!(options.unit, Unit::Bytes);
+    if !needs_file && options.path.is_none() {
+        let range = options.range.resolve(None);
+        let stdin = io::stdin();
+        match options.unit {
+            Unit::Lines => slice_lines(stdin.lock(), range, &mut out)?,
+            _ => slice_chars(stdin.lock(), range, &mut out)?,
+        }
+        return out.flush();
+    }
+
+    let mut file = open_file(options.path.as_deref())?;
+    let len = if options.range.needs_len() {
+        Some(count(options.unit, &mut file)?)
+    } else {
+        None
+    };
+    let range = options.range.resolve(len);
+
+    match options.unit {
+        Unit::Lines => slice_lines(BufReader::new(&mut file), range, &mut out)?,
+        Unit::Chars => slice_chars(BufReader::new(&mut file), range, &mut out)?,
+        Unit::Bytes => slice_bytes(&mut file, range, options.allow_partial, &mut out)?,
+    }
+    out.flush()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("slice: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        // A closed pipe (e.g. `slice ... | head`) is not worth reporting.
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            eprintln!("slice: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    // TempFile is a file in the temp directory holding `contents`, removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> TempFile {
+            let path = env::temp_dir().join(format!("slice-test-{}-{}", process::id(), name));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+
+        fn open(&self) -> File {
+            File::open(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn resolve(range: &str, len: usize) -> Range<usize> {
+        range.parse::<SliceRange>().unwrap().resolve(Some(len))
+    }
+
+    fn lines(input: &str, range: Range<usize>) -> String {
+        let mut out = Vec::new();
+        slice_lines(Cursor::new(input), range, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn chars(input: &str, range: Range<usize>) -> String {
+        let mut out = Vec::new();
+        slice_chars(Cursor::new(input), range, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn bytes(file: &TempFile, range: Range<usize>, allow_partial: bool) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        slice_bytes(&mut file.open(), range, allow_partial, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn line_mode() {
+        let input = "zero\none\ntwo\nthree";
+        assert_eq!(lines(input, 1..3), "one\ntwo\n");
+        assert_eq!(lines(input, 0..1), "zero\n");
+        // The last line keeps having no newline.
+        assert_eq!(lines(input, resolve("-2..", 4)), "two\nthree");
+        assert_eq!(lines(input, resolve("..-3", 4)), "zero\n");
+        assert_eq!(lines(input, 2..usize::MAX), "two\nthree");
+        assert_eq!(lines(input, 9..usize::MAX), "");
+        assert_eq!(lines("", 0..usize::MAX), "");
+
+        let file = TempFile::new("lines", input.as_bytes());
+        assert_eq!(count(Unit::Lines, &mut file.open()).unwrap(), 4);
+    }
+
+    #[test]
+    fn char_mode() {
+        let input = "héllo\nwörld 日本\n";
+        assert_eq!(chars(input, 0..5), "héllo");
+        // Ranges run across lines, and the newline is a char like any other.
+        assert_eq!(chars(input, 4..9), "o\nwör");
+        assert_eq!(chars(input, resolve("-3..", 15)), "日本\n");
+        assert_eq!(chars(input, 20..30), "");
+
+        let file = TempFile::new("chars", input.as_bytes());
+        assert_eq!(count(Unit::Chars, &mut file.open()).unwrap(), 15);
+        assert_eq!(count(Unit::Bytes, &mut file.open()).unwrap(), input.len());
+
+        let invalid = TempFile::new("invalid", b"ok\n\xff\n");
+        let e = count(Unit::Chars, &mut invalid.open()).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn byte_mode_refuses_to_split_utf8() {
+        // 'a' is one byte, 'é' two and '日' three.
+        let file = TempFile::new("bytes", "aé日".as_bytes());
+        assert_eq!(bytes(&file, 0..3, false).unwrap(), "aé".as_bytes());
+        assert_eq!(bytes(&file, 3..6, false).unwrap(), "日".as_bytes());
+        assert_eq!(bytes(&file, resolve("-3..", 6), false).unwrap(), "日".as_bytes());
+
+        for range in [0..2, 2..3, 1..4, 4..6] {
+            let e = bytes(&file, range.clone(), false).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput, "{:?}", range);
+        }
+        assert_eq!(bytes(&file, 0..2, true).unwrap(), b"a\xc3");
+
+        // Ranges past the end are clamped to it.
+        assert_eq!(bytes(&file, 1..100, false).unwrap(), "é日".as_bytes());
+        assert_eq!(bytes(&file, 50..100, false).unwrap(), b"");
+    }
+
+    #[test]
+    fn spooling_skips_names_already_taken() {
+        // Something left behind by an earlier run with the same pid, under the first name spool tries.
+        let stale = env::temp_dir().join(format!("slice-{}-0", process::id()));
+        fs::write(&stale, "stale").unwrap();
+
+        let mut file = spool(&mut Cursor::new("fresh input")).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "fresh input");
+        // The stale file is left alone, and the spool file is already gone.
+        assert_eq!(fs::read_to_string(&stale).unwrap(), "stale");
+        assert!(!env::temp_dir().join(format!("slice-{}-1", process::id())).exists());
+        fs::remove_file(&stale).unwrap();
+    }
+
+    #[test]
+    fn arguments() {
+        let args = |args: &[&str]| parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+        let options = args(&["--bytes", "-16..", "--allow-partial", "file.bin"]).unwrap();
+        assert!(matches!(options.unit, Unit::Bytes));
+        assert_eq!(options.range, "-16..".parse().unwrap());
+        assert!(options.allow_partial);
+        assert_eq!(options.path.as_deref(), Some("file.bin"));
+
+        assert!(args(&["file.txt"]).is_err());
+        assert!(args(&["--lines"]).is_err());
+        assert!(args(&["--lines", "5"]).is_err());
+        assert!(args(&["--lines", "..5", "--chars", "..5"]).is_err());
+        assert!(args(&["--lines", "..5", "a", "b"]).is_err());
+    }
+}
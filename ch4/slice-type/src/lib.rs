@@ -0,0 +1,6 @@
+pub mod ranges;
+pub mod slices;
+#[cfg(test)]
+mod test_support;
+pub mod tokens;
+pub mod words;
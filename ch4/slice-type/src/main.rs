@@ -5,21 +5,41 @@ The slice type
 slices let you reference a subset of elements in a collection and does not have ownership.
 */
 
+use slice_type::slices::{first_mismatched_chunk, get_range, prefix_until, split_on, windows_with_stride};
+use slice_type::tokens::tokenize;
+use slice_type::words::{first_word, last_word, nth_word, words_by};
+
 fn main() {
-    println!("Hello, world!");
-}
+    let s = String::from("hello\tworld,\u{00A0}caf\u{0065}\u{0301} na\u{00EF}ve\n");
 
-// first_word return the index of the first space or the whole word of the given input string.
-fn first_word(s: &String) -> usize {
-    let bytes = s.as_bytes(); // Convert string into a byte array.
+    // first_word now returns a slice of the String instead of an index into it.
+    println!("first word: {:?}", first_word(&s));
+    println!("last word: {:?}", last_word(&s));
+    println!("third word: {:?}", nth_word(&s, 2));
 
-    for (i, &item) in bytes.iter().enumerate() { // Create an interator over the array. Here, enumerate returns a tuple, the index of the item and the item itself.
-        if item == b' ' {
-            return i;
-        }
+    // Delimiters are configurable, here splitting on commas as well as whitespace.
+    let csv = "alpha, beta,gamma";
+    for word in words_by(csv, |c| c == ',' || c.is_whitespace()) {
+        println!("word: {:?}", word);
     }
 
-    s.len()
+    // Tokens borrow from the input too, so tokenizing never copies the text.
+    let source = "let total = 2.5 * \"unit price\";\nprint(total)";
+    for token in tokenize(source) {
+        println!("{}:{} {:?} {:?} {:?}", token.line, token.column, token.kind, token.text, token.span);
+    }
+
+    // The same slice algorithms work over arrays, vectors and the bytes of a String.
+    let a = [1, 2, 0, 3, 4, 0, 5];
+    let v = vec![10, 20, 30, 40, 50, 60];
+    let bytes = s.as_bytes();
+
+    println!("prefix of a before 0: {:?}", prefix_until(&a, |&x| x == 0));
+    println!("a split on 0: {:?}", split_on(&a, &0).collect::<Vec<_>>());
+    println!("windows of v, size 3 stride 2: {:?}", windows_with_stride(&v, 3, 2).collect::<Vec<_>>());
+    println!("v[4..10] without panicking: {:?}", get_range(&v, 4..10));
+    println!("first word of s as bytes: {:?}", prefix_until(bytes, |b| b.is_ascii_whitespace()));
+    println!("first differing chunk: {:?}", first_mismatched_chunk(b"hello world", b"hello there", 4));
 }
 
 /*
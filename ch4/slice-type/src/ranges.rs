@@ -0,0 +1,181 @@
+/*
+
+Rust range syntax parsed at runtime.
+
+A SliceRange is written the same way as the ranges used to slice strings (`..5`, `3..`, `2..=7`, `..`), with one
+addition: negative bounds count back from the end, so `-10..` is the last ten items and `..-1` drops the last one.
+
+Because negative bounds need to know how many items there are, callers check `needs_len` and only pay for
+counting when it returns true.
+
+*/
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::Range;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceRange {
+    pub start: Option<isize>,
+    pub end: Option<isize>,
+    pub inclusive: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseRangeError {
+    MissingDots,
+    InclusiveWithoutEnd,
+    InvalidBound(ParseIntError),
+}
+
+impl fmt::Display for ParseRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRangeError::MissingDots => write!(f, "expected a range like `3..7`, `..5` or `2..=7`"),
+            ParseRangeError::InclusiveWithoutEnd => write!(f, "`..=` needs an end bound"),
+            ParseRangeError::InvalidBound(e) => write!(f, "invalid range bound: {}", e),
+        }
+    }
+}
+
+impl FromStr for SliceRange {
+    type Err = ParseRangeError;
+
+    fn from_str(s: &str) -> Result<SliceRange, ParseRangeError> {
+        let dots = s.find("..").ok_or(ParseRangeError::MissingDots)?;
+        let (start, rest) = (&s[..dots], &s[dots + 2..]);
+
+        let (inclusive, end) = match rest.strip_prefix('=') {
+            Some(end) => (true, end),
+            None => (false, rest),
+        };
+
+        if inclusive && end.is_empty() {
+            return Err(ParseRangeError::InclusiveWithoutEnd);
+        }
+
+        Ok(SliceRange {
+            start: parse_bound(start)?,
+            end: parse_bound(end)?,
+            inclusive,
+        })
+    }
+}
+
+fn parse_bound(s: &str) -> Result<Option<isize>, ParseRangeError> {
+    if s.is_empty() {
+        return Ok(None);
+    }
+    s.parse().map(Some).map_err(ParseRangeError::InvalidBound)
+}
+
+impl SliceRange {
+    // needs_len reports whether resolving this range requires the total number of items.
+    pub fn needs_len(&self) -> bool {
+        self.start.is_some_and(|i| i < 0) || self.end.is_some_and(|i| i < 0)
+    }
+
+    // resolve turns the range into concrete indexes. `len` must be Some when `needs_len` is true; when it is None
+    // an open end resolves to `usize::MAX` so callers can stream until the input runs out.
+    pub fn resolve(&self, len: Option<usize>) -> Range<usize> {
+        let index = |bound: isize| {
+            if bound < 0 {
+                let len = len.expect("negative range bounds need the input length");
+                len.saturating_sub(bound.unsigned_abs())
+            } else {
+                bound as usize
+            }
+        };
+
+        let start = self.start.map_or(0, index);
+        let mut end = match self.end {
+            Some(bound) if self.inclusive => index(bound).saturating_add(1),
+            Some(bound) => index(bound),
+            None => usize::MAX,
+        };
+        if let Some(len) = len {
+            end = end.min(len);
+        }
+
+        start..end.max(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(s: &str) -> SliceRange {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parsing() {
+        let bounds = |s: &str| {
+            let r = range(s);
+            (r.start, r.end, r.inclusive)
+        };
+        assert_eq!(bounds(".."), (None, None, false));
+        assert_eq!(bounds("..5"), (None, Some(5), false));
+        assert_eq!(bounds("3.."), (Some(3), None, false));
+        assert_eq!(bounds("2..=7"), (Some(2), Some(7), true));
+        assert_eq!(bounds("-10.."), (Some(-10), None, false));
+        assert_eq!(bounds("..-1"), (None, Some(-1), false));
+        assert_eq!(bounds("-3..=-1"), (Some(-3), Some(-1), true));
+
+        assert_eq!("5".parse::<SliceRange>(), Err(ParseRangeError::MissingDots));
+        assert_eq!("".parse::<SliceRange>(), Err(ParseRangeError::MissingDots));
+        assert_eq!("3..=".parse::<SliceRange>(), Err(ParseRangeError::InclusiveWithoutEnd));
+        for &s in &["a..", "..b", "1..2..3", "1...2", " 1..2", "..=+-1"] {
+            assert!(
+                matches!(s.parse::<SliceRange>(), Err(ParseRangeError::InvalidBound(_))),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn resolving() {
+        // Without a length, an open end runs until the input does.
+        assert_eq!(range("3..").resolve(None), 3..usize::MAX);
+        assert_eq!(range("..5").resolve(None), 0..5);
+        assert_eq!(range("2..=7").resolve(None), 2..8);
+        assert!(!range("2..=7").needs_len());
+
+        // Ends past the length are clamped to it.
+        assert_eq!(range("..").resolve(Some(10)), 0..10);
+        assert_eq!(range("3..").resolve(Some(10)), 3..10);
+        assert_eq!(range("5..50").resolve(Some(10)), 5..10);
+        assert_eq!(range("..=9").resolve(Some(10)), 0..10);
+        assert_eq!(range("..=10").resolve(Some(10)), 0..10);
+        // A start past the length, or past the end, leaves an empty range rather than an error.
+        assert_eq!(range("20..30").resolve(Some(10)), 20..20);
+        assert_eq!(range("7..3").resolve(Some(10)), 7..7);
+    }
+
+    #[test]
+    fn negative_bounds_count_from_the_end() {
+        assert!(range("-10..").needs_len());
+        assert!(range("..-1").needs_len());
+        assert!(!range("0..").needs_len());
+
+        assert_eq!(range("-3..").resolve(Some(10)), 7..10);
+        assert_eq!(range("..-1").resolve(Some(10)), 0..9);
+        assert_eq!(range("-3..-1").resolve(Some(10)), 7..9);
+        assert_eq!(range("-3..=-1").resolve(Some(10)), 7..10);
+        assert_eq!(range("2..-2").resolve(Some(10)), 2..8);
+        // Counting back past the start stops at 0.
+        assert_eq!(range("-50..").resolve(Some(10)), 0..10);
+        assert_eq!(range("..-50").resolve(Some(10)), 0..0);
+        assert_eq!(range(&format!("{}..", isize::MIN)).resolve(Some(10)), 0..10);
+        assert_eq!(range("-1..").resolve(Some(0)), 0..0);
+    }
+
+    #[test]
+    #[should_panic(expected = "negative range bounds need the input length")]
+    fn negative_bounds_without_a_length() {
+        range("-1..").resolve(None);
+    }
+}
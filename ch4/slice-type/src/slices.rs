@@ -0,0 +1,242 @@
+/*
+
+Generic slice algorithms.
+
+String slices are one kind of slice; `&[T]` works the same way for arrays, vectors and the bytes of a String:
+
+let a = [1, 2, 3, 4, 5];
+let v = vec![1, 2, 3, 4, 5];
+let s = String::from("hello world");
+
+prefix_until(&a, |&x| x > 2);           // &a[..2]
+prefix_until(&v, |&x| x > 2);           // &v[..2]
+prefix_until(s.as_bytes(), |&b| b == b' '); // b"hello", the byte version of first_word
+
+Everything here borrows from the input slice, so none of these functions allocate.
+
+*/
+
+use std::ops::{Bound, RangeBounds};
+
+// prefix_until returns the elements before the first one matching `predicate`, or the whole slice if none match.
+pub fn prefix_until<T, P>(s: &[T], predicate: P) -> &[T]
+where
+    P: Fn(&T) -> bool,
+{
+    match s.iter().position(predicate) {
+        Some(i) => &s[..i],
+        None => s,
+    }
+}
+
+// get_range is a non-panicking `&s[range]`: it returns None when the range is reversed or runs past the end.
+pub fn get_range<T, R>(s: &[T], range: R) -> Option<&[T]>
+where
+    R: RangeBounds<usize>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i.checked_add(1)?,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => s.len(),
+    };
+
+    if start > end || end > s.len() {
+        return None;
+    }
+
+    Some(&s[start..end])
+}
+
+// SplitOn iterates the sub-slices between occurrences of a separator element. Like `str::split`, adjacent
+// separators produce empty slices and an empty input produces a single empty slice.
+pub struct SplitOn<'a, T> {
+    rest: Option<&'a [T]>,
+    separator: &'a T,
+}
+
+impl<'a, T: PartialEq> Iterator for SplitOn<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        let rest = self.rest?;
+
+        match rest.iter().position(|x| x == self.separator) {
+            Some(i) => {
+                self.rest = Some(&rest[i + 1..]);
+                Some(&rest[..i])
+            }
+            None => {
+                self.rest = None;
+                Some(rest)
+            }
+        }
+    }
+}
+
+pub fn split_on<'a, T: PartialEq>(s: &'a [T], separator: &'a T) -> SplitOn<'a, T> {
+    SplitOn {
+        rest: Some(s),
+        separator,
+    }
+}
+
+// StridedWindows iterates windows of `size` elements whose starts are `stride` elements apart. A stride of 1 is
+// the same as `slice::windows`; a stride equal to `size` is the same as `slice::chunks_exact`.
+pub struct StridedWindows<'a, T> {
+    s: &'a [T],
+    size: usize,
+    stride: usize,
+    start: usize,
+}
+
+impl<'a, T> Iterator for StridedWindows<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        let end = self.start.checked_add(self.size)?;
+        let window = self.s.get(self.start..end)?;
+        self.start = self.start.saturating_add(self.stride);
+        Some(window)
+    }
+}
+
+// windows_with_stride panics if `size` or `stride` is zero, like `slice::windows` does for a zero size.
+pub fn windows_with_stride<T>(s: &[T], size: usize, stride: usize) -> StridedWindows<'_, T> {
+    assert!(size != 0, "window size must be non-zero");
+    assert!(stride != 0, "window stride must be non-zero");
+
+    StridedWindows {
+        s,
+        size,
+        stride,
+        start: 0,
+    }
+}
+
+// compare_chunks walks two slices `size` elements at a time, yielding whether each pair of chunks is equal. When
+// the slices differ in length, the chunks past the end of the shorter one compare as unequal.
+pub fn compare_chunks<'a, T: PartialEq>(
+    a: &'a [T],
+    b: &'a [T],
+    size: usize,
+) -> impl Iterator<Item = bool> + 'a {
+    assert!(size != 0, "chunk size must be non-zero");
+
+    let count = a.len().max(b.len()).div_ceil(size);
+    (0..count).map(move |i| {
+        let range = i * size..(i * size + size);
+        let chunk_a = get_range(a, range.clone()).unwrap_or_else(|| &a[(i * size).min(a.len())..]);
+        let chunk_b = get_range(b, range).unwrap_or_else(|| &b[(i * size).min(b.len())..]);
+        chunk_a == chunk_b
+    })
+}
+
+// first_mismatched_chunk returns the index of the first chunk that differs between `a` and `b`.
+pub fn first_mismatched_chunk<T: PartialEq>(a: &[T], b: &[T], size: usize) -> Option<usize> {
+    compare_chunks(a, b, size).position(|equal| !equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_until_on_arrays_vecs_and_bytes() {
+        let a = [1, 2, 3, 4, 5];
+        let v = vec![1, 2, 3, 4, 5];
+        let s = String::from("hello world");
+
+        assert_eq!(prefix_until(&a, |&x| x > 2), &[1, 2]);
+        assert_eq!(prefix_until(&v, |&x| x > 2), &[1, 2]);
+        assert_eq!(prefix_until(s.as_bytes(), |&b| b == b' '), b"hello");
+        assert_eq!(prefix_until(&a, |&x| x > 10), &a);
+        assert_eq!(prefix_until(&v, |&x| x == 1), &[] as &[i32]);
+        assert_eq!(prefix_until(b"", |&b| b == b' '), b"");
+    }
+
+    #[test]
+    fn get_range_on_arrays_vecs_and_bytes() {
+        let a = [1, 2, 3, 4, 5];
+        let v = vec![1, 2, 3, 4, 5];
+        let s = String::from("hello");
+
+        assert_eq!(get_range(&a, 1..3), Some(&[2, 3][..]));
+        assert_eq!(get_range(&v, 3..), Some(&[4, 5][..]));
+        assert_eq!(get_range(s.as_bytes(), ..=1), Some(&b"he"[..]));
+        assert_eq!(get_range(&a, ..), Some(&a[..]));
+        assert_eq!(get_range(&a, 5..5), Some(&[] as &[i32]));
+
+        assert_eq!(get_range(&a, 3..6), None);
+        assert_eq!(get_range(&v, 6..), None);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = get_range(s.as_bytes(), 3..1);
+        assert_eq!(reversed, None);
+        assert_eq!(get_range(&v, ..=usize::MAX), None);
+        assert_eq!(get_range(&a, (Bound::Excluded(usize::MAX), Bound::Unbounded)), None);
+    }
+
+    #[test]
+    fn split_on_on_arrays_vecs_and_bytes() {
+        let a = [1, 0, 2, 3, 0, 0, 4];
+        let v: Vec<i32> = a.to_vec();
+        let s = String::from("a,b,,c");
+
+        let expected: Vec<&[i32]> = vec![&[1], &[2, 3], &[], &[4]];
+        assert_eq!(split_on(&a, &0).collect::<Vec<_>>(), expected);
+        assert_eq!(split_on(&v, &0).collect::<Vec<_>>(), expected);
+        assert_eq!(
+            split_on(s.as_bytes(), &b',').collect::<Vec<_>>(),
+            [&b"a"[..], b"b", b"", b"c"]
+        );
+        assert_eq!(split_on(&[] as &[i32], &0).collect::<Vec<_>>(), [&[] as &[i32]]);
+        assert_eq!(split_on(&[0], &0).collect::<Vec<_>>(), [&[] as &[i32], &[]]);
+    }
+
+    #[test]
+    fn windows_with_stride_on_arrays_vecs_and_bytes() {
+        let a = [1, 2, 3, 4, 5, 6];
+        let v = a.to_vec();
+        let s = String::from("abcdef");
+
+        assert_eq!(windows_with_stride(&a, 3, 2).collect::<Vec<_>>(), [&[1, 2, 3], &[3, 4, 5]]);
+        assert_eq!(
+            windows_with_stride(&v, 2, 1).collect::<Vec<_>>(),
+            v.windows(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            windows_with_stride(s.as_bytes(), 2, 2).collect::<Vec<_>>(),
+            s.as_bytes().chunks_exact(2).collect::<Vec<_>>()
+        );
+        assert_eq!(windows_with_stride(&a, 7, 1).count(), 0);
+        assert_eq!(windows_with_stride(&a, 1, usize::MAX).collect::<Vec<_>>(), [&[1]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "window stride must be non-zero")]
+    fn windows_with_zero_stride_panics() {
+        windows_with_stride(&[1, 2, 3], 1, 0);
+    }
+
+    #[test]
+    fn compare_chunks_on_arrays_vecs_and_bytes() {
+        let a = [1, 2, 3, 4, 5];
+        let v = vec![1, 2, 9, 4, 5];
+        let hello = String::from("hello world");
+        let help = String::from("help");
+
+        assert_eq!(compare_chunks(&a, &v, 2).collect::<Vec<_>>(), [true, false, true]);
+        assert_eq!(first_mismatched_chunk(&a, &v, 2), Some(1));
+        assert_eq!(first_mismatched_chunk(&a, &[1, 2, 3, 4, 5], 2), None);
+        assert_eq!(
+            compare_chunks(hello.as_bytes(), help.as_bytes(), 3).collect::<Vec<_>>(),
+            [true, false, false, false]
+        );
+        // A missing final chunk is unequal to a present one.
+        assert_eq!(first_mismatched_chunk(&a[..4], &a, 2), Some(2));
+    }
+}
@@ -0,0 +1,225 @@
+/*
+
+A zero-copy tokenizer.
+
+Each Token borrows its text from the input rather than owning a copy, so tokenizing never allocates. The byte
+span lets callers get back to the original input (e.g. `&input[token.span.clone()]` is `token.text`), and the
+line/column pair is meant for error messages. Columns count chars, not bytes, so they line up with what an
+editor shows for non-ASCII input.
+
+*/
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    Number,
+    Punctuation,
+    // A double quoted string, including its quotes. Backslash escapes the next char.
+    Quoted,
+    // A quoted string that reached the end of input before its closing quote.
+    Unterminated,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub span: Range<usize>,
+    pub line: usize,
+    pub column: usize,
+}
+
+pub struct Tokenizer<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub fn new(input: &'a str) -> Tokenizer<'a> {
+        Tokenizer {
+            input,
+            pos: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    // bump consumes one char, keeping line and column up to date.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn bump_while<P>(&mut self, predicate: P)
+    where
+        P: Fn(char) -> bool,
+    {
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    // bump_quoted consumes the rest of a quoted string after its opening quote.
+    fn bump_quoted(&mut self) -> TokenKind {
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => return TokenKind::Quoted,
+                '\\' => {
+                    self.bump();
+                }
+                _ => {}
+            }
+        }
+        TokenKind::Unterminated
+    }
+
+    // bump_number consumes digits with an optional fractional part. The `.` is only taken when a digit follows it,
+    // so `1.` tokenizes as a number followed by punctuation.
+    fn bump_number(&mut self) {
+        self.bump_while(|c| c.is_ascii_digit());
+
+        let mut rest = self.input[self.pos..].chars();
+        if rest.next() == Some('.') && rest.next().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+            self.bump_while(|c| c.is_ascii_digit());
+        }
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.bump_while(char::is_whitespace);
+
+        let start = self.pos;
+        let line = self.line;
+        let column = self.column;
+
+        let kind = match self.bump()? {
+            '"' => self.bump_quoted(),
+            c if c.is_ascii_digit() => {
+                self.bump_number();
+                TokenKind::Number
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                self.bump_while(|c| c.is_alphanumeric() || c == '_');
+                TokenKind::Word
+            }
+            _ => TokenKind::Punctuation,
+        };
+
+        Some(Token {
+            kind,
+            text: &self.input[start..self.pos],
+            span: start..self.pos,
+            line,
+            column,
+        })
+    }
+}
+
+// tokenize is shorthand for `Tokenizer::new(input)`.
+pub fn tokenize(input: &str) -> Tokenizer<'_> {
+    Tokenizer::new(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(input).map(|token| (token.kind, token.text)).collect()
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(tokenize("").next(), None);
+        assert_eq!(tokenize(" \t\n\r\n ").next(), None);
+    }
+
+    #[test]
+    fn words_numbers_and_punctuation() {
+        assert_eq!(
+            kinds("let x_1 = 3.25 + y;"),
+            vec![
+                (Word, "let"),
+                (Word, "x_1"),
+                (Punctuation, "="),
+                (Number, "3.25"),
+                (Punctuation, "+"),
+                (Word, "y"),
+                (Punctuation, ";"),
+            ]
+        );
+        // Every punctuation char is a token of its own, even when they're next to each other.
+        assert_eq!(
+            kinds("a->b!=(c)"),
+            vec![
+                (Word, "a"),
+                (Punctuation, "-"),
+                (Punctuation, ">"),
+                (Word, "b"),
+                (Punctuation, "!"),
+                (Punctuation, "="),
+                (Punctuation, "("),
+                (Word, "c"),
+                (Punctuation, ")"),
+            ]
+        );
+        // A `.` is only part of a number when a digit follows it.
+        assert_eq!(kinds("1."), vec![(Number, "1"), (Punctuation, ".")]);
+        assert_eq!(kinds("1.2.3"), vec![(Number, "1.2"), (Punctuation, "."), (Number, "3")]);
+        assert_eq!(kinds(".5"), vec![(Punctuation, "."), (Number, "5")]);
+        assert_eq!(kinds("42abc"), vec![(Number, "42"), (Word, "abc")]);
+        assert_eq!(kinds("_x9 café"), vec![(Word, "_x9"), (Word, "café")]);
+        assert_eq!(kinds("🦀"), vec![(Punctuation, "🦀")]);
+    }
+
+    #[test]
+    fn quoted_and_escaped_text() {
+        assert_eq!(kinds(r#"say "hi there""#), vec![(Word, "say"), (Quoted, r#""hi there""#)]);
+        assert_eq!(kinds(r#""""#), vec![(Quoted, r#""""#)]);
+        // A backslash escapes a quote, or another backslash.
+        assert_eq!(kinds(r#""a \"b\" c" d"#), vec![(Quoted, r#""a \"b\" c""#), (Word, "d")]);
+        assert_eq!(kinds(r#""a\\" b"#), vec![(Quoted, r#""a\\""#), (Word, "b")]);
+        // Quoted text may hold newlines and anything else.
+        assert_eq!(kinds("\"x\ny 1.5 ;\""), vec![(Quoted, "\"x\ny 1.5 ;\"")]);
+
+        assert_eq!(kinds(r#"x "never closed"#), vec![(Word, "x"), (Unterminated, r#""never closed"#)]);
+        assert_eq!(kinds(r#""ends in \"#), vec![(Unterminated, r#""ends in \"#)]);
+        assert_eq!(kinds(r#"""#), vec![(Unterminated, r#"""#)]);
+    }
+
+    #[test]
+    fn spans_lines_and_columns() {
+        let input = "é = \"a\nb\"\n  42";
+        let tokens: Vec<Token> = tokenize(input).collect();
+        for token in &tokens {
+            assert_eq!(&input[token.span.clone()], token.text);
+        }
+        let positions: Vec<(&str, usize, usize)> = tokens.iter().map(|t| (t.text, t.line, t.column)).collect();
+        // Columns count chars, so the `=` after the two byte `é` is in column 3.
+        assert_eq!(positions, vec![("é", 1, 1), ("=", 1, 3), ("\"a\nb\"", 1, 5), ("42", 3, 3)]);
+        assert_eq!(tokens[3].span, 13..15);
+    }
+}
@@ -0,0 +1,235 @@
+/*
+
+Word segmentation over string slices.
+
+Every function here borrows the input `&str` and hands back `&str` slices into it, so the compiler ties the
+lifetime of each word to the string it came from (the same guarantee the rewritten `first_word` relies on).
+
+Slices are only ever cut on grapheme cluster boundaries, which are always char boundaries, so a returned word
+can never split a UTF-8 code point. Clusters are the extended grapheme clusters of Unicode Standard Annex #29, as
+found by the unicode-segmentation crate, so a letter and its combining marks, an emoji joined to others by zero
+width joiners, a pair of regional indicators making up a flag, and a "\r\n" are each a single cluster.
+
+*/
+
+use unicode_segmentation::UnicodeSegmentation;
+
+// next_cluster returns the byte range of the grapheme cluster starting at `start`.
+fn next_cluster(s: &str, start: usize) -> (usize, usize) {
+    let len = s[start..].graphemes(true).next().map_or(0, str::len);
+    (start, start + len)
+}
+
+// prev_cluster returns the byte range of the grapheme cluster ending at `end`.
+fn prev_cluster(s: &str, end: usize) -> (usize, usize) {
+    let len = s[..end].graphemes(true).next_back().map_or(0, str::len);
+    (end - len, end)
+}
+
+// Words iterates the words of a string slice, where a word is a maximal run of grapheme clusters that are not
+// delimiters. A cluster counts as a delimiter only when `is_delimiter` accepts every char in it, so "\r\n" is a
+// delimiter but a space carrying a combining mark stays part of the surrounding word.
+pub struct Words<'a, F> {
+    s: &'a str,
+    front: usize,
+    back: usize,
+    is_delimiter: F,
+}
+
+impl<'a, F> Words<'a, F>
+where
+    F: Fn(char) -> bool,
+{
+    fn is_delimiter_cluster(&self, start: usize, end: usize) -> bool {
+        start < end && self.s[start..end].chars().all(&self.is_delimiter)
+    }
+}
+
+impl<'a, F> Iterator for Words<'a, F>
+where
+    F: Fn(char) -> bool,
+{
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        // Skip any leading delimiters.
+        while self.front < self.back {
+            let (start, end) = next_cluster(self.s, self.front);
+            if !self.is_delimiter_cluster(start, end) {
+                break;
+            }
+            self.front = end;
+        }
+
+        if self.front >= self.back {
+            return None;
+        }
+
+        let word_start = self.front;
+        while self.front < self.back {
+            let (start, end) = next_cluster(self.s, self.front);
+            if self.is_delimiter_cluster(start, end) {
+                break;
+            }
+            self.front = end;
+        }
+
+        Some(&self.s[word_start..self.front])
+    }
+}
+
+impl<'a, F> DoubleEndedIterator for Words<'a, F>
+where
+    F: Fn(char) -> bool,
+{
+    fn next_back(&mut self) -> Option<&'a str> {
+        // Skip any trailing delimiters.
+        while self.front < self.back {
+            let (start, end) = prev_cluster(self.s, self.back);
+            if !self.is_delimiter_cluster(start, end) {
+                break;
+            }
+            self.back = start;
+        }
+
+        if self.front >= self.back {
+            return None;
+        }
+
+        let word_end = self.back;
+        while self.front < self.back {
+            let (start, end) = prev_cluster(self.s, self.back);
+            if self.is_delimiter_cluster(start, end) {
+                break;
+            }
+            self.back = start;
+        }
+
+        Some(&self.s[self.back..word_end])
+    }
+}
+
+// words splits on Unicode whitespace (spaces, tabs, newlines, no-break spaces, ideographic spaces, ...).
+pub fn words(s: &str) -> Words<'_, fn(char) -> bool> {
+    words_by(s, char::is_whitespace)
+}
+
+// words_by splits on any char accepted by `is_delimiter`, e.g. `words_by(s, |c| c == ',' || c.is_whitespace())`.
+pub fn words_by<F>(s: &str, is_delimiter: F) -> Words<'_, F>
+where
+    F: Fn(char) -> bool,
+{
+    Words {
+        s,
+        front: 0,
+        back: s.len(),
+        is_delimiter,
+    }
+}
+
+// first_word returns the first whitespace separated word, or an empty slice when `s` has no words.
+pub fn first_word(s: &str) -> &str {
+    words(s).next().unwrap_or(&s[..0])
+}
+
+// last_word returns the last whitespace separated word, or an empty slice when `s` has no words.
+pub fn last_word(s: &str) -> &str {
+    words(s).next_back().unwrap_or(&s[s.len()..])
+}
+
+// nth_word returns the zero-indexed `n`th whitespace separated word, if there is one.
+pub fn nth_word(s: &str, n: usize) -> Option<&str> {
+    words(s).nth(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    // A pool of chars that exercise the tricky cases: ASCII and Unicode whitespace, CR and LF, multi-byte letters,
+    // combining marks, zero width joiners, emoji and regional indicators.
+    const POOL: &[char] = &[
+        'a', 'b', 'z', ' ', ' ', '\t', '\r', '\n', ',', '\u{00A0}', '\u{3000}', 'é', 'ß', '日', '本', '\u{0301}',
+        '\u{0308}', '\u{200D}', '\u{FE0F}', '👍', '\u{1F3FD}', '🙂', '\u{1F1FA}', '\u{1F1F8}',
+    ];
+
+    // assert_within checks that `word` points into `s`, not just that it's equal to some part of it.
+    fn assert_within(s: &str, word: &str) {
+        let range = s.as_bytes().as_ptr_range();
+        let word_range = word.as_bytes().as_ptr_range();
+        assert!(
+            range.start <= word_range.start && word_range.end <= range.end,
+            "{:?} is not a slice of {:?}",
+            word,
+            s
+        );
+        let offset = word_range.start as usize - range.start as usize;
+        assert!(s.is_char_boundary(offset) && s.is_char_boundary(offset + word.len()));
+    }
+
+    #[test]
+    fn every_word_is_a_slice_of_the_input() {
+        let mut random = Random(26);
+        for _ in 0..5000 {
+            let s = random.string(POOL, 23);
+            for word in words(&s).chain(words_by(&s, |c| c == ',' || c.is_whitespace())) {
+                assert!(!word.is_empty());
+                assert_within(&s, word);
+            }
+            assert_within(&s, first_word(&s));
+            assert_within(&s, last_word(&s));
+        }
+    }
+
+    #[test]
+    fn words_never_hold_a_delimiter() {
+        let mut random = Random(27);
+        for _ in 0..5000 {
+            let s = random.string(POOL, 23);
+            for word in words(&s) {
+                // A delimiter can only be inside a word when a combining char makes it part of a bigger cluster.
+                for cluster in word.graphemes(true) {
+                    assert!(!cluster.chars().all(char::is_whitespace), "{:?} in {:?}", word, s);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn backwards_gives_the_same_words_reversed() {
+        let mut random = Random(28);
+        for _ in 0..5000 {
+            let s = random.string(POOL, 23);
+            let forwards: Vec<&str> = words(&s).collect();
+            let mut backwards: Vec<&str> = words(&s).rev().collect();
+            backwards.reverse();
+            assert_eq!(forwards, backwards, "{:?}", s);
+
+            assert_eq!(first_word(&s), forwards.first().copied().unwrap_or(""));
+            assert_eq!(last_word(&s), forwards.last().copied().unwrap_or(""));
+            for (n, word) in forwards.iter().enumerate() {
+                assert_eq!(nth_word(&s, n), Some(*word));
+            }
+            assert_eq!(nth_word(&s, forwards.len()), None);
+        }
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(words("  hello\tworld\n").collect::<Vec<_>>(), ["hello", "world"]);
+        assert_eq!(words("東京\u{3000}大阪").collect::<Vec<_>>(), ["東京", "大阪"]);
+        assert_eq!(first_word("cafe\u{0301} au lait"), "cafe\u{0301}");
+        assert_eq!(last_word("thumbs 👍\u{1F3FD}"), "👍\u{1F3FD}");
+        assert_eq!(first_word(" \t\n"), "");
+        assert_eq!(words_by("a,b,,c", |c| c == ',').collect::<Vec<_>>(), ["a", "b", "c"]);
+        // The combining mark attaches to the space, so it doesn't split the words.
+        assert_eq!(words("a \u{0301}b").collect::<Vec<_>>(), ["a \u{0301}b"]);
+        assert_eq!(words("one\r\ntwo\r\n").collect::<Vec<_>>(), ["one", "two"]);
+        // Emoji joined by zero width joiners, and pairs of regional indicators, are one cluster each.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(last_word(&format!("the {}", family)), family);
+        assert_eq!(words_by("🇺🇸🇫🇷", |c| c == '\u{1F1EB}').collect::<Vec<_>>(), ["🇺🇸🇫🇷"]);
+        assert_eq!(words_by("a\u{200D},b", |c| c == ',').collect::<Vec<_>>(), ["a\u{200D}", "b"]);
+    }
+}
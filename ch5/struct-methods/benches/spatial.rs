@@ -0,0 +1,113 @@
+/*
+
+Compares QuadTree with checking every rectangle against every other one.
+
+Run with `cargo bench --bench spatial`. Both sides of each comparison must find the same rectangles, which the
+benchmark checks before reporting.
+
+*/
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use struct_methods::quadtree::{QuadTree, RectId};
+use struct_methods::rectangle::Rectangle;
+
+// The tests' Random isn't part of the library, so it's compiled into the benchmark from its source file.
+#[path = "../src/test_support.rs"]
+mod test_support;
+
+use test_support::Random;
+
+const RECTANGLES: usize = 10_000;
+const QUERIES: usize = 10_000;
+const AREA: u32 = 4_000;
+const MAX_SIZE: u32 = 40;
+
+fn rectangle(random: &mut Random) -> Rectangle {
+    Rectangle::new(random.below(MAX_SIZE) + 1, random.below(MAX_SIZE) + 1).at(random.below(AREA), random.below(AREA))
+}
+
+fn report<T, F: FnOnce() -> T>(name: &str, f: F) -> T {
+    let start = Instant::now();
+    let result = f();
+    println!("{:<40} {:>10.2?}", name, start.elapsed());
+    result
+}
+
+fn main() {
+    let mut random = Random(42);
+    let rectangles: Vec<Rectangle> = (0..RECTANGLES).map(|_| rectangle(&mut random)).collect();
+    let points: Vec<(u32, u32)> = (0..QUERIES).map(|_| (random.below(AREA), random.below(AREA))).collect();
+    let ranges: Vec<Rectangle> = (0..QUERIES).map(|_| rectangle(&mut random)).collect();
+
+    let tree = report("QuadTree: insert", || {
+        let mut tree = QuadTree::new(Rectangle::square(AREA + MAX_SIZE));
+        for rect in &rectangles {
+            tree.insert(*rect);
+        }
+        tree
+    });
+
+    let brute = report("brute force: overlapping pairs", || {
+        let mut pairs = Vec::new();
+        for (i, a) in rectangles.iter().enumerate() {
+            for (j, b) in rectangles.iter().enumerate().skip(i + 1) {
+                if a.intersects(b) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        black_box(pairs)
+    });
+    let indexed = report("QuadTree: overlapping pairs", || black_box(tree.overlapping_pairs()));
+    assert_eq!(brute.len(), indexed.len());
+    println!("{} overlapping pairs", indexed.len());
+
+    let brute = report("brute force: point queries", || {
+        let hits: usize = points
+            .iter()
+            .map(|&(x, y)| rectangles.iter().filter(|rect| rect.contains_point(x, y)).count())
+            .sum();
+        black_box(hits)
+    });
+    let indexed = report("QuadTree: point queries", || {
+        let hits: usize = points.iter().map(|&(x, y)| tree.at_point(x, y).len()).sum();
+        black_box(hits)
+    });
+    assert_eq!(brute, indexed);
+
+    let brute = report("brute force: range queries", || {
+        let hits: usize = ranges
+            .iter()
+            .map(|range| rectangles.iter().filter(|rect| rect.intersects(range)).count())
+            .sum();
+        black_box(hits)
+    });
+    let indexed = report("QuadTree: range queries", || {
+        let hits: usize = ranges.iter().map(|range| tree.in_range(range).len()).sum();
+        black_box(hits)
+    });
+    assert_eq!(brute, indexed);
+
+    // Removing every other rectangle and inserting it again shouldn't change any answer.
+    let mut tree = tree;
+    report("QuadTree: remove and reinsert half", || {
+        let ids: Vec<RectId> = tree.iter().map(|(id, _)| id).step_by(2).collect();
+        for id in ids {
+            let rect = tree.remove(id).expect("every id was inserted");
+            tree.insert(rect);
+        }
+    });
+    assert_eq!(tree.overlapping_pairs().len(), brute_pairs(&tree));
+}
+
+// brute_pairs counts the overlapping pairs in `tree` the slow way.
+fn brute_pairs(tree: &QuadTree) -> usize {
+    let rectangles: Vec<&Rectangle> = tree.iter().map(|(_, rect)| rect).collect();
+    let mut count = 0;
+    for (i, a) in rectangles.iter().enumerate() {
+        count += rectangles[i + 1..].iter().filter(|b| a.intersects(b)).count();
+    }
+    count
+}
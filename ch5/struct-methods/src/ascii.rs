@@ -0,0 +1,175 @@
+/*
+
+Box art for rectangles in a terminal.
+
+draw scales a scene of rectangles down (or up) to a number of columns and draws each as a box:
+
++-------------+
+|             |
+|    30x50    |
+|             |
++-------------+
+
+Terminal cells are roughly twice as tall as they are wide, so rows are scaled by half as much as columns, which
+keeps squares looking square. A drawing is never more than MAX_ROWS rows tall: a scene too tall for that is
+scaled down further, and comes out narrower than asked for. Boxes are drawn in order and only their outlines are
+drawn, so overlapping rectangles stay visible. Every rectangle is at least one cell wide and tall however small it
+is, but rectangles closer together than one cell can merge.
+
+*/
+
+use crate::rectangle::Rectangle;
+
+pub const DEFAULT_COLUMNS: usize = 80;
+pub const MAX_ROWS: usize = 60;
+
+struct Canvas {
+    cells: Vec<Vec<char>>,
+}
+
+impl Canvas {
+    fn set(&mut self, row: usize, column: usize, c: char) {
+        let cell = &mut self.cells[row][column];
+        // Where two boxes' edges cross, the corner wins, so the crossing reads as a join.
+        *cell = match (*cell, c) {
+            ('-', '|') | ('|', '-') => '+',
+            ('+', _) => '+',
+            _ => c,
+        };
+    }
+
+    fn draw_box(&mut self, left: usize, top: usize, right: usize, bottom: usize) {
+        for column in left..=right {
+            self.set(top, column, '-');
+            self.set(bottom, column, '-');
+        }
+        for row in top..=bottom {
+            self.set(row, left, '|');
+            self.set(row, right, '|');
+        }
+        for &(row, column) in &[(top, left), (top, right), (bottom, left), (bottom, right)] {
+            self.cells[row][column] = '+';
+        }
+    }
+
+    // write puts `text` centered between `left` and `right`, if it fits between them.
+    fn write(&mut self, row: usize, left: usize, right: usize, text: &str) {
+        let len = text.chars().count();
+        if right - left < len + 1 {
+            return;
+        }
+        let start = left + (right - left - len).div_ceil(2);
+        for (i, c) in text.chars().enumerate() {
+            self.cells[row][start + i] = c;
+        }
+    }
+}
+
+// scaled maps a coordinate onto a cell, keeping it within `cells`.
+fn scaled(coordinate: u64, scale: f64, cells: usize) -> usize {
+    ((coordinate as f64 * scale).round() as usize).min(cells - 1)
+}
+
+// draw renders `rectangles`, from the origin to their furthest edges, `columns` characters wide, or narrower if
+// that would take more than MAX_ROWS rows. Each rectangle that is big enough is labelled with its size and position.
+pub fn draw(rectangles: &[Rectangle], columns: usize) -> String {
+    let width = rectangles.iter().map(Rectangle::right).max().unwrap_or(0);
+    let height = rectangles.iter().map(Rectangle::bottom).max().unwrap_or(0);
+    if width == 0 || height == 0 || columns < 2 {
+        return String::new();
+    }
+
+    let x_scale = f64::min((columns - 1) as f64 / width as f64, 2.0 * (MAX_ROWS - 1) as f64 / height as f64);
+    let y_scale = x_scale / 2.0;
+    let rows = ((height as f64 * y_scale).round() as usize).max(1) + 1;
+    let mut canvas = Canvas {
+        cells: vec![vec![' '; columns]; rows],
+    };
+
+    let mut labels = Vec::new();
+    for rect in rectangles.iter().filter(|rect| !rect.is_empty()) {
+        let mut left = scaled(rect.x as u64, x_scale, columns);
+        let right = scaled(rect.right(), x_scale, columns).max(left + 1).min(columns - 1);
+        left = left.min(right - 1);
+        let mut top = scaled(rect.y as u64, y_scale, rows);
+        let bottom = scaled(rect.bottom(), y_scale, rows).max(top + 1).min(rows - 1);
+        top = top.min(bottom - 1);
+
+        canvas.draw_box(left, top, right, bottom);
+        if bottom - top >= 2 {
+            labels.push(((top + bottom) / 2, left, right, rect.to_string()));
+        }
+    }
+    // Labels go on top of every box, so later boxes can't cut through them.
+    for (row, left, right, label) in labels {
+        canvas.write(row, left, right, &label);
+    }
+
+    let mut art = String::new();
+    for row in canvas.cells {
+        let line: String = row.into_iter().collect();
+        art.push_str(line.trim_end());
+        art.push('\n');
+    }
+    art
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes() {
+        assert_eq!(draw(&[Rectangle::new(4, 2)], 9), "+-------+\n|  4x2  |\n+-------+\n");
+        assert_eq!(
+            draw(&[Rectangle::new(30, 20), Rectangle::new(20, 10).at(20, 10)], 31),
+            concat!(
+                "+----------------------+\n",
+                "|                      |\n",
+                "|                      |\n",
+                "|                      |\n",
+                "|        30x20 +-------+------+\n",
+                "|              |       |      |\n",
+                "|              | 20x10@20,10  |\n",
+                "|              |       |      |\n",
+                "+--------------+-------+------+\n",
+            )
+        );
+    }
+
+    #[test]
+    fn nothing_to_draw() {
+        assert_eq!(draw(&[], 80), "");
+        assert_eq!(draw(&[Rectangle::new(0, 5)], 80), "");
+        assert_eq!(draw(&[Rectangle::new(5, 5)], 1), "");
+    }
+
+    #[test]
+    fn stays_within_the_width_and_rows() {
+        let scenes = [
+            vec![Rectangle::new(1, 1)],
+            vec![Rectangle::new(1000, 1)],
+            vec![Rectangle::new(1, 2_000_000)],
+            vec![Rectangle::new(u32::MAX, u32::MAX), Rectangle::new(3, 3).at(u32::MAX, u32::MAX)],
+            vec![Rectangle::new(30, 50), Rectangle::new(5, 5).at(28, 1), Rectangle::new(1, 1).at(100, 2)],
+        ];
+        for rectangles in &scenes {
+            for &columns in &[2, 3, 10, 80, 200] {
+                let art = draw(rectangles, columns);
+                assert!(art.lines().count() <= MAX_ROWS, "{:?} at {} columns", rectangles, columns);
+                assert!(
+                    art.lines().all(|line| line.chars().count() <= columns),
+                    "{:?} at {} columns:\n{}",
+                    rectangles,
+                    columns,
+                    art
+                );
+            }
+        }
+
+        // A very tall rectangle comes out narrow rather than two million rows long.
+        let art = format!("{:#}", Rectangle::new(1, 2_000_000));
+        assert_eq!(art.lines().count(), MAX_ROWS);
+        assert!(art.lines().all(|line| line == "++" || line == "||"));
+    }
+}
@@ -0,0 +1,91 @@
+/*
+
+Draws rectangles given on the command line.
+
+draw [--columns N] [--svg FILE] RECT...
+
+Each RECT is `WIDTHxHEIGHT` or `WIDTHxHEIGHT@X,Y`, e.g. `draw 30x50 10x40@5,20 60x45@20,10`. The scene is drawn as
+box art as wide as the terminal, going by $COLUMNS, or N columns, and `--svg` also writes it out as an SVG image.
+
+*/
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+use struct_methods::ascii::{self, DEFAULT_COLUMNS};
+use struct_methods::rectangle::Rectangle;
+use struct_methods::svg::Svg;
+
+const USAGE: &str = "usage: draw [--columns N] [--svg FILE] RECT...";
+
+struct Options {
+    columns: usize,
+    svg: Option<String>,
+    rectangles: Vec<Rectangle>,
+}
+
+// terminal_columns reads the terminal width from $COLUMNS, which most shells set.
+fn terminal_columns() -> usize {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_COLUMNS)
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut columns = None;
+    let mut svg = None;
+    let mut rectangles = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--columns" => {
+                let value = args.next().ok_or("--columns needs a value")?;
+                columns = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("--columns must be a whole number, not {:?}", value))?,
+                );
+            }
+            "--svg" => svg = Some(args.next().ok_or("--svg needs a file")?.clone()),
+            _ => rectangles.push(arg.parse().map_err(|e| format!("{:?}: {}", arg, e))?),
+        }
+    }
+
+    if rectangles.is_empty() {
+        return Err(String::from("no rectangles given"));
+    }
+    Ok(Options {
+        columns: columns.unwrap_or_else(terminal_columns),
+        svg,
+        rectangles,
+    })
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    print!("{}", ascii::draw(&options.rectangles, options.columns));
+    if let Some(path) = &options.svg {
+        fs::write(path, Svg::scene(&options.rectangles).to_string())?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("draw: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        eprintln!("draw: {}", e);
+        process::exit(1);
+    }
+}
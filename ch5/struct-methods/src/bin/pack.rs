@@ -0,0 +1,233 @@
+/*
+
+Packs rectangles read from CSV into a container and reports where each one went.
+
+pack (--container WxH | --width W --height H) [--algorithm shelf|guillotine|maxrects] [--rotate] [--svg FILE] [CSV]
+
+Each CSV row is either `width,height` or `label,width,height`. Blank lines and lines starting with '#' are skipped,
+and so is a header row. Rows are numbered from 1 in the output unless they have a label. Without a file, the CSV is
+read from stdin. The algorithm defaults to maxrects; see `src/packing.rs` for how they differ. `--svg` also writes
+the packing out as an SVG image.
+
+*/
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use struct_methods::fit::Orientation;
+use struct_methods::packing::{self, Algorithm};
+use struct_methods::rectangle::Rectangle;
+
+const USAGE: &str = "usage: pack (--container WxH | --width W --height H) \
+                     [--algorithm shelf|guillotine|maxrects] [--rotate] [--svg FILE] [CSV]";
+
+struct Options {
+    width: u32,
+    height: u32,
+    algorithm: Algorithm,
+    rotate: bool,
+    svg: Option<String>,
+    path: Option<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut width = None;
+    let mut height = None;
+    let mut algorithm = Algorithm::MaxRects;
+    let mut rotate = false;
+    let mut svg = None;
+    let mut path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rotate" => rotate = true,
+            "--container" | "--width" | "--height" | "--algorithm" | "--svg" => {
+                let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+                let dimension = || {
+                    value
+                        .parse::<u32>()
+                        .map_err(|_| format!("{} must be a whole number, not {:?}", arg, value))
+                };
+                match arg.as_str() {
+                    "--container" => {
+                        let container: Rectangle = value.parse().map_err(|e| format!("--container: {}", e))?;
+                        width = Some(container.width);
+                        height = Some(container.height);
+                    }
+                    "--width" => width = Some(dimension()?),
+                    "--height" => height = Some(dimension()?),
+                    "--algorithm" => algorithm = value.parse()?,
+                    _ => svg = Some(value.clone()),
+                }
+            }
+            _ if path.is_none() => path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    Ok(Options {
+        width: width.ok_or("--container or --width is required")?,
+        height: height.ok_or("--container or --height is required")?,
+        algorithm,
+        rotate,
+        svg,
+        path,
+    })
+}
+
+// parse_csv reads rectangles and their labels. Unlabelled rows are labelled with their position in the list.
+fn parse_csv(csv: &str) -> Result<(Vec<Rectangle>, Vec<String>), String> {
+    let mut rectangles = Vec::new();
+    let mut labels = Vec::new();
+    let header = first_row(csv);
+
+    for (i, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let (label, width, height) = match fields[..] {
+            [width, height] => (None, width, height),
+            [label, width, height] => (Some(label), width, height),
+            _ => return Err(format!("line {}: expected `width,height` or `label,width,height`", i + 1)),
+        };
+        let size = (width.parse::<u32>(), height.parse::<u32>());
+        let (width, height) = match size {
+            (Ok(width), Ok(height)) => (width, height),
+            // Only the first row can be a header.
+            _ if i == header => continue,
+            _ => return Err(format!("line {}: {:?} is not a valid size", i + 1, line)),
+        };
+
+        rectangles.push(Rectangle::new(width, height));
+        labels.push(label.map_or_else(|| rectangles.len().to_string(), str::to_string));
+    }
+    Ok((rectangles, labels))
+}
+
+// first_row returns the index of the first line that isn't blank or a comment.
+fn first_row(csv: &str) -> usize {
+    csv.lines()
+        .position(|line| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .unwrap_or(0)
+}
+
+fn run(options: Options) -> Result<(), Box<dyn Error>> {
+    let csv = match &options.path {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut csv = String::new();
+            io::stdin().read_to_string(&mut csv)?;
+            csv
+        }
+    };
+    let (rectangles, labels) = parse_csv(&csv)?;
+
+    let container = Rectangle::new(options.width, options.height);
+    let packing = packing::pack(&container, &rectangles, options.algorithm, options.rotate);
+
+    for placement in &packing.placements {
+        let rect = &placement.rect;
+        let rotated = match placement.orientation {
+            Orientation::Upright => "",
+            Orientation::Rotated => " (rotated)",
+        };
+        println!(
+            "{}: {}x{} at {},{}{}",
+            labels[placement.index], rect.width, rect.height, rect.x, rect.y, rotated
+        );
+    }
+    for &index in &packing.unplaced {
+        let rect = &rectangles[index];
+        println!("{}: {}x{} doesn't fit", labels[index], rect.width, rect.height);
+    }
+    println!(
+        "{}: placed {} of {}, {:.1}% used, {} wasted",
+        options.algorithm,
+        packing.placements.len(),
+        rectangles.len(),
+        packing.utilization() * 100.0,
+        packing.wasted_area()
+    );
+
+    if let Some(path) = &options.svg {
+        fs::write(path, packing.to_svg(|index| labels[index].clone()).to_string())?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("pack: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options) {
+        eprintln!("pack: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(csv: &str) -> Vec<String> {
+        parse_csv(csv).unwrap().1
+    }
+
+    #[test]
+    fn rows_with_and_without_labels() {
+        let (rectangles, labels) = parse_csv("3,4\n door , 5 , 6 \n7,8\n").unwrap();
+        assert_eq!(rectangles, vec![Rectangle::new(3, 4), Rectangle::new(5, 6), Rectangle::new(7, 8)]);
+        // Unlabelled rows are numbered by their place in the list.
+        assert_eq!(labels, vec!["1", "door", "3"]);
+        assert_eq!(parse_csv(""), Ok((Vec::new(), Vec::new())));
+        assert_eq!(parse_csv("# nothing yet\n\n"), Ok((Vec::new(), Vec::new())));
+    }
+
+    #[test]
+    fn header_rows() {
+        assert_eq!(labels("width,height\n3,4\n"), vec!["1"]);
+        assert_eq!(labels("name,width,height\nshelf,3,4\n"), vec!["shelf"]);
+        // The header is the first row that isn't blank or a comment.
+        assert_eq!(labels("# parts\n\n  name, w, h\nshelf,3,4\n"), vec!["shelf"]);
+        // A first row that is a valid size is a rectangle, not a header.
+        assert_eq!(labels("1,2\n3,4\n"), vec!["1", "2"]);
+    }
+
+    #[test]
+    fn invalid_rows() {
+        assert_eq!(
+            parse_csv("3,4\nwidth,height\n"),
+            Err(String::from("line 2: \"width,height\" is not a valid size"))
+        );
+        assert_eq!(
+            parse_csv("w,h\nx,y\n"),
+            Err(String::from("line 2: \"x,y\" is not a valid size"))
+        );
+        assert_eq!(
+            parse_csv("3,4\n\n1,2,3,4\n"),
+            Err(String::from("line 3: expected `width,height` or `label,width,height`"))
+        );
+        assert_eq!(
+            parse_csv("3\n"),
+            Err(String::from("line 1: expected `width,height` or `label,width,height`"))
+        );
+        assert!(parse_csv("3,4\n-1,2\n").is_err());
+    }
+}
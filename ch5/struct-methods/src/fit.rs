@@ -0,0 +1,202 @@
+/*
+
+Whether one rectangle fits inside another.
+
+The chapter's `can_hold` compared with `>` in both directions, so a rectangle couldn't hold an identical one, and a
+10x40 rectangle couldn't hold a 40x10 one even though turning it on its side would work. When fitting UI panels into
+containers, a panel exactly as big as its container fits, so `can_hold` now compares inclusively, and the rules are
+configurable through Fit:
+
+                                     Fit::INCLUSIVE   Fit::STRICT   Fit::INCLUSIVE.rotated()
+30x50 holding 30x50                  yes              no            yes
+30x50 holding 10x40                  yes              yes           yes
+30x50 holding 50x30                  no               no            yes
+30x50 holding 31x10                  no               no            yes
+
+Only sizes are compared here; positions are ignored. `Rectangle::contains` is the check for one positioned
+rectangle lying inside another.
+
+*/
+
+use crate::rectangle::Rectangle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    // The inner rectangle must be smaller in both dimensions.
+    Strict,
+    // The inner rectangle may be as wide or as tall as the outer one.
+    Inclusive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fit {
+    pub bound: Bound,
+    // Whether the inner rectangle may be turned 90 degrees to make it fit.
+    pub allow_rotation: bool,
+}
+
+impl Fit {
+    pub const STRICT: Fit = Fit {
+        bound: Bound::Strict,
+        allow_rotation: false,
+    };
+
+    pub const INCLUSIVE: Fit = Fit {
+        bound: Bound::Inclusive,
+        allow_rotation: false,
+    };
+
+    pub fn rotated(self) -> Fit {
+        Fit {
+            allow_rotation: true,
+            ..self
+        }
+    }
+}
+
+impl Default for Fit {
+    fn default() -> Fit {
+        Fit::INCLUSIVE
+    }
+}
+
+// How a rectangle has to be turned to fit somewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    Upright,
+    Rotated,
+}
+
+impl Bound {
+    // allows reports whether an inner dimension of `inner` fits an outer dimension of `outer`.
+    fn allows(self, outer: u64, inner: u64) -> bool {
+        match self {
+            Bound::Strict => inner < outer,
+            Bound::Inclusive => inner <= outer,
+        }
+    }
+}
+
+impl Rectangle {
+    // orientation_in returns how this rectangle fits inside `container` under `fit`, preferring Upright when both
+    // work, or None if it doesn't fit at all.
+    pub fn orientation_in(&self, container: &Rectangle, fit: Fit) -> Option<Orientation> {
+        self.orientation_in_size(container.width as u64, container.height as u64, fit)
+    }
+
+    fn orientation_in_size(&self, width: u64, height: u64, fit: Fit) -> Option<Orientation> {
+        let (w, h) = (self.width as u64, self.height as u64);
+        if fit.bound.allows(width, w) && fit.bound.allows(height, h) {
+            Some(Orientation::Upright)
+        } else if fit.allow_rotation && fit.bound.allows(width, h) && fit.bound.allows(height, w) {
+            Some(Orientation::Rotated)
+        } else {
+            None
+        }
+    }
+
+    pub fn can_hold_with(&self, other: &Rectangle, fit: Fit) -> bool {
+        other.orientation_in(self, fit).is_some()
+    }
+
+    // fits_with_margin reports whether `other` fits inside this rectangle with at least `margin` to spare on every
+    // side, e.g. a panel inside a container with padding.
+    pub fn fits_with_margin(&self, other: &Rectangle, margin: u32, fit: Fit) -> bool {
+        let inside = |dimension: u32| (dimension as u64).checked_sub(2 * margin as u64);
+        match (inside(self.width), inside(self.height)) {
+            (Some(width), Some(height)) => other.orientation_in_size(width, height, fit).is_some(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROTATED: Fit = Fit {
+        bound: Bound::Inclusive,
+        allow_rotation: true,
+    };
+
+    const STRICT_ROTATED: Fit = Fit {
+        bound: Bound::Strict,
+        allow_rotation: true,
+    };
+
+    // fits checks `outer` holding `inner` under each rule, in the order INCLUSIVE, STRICT, INCLUSIVE.rotated() and
+    // STRICT.rotated().
+    fn fits(outer: Rectangle, inner: Rectangle) -> [bool; 4] {
+        [Fit::INCLUSIVE, Fit::STRICT, ROTATED, STRICT_ROTATED].map(|fit| outer.can_hold_with(&inner, fit))
+    }
+
+    #[test]
+    fn the_table_in_the_header() {
+        let outer = Rectangle::new(30, 50);
+        assert_eq!(fits(outer, Rectangle::new(30, 50)), [true, false, true, false]);
+        assert_eq!(fits(outer, Rectangle::new(10, 40)), [true, true, true, true]);
+        assert_eq!(fits(outer, Rectangle::new(50, 30)), [false, false, true, false]);
+        assert_eq!(fits(outer, Rectangle::new(31, 10)), [false, false, true, true]);
+        assert_eq!(Fit::default(), Fit::INCLUSIVE);
+        assert_eq!(Fit::INCLUSIVE.rotated(), ROTATED);
+        assert_eq!(Fit::STRICT.rotated(), STRICT_ROTATED);
+    }
+
+    #[test]
+    fn equal_sizes() {
+        // Inclusive holds an identical rectangle and strict never does, even turned. Matching in one dimension is
+        // enough to fail strict, unless turning gets it clear in both.
+        let square = Rectangle::square(20);
+        assert_eq!(fits(square, square), [true, false, true, false]);
+        assert_eq!(fits(Rectangle::new(20, 30), Rectangle::new(20, 10)), [true, false, true, true]);
+        assert_eq!(fits(Rectangle::new(20, 30), Rectangle::new(10, 30)), [true, false, true, false]);
+        assert!(square.can_hold(&square));
+    }
+
+    #[test]
+    fn zero_sizes() {
+        let empty = Rectangle::new(0, 0);
+        let line = Rectangle::new(0, 5);
+        // Nothing is smaller than zero, so under strict rules an empty rectangle can't hold anything, not even
+        // another empty one, while inclusive rules let it hold empty rectangles.
+        assert_eq!(fits(empty, empty), [true, false, true, false]);
+        assert_eq!(fits(empty, Rectangle::new(1, 1)), [false; 4]);
+        assert_eq!(fits(line, empty), [true, false, true, false]);
+        assert_eq!(fits(line, Rectangle::new(5, 0)), [false, false, true, false]);
+        // Anything non-empty can strictly hold a zero sized rectangle smaller than itself.
+        assert_eq!(fits(Rectangle::new(1, 1), empty), [true; 4]);
+        assert_eq!(fits(Rectangle::new(10, 10), line), [true; 4]);
+    }
+
+    #[test]
+    fn rotation() {
+        let tall = Rectangle::new(10, 40);
+        let wide = Rectangle::new(40, 10);
+        assert_eq!(tall.orientation_in(&wide, Fit::INCLUSIVE), None);
+        assert_eq!(tall.orientation_in(&wide, ROTATED), Some(Orientation::Rotated));
+        // Upright is preferred when both orientations work.
+        assert_eq!(Rectangle::square(5).orientation_in(&Rectangle::square(5), ROTATED), Some(Orientation::Upright));
+        assert_eq!(tall.orientation_in(&Rectangle::new(40, 40), ROTATED), Some(Orientation::Upright));
+        // Turning doesn't help what's too big both ways.
+        assert_eq!(Rectangle::new(50, 5).orientation_in(&Rectangle::new(40, 40), ROTATED), None);
+        // Positions are ignored.
+        assert_eq!(tall.at(1000, 1000).orientation_in(&wide, ROTATED), Some(Orientation::Rotated));
+    }
+
+    #[test]
+    fn margins() {
+        let container = Rectangle::new(20, 30);
+        assert!(container.fits_with_margin(&Rectangle::new(10, 20), 5, Fit::INCLUSIVE));
+        assert!(!container.fits_with_margin(&Rectangle::new(10, 20), 5, Fit::STRICT));
+        assert!(!container.fits_with_margin(&Rectangle::new(20, 10), 5, Fit::INCLUSIVE));
+        assert!(container.fits_with_margin(&Rectangle::new(20, 10), 5, ROTATED));
+        // A margin can use up the whole container, leaving room for only an empty rectangle, but not more than that.
+        assert!(container.fits_with_margin(&Rectangle::new(0, 0), 10, Fit::INCLUSIVE));
+        assert!(!container.fits_with_margin(&Rectangle::new(0, 0), 11, Fit::INCLUSIVE));
+        assert!(!container.fits_with_margin(&Rectangle::new(0, 0), u32::MAX, ROTATED));
+        let huge = Rectangle::new(u32::MAX, u32::MAX);
+        // Twice the margin doesn't overflow, and leaves 1x1 here.
+        assert!(huge.fits_with_margin(&Rectangle::new(1, 1), u32::MAX / 2, Fit::INCLUSIVE));
+        assert!(!huge.fits_with_margin(&Rectangle::new(1, 1), u32::MAX / 2, Fit::STRICT));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod ascii;
+pub mod fit;
+pub mod packing;
+pub mod quadtree;
+pub mod rectangle;
+pub mod shape;
+pub mod svg;
+#[cfg(test)]
+mod test_support;
@@ -1,43 +1,32 @@
-#[derive(Debug)]
-struct Rectangle {
-    width: u32,
-    height: u32,
-}
-
-impl Rectangle {
-    fn area(&self) -> u32 {
-        self.width * self.height
-    }
+use std::error::Error;
 
-    fn can_hold(&self, other: &Rectangle) -> bool {
-        self.width > other.width && self.height > other.height
-    }
-
-}
+use struct_methods::ascii;
+use struct_methods::fit::Fit;
+use struct_methods::packing::{self, Algorithm};
+use struct_methods::quadtree::QuadTree;
+use struct_methods::rectangle::Rectangle;
+use struct_methods::shape::{Circle, Point, Polygon, Scene, Square, Triangle};
 
-// We can also define multiple impl blocks for the same struct:
-impl Rectangle {
-    // Not all impl functions require taking `self` as the first parameter:
-    fn square(size: u32) -> Rectangle {
-        Rectangle {
-            width: size,
-            height: size,
-        }
-    }
-}
+// Rectangle and its methods live in `src/rectangle.rs`.
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     let rect1 = Rectangle {
+        x: 0,
+        y: 0,
         width: 30,
         height: 50,
     };
 
     let rect2 = Rectangle {
+        x: 5,
+        y: 20,
         width: 10,
         height: 40,
     };
 
     let rect3 = Rectangle {
+        x: 20,
+        y: 10,
         width: 60,
         height: 45,
     };
@@ -53,4 +42,103 @@ fn main() {
 
     println!("Square is {:?}", sq);
 
+    // can_hold is inclusive and doesn't turn rectangles; can_hold_with takes other rules.
+    let rotated = Rectangle::new(rect2.height, rect2.width);
+    println!("Can rect1 hold a copy of itself? {}", rect1.can_hold(&rect1));
+    println!("Strictly? {}", rect1.can_hold_with(&rect1, Fit::STRICT));
+    println!("Can rect2 hold itself turned on its side? {}", rect2.can_hold(&rotated));
+    println!("If it may turn it back? {:?}", rotated.orientation_in(&rect2, Fit::INCLUSIVE.rotated()));
+    println!("Can rect1 hold rect2 with a margin of 10? {}", rect1.fits_with_margin(&rect2, 10, Fit::INCLUSIVE));
+    println!("With a margin of 5? {}", rect1.fits_with_margin(&rect2, 5, Fit::INCLUSIVE));
+
+    // Rectangles have a position too, so we can ask how they relate to each other.
+    println!("rect1 perimeter: {}, aspect ratio: {:?}", rect1.perimeter(), rect1.aspect_ratio());
+    println!("rect1 and rect3 intersect? {}", rect1.intersects(&rect3));
+    println!("rect1 ∩ rect3: {:?}", rect1.intersection(&rect3));
+    println!("rect1 ∪ rect3: {:?}", rect1.union(&rect3));
+    println!("bounding box: {:?}", Rectangle::bounding_box(&[rect1, rect2, rect3]));
+    println!("rect1 contains (29, 49)? {}", rect1.contains_point(29, 49));
+    println!("rect1 contains (30, 50)? {}", rect1.contains_point(30, 50));
+    println!("rect1 contains rect2? {}", rect1.contains(&rect2));
+    println!("rect2 moved by (-5, 10): {:?}", rect2.translate(-5, 10));
+    println!("rect2 moved by (-10, 0): {:?}", rect2.translate(-10, 0));
+    println!("sq scaled by 4: {:?}", sq.scale(4));
+
+    // can_hold generalizes to packing many rectangles into one. `src/bin/pack.rs` does this for rectangles from CSV.
+    let panels = [rect1, rect2, rect3, sq, Rectangle::new(45, 60)];
+    let container = Rectangle::new(100, 100);
+    for &algorithm in &[Algorithm::Shelf, Algorithm::Guillotine, Algorithm::MaxRects] {
+        let packing = packing::pack(&container, &panels, algorithm, true);
+        println!(
+            "{}: placed {} of {} panels, {} square pixels wasted",
+            algorithm,
+            packing.placements.len(),
+            panels.len(),
+            packing.wasted_area()
+        );
+    }
+
+    // The Shape trait lets different kinds of shapes share a Vec<Box<dyn Shape>>.
+    let mut scene = Scene::new();
+    scene.add(Rectangle::new(100, 60));
+    scene.add(Square { x: 10, y: 10, size: 20 });
+    scene.add(Circle::new(Point::new(70.0, 30.0), 15.0).expect("the radius is positive"));
+    // A 3-4-5 right triangle, with area 6.
+    scene.add(Triangle(Point::new(40.0, 40.0), Point::new(43.0, 40.0), Point::new(40.0, 44.0)));
+    // An L shape: a 20x20 square with a 10x10 corner cut out, so its area is 300.
+    let l_shape = vec![
+        Point::new(0.0, 0.0),
+        Point::new(20.0, 0.0),
+        Point::new(20.0, 10.0),
+        Point::new(10.0, 10.0),
+        Point::new(10.0, 20.0),
+        Point::new(0.0, 20.0),
+    ];
+    scene.add(Polygon::new(l_shape)?);
+    scene.add(Square { x: 12, y: 12, size: 6 });
+
+    for (i, shape) in scene.shapes().iter().enumerate() {
+        println!(
+            "{}: {} with area {:.2} and perimeter {:.2}",
+            i,
+            shape.name(),
+            shape.area(),
+            shape.perimeter()
+        );
+    }
+    println!("total area: {:.2}, bounding box: {:?}", scene.total_area(), scene.bounding_box());
+    println!("shapes at (15, 15): {:?}", scene.shapes_at(Point::new(15.0, 15.0)));
+    for (outer, inner) in scene.holdings() {
+        println!("{} {} can hold {} {}", scene.shapes()[outer].name(), outer, scene.shapes()[inner].name(), inner);
+    }
+    if let Err(e) = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)]) {
+        println!("{}", e);
+    }
+
+    // A QuadTree finds overlaps and hits without comparing every rectangle with every other one.
+    let mut tree = QuadTree::new(Rectangle::square(100));
+    let ids: Vec<_> = [rect1, rect2, rect3, sq].iter().map(|&rect| tree.insert(rect)).collect();
+    println!("overlapping pairs: {:?}", tree.overlapping_pairs());
+    println!("at (25, 30): {:?}", tree.at_point(25, 30));
+    println!("in 10x10 at (0, 0): {:?}", tree.in_range(&Rectangle::new(10, 10)));
+    tree.remove(ids[0]);
+    println!("after removing {:?}: {:?}", ids[0], tree.overlapping_pairs());
+
+    // Rectangles can be written out and parsed back, or drawn. `src/bin/draw.rs` draws rectangles given like this.
+    for spec in &["30x50", "10x40@5,20", " 60x45 @ 20 , 10 ", "30by50", "30x50@10", "30x-5"] {
+        match spec.parse::<Rectangle>() {
+            Ok(rect) => println!("{:?} parses to {:?}, displayed as {}", spec, rect, rect),
+            Err(e) => println!("{:?}: {}", spec, e),
+        }
+    }
+    println!("{:#24}", rect1);
+    print!("{}", ascii::draw(&[rect1, rect2, rect3], 60));
+
+    // None of these overflow, even at the edge of the u32 range.
+    let huge = Rectangle::square(u32::MAX).at(u32::MAX, u32::MAX);
+    println!("huge area: {}, bottom right corner: ({}, {})", huge.area(), huge.right(), huge.bottom());
+    println!("huge ∪ rect1: {:?}", huge.union(&rect1));
+    println!("huge scaled by 2: {:?}", huge.scale(2));
+
+    Ok(())
 }
@@ -0,0 +1,499 @@
+/*
+
+2D bin packing: placing as many rectangles as possible inside a container without overlaps.
+
+Finding the best packing is NP-hard, so `pack` uses one of three well known heuristics, each trading speed for
+how tightly it packs:
+
+* Shelf splits the container into horizontal shelves, each as tall as the first rectangle placed on it, and fills
+  them left to right. It is the simplest and fastest, but wastes the space above shorter rectangles on a shelf.
+* Guillotine keeps a list of free rectangles. Each placement goes into the free rectangle it fills best, and the
+  rest of that free rectangle is cut in two with a single straight cut, like a guillotine.
+* MaxRects also keeps a list of free rectangles, but they are the largest empty rectangles and may overlap each
+  other, so no space is lost to an early cut. It usually packs tightest and is the slowest.
+
+All three place the rectangles largest first, which packs far better than taking them in the order given. With
+rotation allowed, a rectangle may be turned 90 degrees when that fits better.
+
+Placements are relative to the container's top left corner, whatever the container's own position is.
+
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::fit::Orientation;
+use crate::rectangle::Rectangle;
+use crate::svg::{self, Svg};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Shelf,
+    Guillotine,
+    MaxRects,
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::Shelf => "shelf",
+            Algorithm::Guillotine => "guillotine",
+            Algorithm::MaxRects => "maxrects",
+        })
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Algorithm, String> {
+        match s {
+            "shelf" => Ok(Algorithm::Shelf),
+            "guillotine" => Ok(Algorithm::Guillotine),
+            "maxrects" => Ok(Algorithm::MaxRects),
+            _ => Err(format!(
+                "unknown packing algorithm {:?}; expected shelf, guillotine or maxrects",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    // The position of the rectangle in the list given to `pack`.
+    pub index: usize,
+    // Where the rectangle went, with its width and height swapped if it was rotated.
+    pub rect: Rectangle,
+    pub orientation: Orientation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packing {
+    pub container: Rectangle,
+    pub placements: Vec<Placement>,
+    // Indexes of the rectangles that didn't fit.
+    pub unplaced: Vec<usize>,
+}
+
+impl Packing {
+    pub fn used_area(&self) -> u64 {
+        self.placements.iter().map(|placement| placement.rect.area()).sum()
+    }
+
+    pub fn wasted_area(&self) -> u64 {
+        self.container.area() - self.used_area()
+    }
+
+    // utilization is the fraction of the container that is covered, from 0 to 1.
+    pub fn utilization(&self) -> f64 {
+        match self.container.area() {
+            0 => 0.0,
+            area => self.used_area() as f64 / area as f64,
+        }
+    }
+
+    // to_svg draws the container and every placement, labelling each placement with `label(index)`.
+    pub fn to_svg<F>(&self, label: F) -> Svg
+    where
+        F: Fn(usize) -> String,
+    {
+        let (width, height) = (self.container.width, self.container.height);
+        let mut svg = Svg::new(width as u64, height as u64);
+        svg.rect(&Rectangle::new(width, height), "white");
+        for placement in &self.placements {
+            let rect = &placement.rect;
+            svg.rect(rect, &svg::fill(placement.index));
+            svg.label(
+                rect.x as u64 + rect.width as u64 / 2,
+                rect.y as u64 + rect.height as u64 / 2,
+                &label(placement.index),
+            );
+        }
+        svg
+    }
+}
+
+// orientations lists the sizes `item` can be placed at, upright first.
+fn orientations(item: &Rectangle, rotation: bool) -> Vec<(u32, u32, Orientation)> {
+    let mut sizes = vec![(item.width, item.height, Orientation::Upright)];
+    if rotation && item.width != item.height {
+        sizes.push((item.height, item.width, Orientation::Rotated));
+    }
+    sizes
+}
+
+// largest_first returns the indexes of `items`, ordered by `key` from largest to smallest. The sort is stable, so
+// equal items keep their order.
+fn largest_first<K: Ord>(items: &[Rectangle], key: impl Fn(&Rectangle) -> K) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| key(&items[b]).cmp(&key(&items[a])));
+    order
+}
+
+fn placement(index: usize, x: u32, y: u32, (width, height, orientation): (u32, u32, Orientation)) -> Placement {
+    Placement {
+        index,
+        rect: Rectangle { x, y, width, height },
+        orientation,
+    }
+}
+
+// pack places `items` inside `container` with `algorithm`, turning them when `rotation` is true and it helps.
+pub fn pack(container: &Rectangle, items: &[Rectangle], algorithm: Algorithm, rotation: bool) -> Packing {
+    let bin = Rectangle::new(container.width, container.height);
+    let (mut placements, mut unplaced) = match algorithm {
+        Algorithm::Shelf => shelf(&bin, items, rotation),
+        Algorithm::Guillotine => guillotine(&bin, items, rotation),
+        Algorithm::MaxRects => max_rects(&bin, items, rotation),
+    };
+    placements.sort_by_key(|placement| placement.index);
+    unplaced.sort_unstable();
+
+    Packing {
+        container: *container,
+        placements,
+        unplaced,
+    }
+}
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    // How much of the shelf's width is already taken.
+    used: u32,
+}
+
+fn shelf(bin: &Rectangle, items: &[Rectangle], rotation: bool) -> (Vec<Placement>, Vec<usize>) {
+    // With rotation, rectangles are laid flat (wider than tall) where possible, which keeps shelves low.
+    let flat = |item: &Rectangle| {
+        let mut sizes = orientations(item, rotation);
+        sizes.sort_by_key(|&(_, height, _)| height);
+        sizes
+    };
+    let order = largest_first(items, |item| (flat(item)[0].1, item.area()));
+
+    let mut shelves: Vec<Shelf> = Vec::new();
+    let mut placements = Vec::new();
+    let mut unplaced = Vec::new();
+    'items: for index in order {
+        let sizes = flat(&items[index]);
+
+        for shelf in shelves.iter_mut() {
+            for &size in &sizes {
+                let (width, height, _) = size;
+                if height <= shelf.height && width as u64 + shelf.used as u64 <= bin.width as u64 {
+                    placements.push(placement(index, shelf.used, shelf.y, size));
+                    shelf.used += width;
+                    continue 'items;
+                }
+            }
+        }
+
+        // Shelves are stacked from the top, so the next one starts where the last one ends.
+        let top = shelves.last().map_or(0, |shelf| shelf.y + shelf.height);
+        for &size in &sizes {
+            let (width, height, _) = size;
+            if width <= bin.width && height as u64 + top as u64 <= bin.height as u64 {
+                placements.push(placement(index, 0, top, size));
+                shelves.push(Shelf {
+                    y: top,
+                    height,
+                    used: width,
+                });
+                continue 'items;
+            }
+        }
+        unplaced.push(index);
+    }
+    (placements, unplaced)
+}
+
+// best_fit finds the free rectangle and orientation that `score` rates lowest for `item`, if it fits anywhere.
+fn best_fit<S: Ord>(
+    free: &[Rectangle],
+    item: &Rectangle,
+    rotation: bool,
+    score: impl Fn(&Rectangle, u32, u32) -> S,
+) -> Option<(usize, (u32, u32, Orientation))> {
+    let mut best: Option<(S, usize, (u32, u32, Orientation))> = None;
+    for (i, space) in free.iter().enumerate() {
+        for size in orientations(item, rotation) {
+            let (width, height, _) = size;
+            if width > space.width || height > space.height {
+                continue;
+            }
+            let s = score(space, width, height);
+            if best.as_ref().is_none_or(|(best, _, _)| s < *best) {
+                best = Some((s, i, size));
+            }
+        }
+    }
+    best.map(|(_, i, size)| (i, size))
+}
+
+fn guillotine(bin: &Rectangle, items: &[Rectangle], rotation: bool) -> (Vec<Placement>, Vec<usize>) {
+    let mut free = vec![*bin];
+    let mut placements = Vec::new();
+    let mut unplaced = Vec::new();
+
+    for index in largest_first(items, Rectangle::area) {
+        // Best area fit: the free rectangle with the least area left over.
+        let fit = best_fit(&free, &items[index], rotation, |space, width, height| {
+            space.area() - width as u64 * height as u64
+        });
+        let (i, size) = match fit {
+            Some(fit) => fit,
+            None => {
+                unplaced.push(index);
+                continue;
+            }
+        };
+
+        let space = free.swap_remove(i);
+        let (width, height, _) = size;
+        placements.push(placement(index, space.x, space.y, size));
+
+        // Cut along the shorter leftover side, which leaves the larger of the two pieces as big as possible.
+        let (right, below) = if space.width - width < space.height - height {
+            (
+                Rectangle::new(space.width - width, height).at(space.x + width, space.y),
+                Rectangle::new(space.width, space.height - height).at(space.x, space.y + height),
+            )
+        } else {
+            (
+                Rectangle::new(space.width - width, space.height).at(space.x + width, space.y),
+                Rectangle::new(width, space.height - height).at(space.x, space.y + height),
+            )
+        };
+        free.extend([right, below].iter().filter(|piece| !piece.is_empty()));
+    }
+    (placements, unplaced)
+}
+
+// split_free replaces every free rectangle that `used` overlaps with the largest free rectangles left around it:
+// the parts to its left, right, above and below, which overlap each other at the corners.
+fn split_free(free: &[Rectangle], used: &Rectangle) -> Vec<Rectangle> {
+    let mut split = Vec::with_capacity(free.len() + 4);
+    for space in free {
+        if !space.intersects(used) {
+            split.push(*space);
+            continue;
+        }
+
+        // Free rectangles lie inside the bin, so every edge here fits in a u32.
+        let (right, bottom) = (used.right() as u32, used.bottom() as u32);
+        let (space_right, space_bottom) = (space.right() as u32, space.bottom() as u32);
+        if used.x > space.x {
+            split.push(Rectangle::new(used.x - space.x, space.height).at(space.x, space.y));
+        }
+        if right < space_right {
+            split.push(Rectangle::new(space_right - right, space.height).at(right, space.y));
+        }
+        if used.y > space.y {
+            split.push(Rectangle::new(space.width, used.y - space.y).at(space.x, space.y));
+        }
+        if bottom < space_bottom {
+            split.push(Rectangle::new(space.width, space_bottom - bottom).at(space.x, bottom));
+        }
+    }
+
+    // A free rectangle inside another one adds nothing. Of two identical ones, the first is kept.
+    let contained = |i: usize| {
+        split
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && other.contains(&split[i]) && (other != &split[i] || j < i))
+    };
+    (0..split.len())
+        .filter(|&i| !contained(i))
+        .map(|i| split[i])
+        .collect()
+}
+
+fn max_rects(bin: &Rectangle, items: &[Rectangle], rotation: bool) -> (Vec<Placement>, Vec<usize>) {
+    let mut free = vec![*bin];
+    let mut placements = Vec::new();
+    let mut unplaced = Vec::new();
+
+    for index in largest_first(items, Rectangle::area) {
+        // Best short side fit: the free rectangle that leaves the least room along its tighter side.
+        let fit = best_fit(&free, &items[index], rotation, |space, width, height| {
+            let (dx, dy) = (space.width - width, space.height - height);
+            (dx.min(dy), dx.max(dy))
+        });
+        let (i, size) = match fit {
+            Some(fit) => fit,
+            None => {
+                unplaced.push(index);
+                continue;
+            }
+        };
+
+        let space = free[i];
+        let placed = placement(index, space.x, space.y, size);
+        free = split_free(&free, &placed.rect);
+        placements.push(placed);
+    }
+    (placements, unplaced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    const ALGORITHMS: [Algorithm; 3] = [Algorithm::Shelf, Algorithm::Guillotine, Algorithm::MaxRects];
+
+    // check_packing asserts what every packing must get right, however tightly it packs.
+    fn check_packing(packing: &Packing, items: &[Rectangle], rotation: bool) {
+        let bin = Rectangle::new(packing.container.width, packing.container.height);
+        let context = || format!("{:?} {:?} rotation {}", bin, items, rotation);
+
+        for (i, placement) in packing.placements.iter().enumerate() {
+            let (rect, item) = (placement.rect, items[placement.index]);
+            assert!(bin.contains(&rect), "{:?} is outside, {}", rect, context());
+            let size = match placement.orientation {
+                Orientation::Upright => (item.width, item.height),
+                Orientation::Rotated => {
+                    assert!(rotation, "{:?} was rotated, {}", placement, context());
+                    (item.height, item.width)
+                }
+            };
+            assert_eq!((rect.width, rect.height), size, "{}", context());
+            for other in &packing.placements[i + 1..] {
+                assert!(!rect.intersects(&other.rect), "{:?} and {:?} overlap, {}", placement, other, context());
+            }
+        }
+
+        // Every item is either placed or unplaced, exactly once, and both lists are in order.
+        let mut indexes: Vec<usize> = packing.placements.iter().map(|placement| placement.index).collect();
+        assert!(indexes.windows(2).all(|pair| pair[0] < pair[1]), "{}", context());
+        assert!(packing.unplaced.windows(2).all(|pair| pair[0] < pair[1]), "{}", context());
+        indexes.extend(&packing.unplaced);
+        indexes.sort_unstable();
+        assert_eq!(indexes, (0..items.len()).collect::<Vec<usize>>(), "{}", context());
+
+        let used: u64 = packing.placements.iter().map(|placement| placement.rect.area()).sum();
+        assert_eq!(packing.used_area(), used);
+        assert_eq!(packing.wasted_area(), bin.area() - used);
+    }
+
+    #[test]
+    fn placements_never_overlap_and_stay_inside() {
+        let mut random = Random(45);
+        for _ in 0..2000 {
+            let container = Rectangle::new(random.below(60), random.below(60)).at(random.below(100), random.below(100));
+            // Sizes start at 0, so empty items and containers are covered too.
+            let items: Vec<Rectangle> = (0..random.below(30))
+                .map(|_| Rectangle::new(random.below(25), random.below(25)))
+                .collect();
+            for &algorithm in &ALGORITHMS {
+                for &rotation in &[false, true] {
+                    let packing = pack(&container, &items, algorithm, rotation);
+                    assert_eq!(packing.container, container);
+                    check_packing(&packing, &items, rotation);
+                }
+            }
+        }
+    }
+
+    fn placed(packing: &Packing) -> Vec<(usize, Rectangle)> {
+        packing
+            .placements
+            .iter()
+            .map(|placement| (placement.index, placement.rect))
+            .collect()
+    }
+
+    #[test]
+    fn known_packings() {
+        // Three rectangles that exactly fill a 10x6 container.
+        let container = Rectangle::new(10, 6);
+        let items = [Rectangle::new(6, 4), Rectangle::new(4, 6), Rectangle::new(6, 2)];
+
+        // Shelf puts the tallest first, which makes its shelf the whole height, and the 6x2 finds no room left.
+        let shelf = pack(&container, &items, Algorithm::Shelf, false);
+        assert_eq!(
+            placed(&shelf),
+            vec![(0, Rectangle::new(6, 4).at(4, 0)), (1, Rectangle::new(4, 6))]
+        );
+        assert_eq!(shelf.unplaced, vec![2]);
+        assert_eq!((shelf.used_area(), shelf.wasted_area()), (48, 12));
+        assert_eq!(shelf.utilization(), 0.8);
+
+        // Guillotine and MaxRects both find the exact fit.
+        let exact = vec![
+            (0, Rectangle::new(6, 4)),
+            (1, Rectangle::new(4, 6).at(6, 0)),
+            (2, Rectangle::new(6, 2).at(0, 4)),
+        ];
+        for &algorithm in &[Algorithm::Guillotine, Algorithm::MaxRects] {
+            let packing = pack(&container, &items, algorithm, false);
+            assert_eq!(placed(&packing), exact, "{}", algorithm);
+            assert!(packing.unplaced.is_empty());
+            assert_eq!((packing.wasted_area(), packing.utilization()), (0, 1.0));
+        }
+
+        // A rectangle only fits its container turned on its side.
+        for &algorithm in &ALGORITHMS {
+            let upright = pack(&container, &[Rectangle::new(6, 10)], algorithm, false);
+            assert_eq!(upright.unplaced, vec![0], "{}", algorithm);
+            let turned = pack(&container, &[Rectangle::new(6, 10)], algorithm, true);
+            assert_eq!(
+                turned.placements,
+                vec![Placement {
+                    index: 0,
+                    rect: Rectangle::new(10, 6),
+                    orientation: Orientation::Rotated,
+                }],
+                "{}",
+                algorithm
+            );
+        }
+
+        let empty = pack(&Rectangle::new(0, 0), &items, Algorithm::MaxRects, true);
+        assert_eq!((empty.unplaced.len(), empty.wasted_area(), empty.utilization()), (3, 0, 0.0));
+    }
+
+    #[test]
+    fn splitting_free_space() {
+        let bin = Rectangle::new(10, 10);
+        // Used space in the middle leaves four overlapping pieces around it.
+        assert_eq!(
+            split_free(&[bin], &Rectangle::new(4, 4).at(3, 3)),
+            vec![
+                Rectangle::new(3, 10),
+                Rectangle::new(3, 10).at(7, 0),
+                Rectangle::new(10, 3),
+                Rectangle::new(10, 3).at(0, 7),
+            ]
+        );
+        // In a corner, only two.
+        assert_eq!(
+            split_free(&[bin], &Rectangle::new(4, 4)),
+            vec![Rectangle::new(6, 10).at(4, 0), Rectangle::new(10, 6).at(0, 4)]
+        );
+        // Filling the space leaves nothing.
+        assert!(split_free(&[bin], &bin).is_empty());
+
+        // Free space the used rectangle doesn't touch is kept, unless a new piece covers it.
+        let apart = Rectangle::new(2, 2).at(20, 20);
+        assert_eq!(split_free(&[bin, apart], &Rectangle::new(10, 2)), vec![Rectangle::new(10, 8).at(0, 2), apart]);
+        let covered = Rectangle::new(4, 10).at(6, 0);
+        assert_eq!(
+            split_free(&[bin, covered], &Rectangle::new(2, 2)),
+            vec![Rectangle::new(8, 10).at(2, 0), Rectangle::new(10, 8).at(0, 2)]
+        );
+        // Of two identical free rectangles, one is kept.
+        assert_eq!(split_free(&[apart, apart], &Rectangle::new(1, 1)), vec![apart]);
+    }
+
+    #[test]
+    fn algorithm_names() {
+        for &algorithm in &ALGORITHMS {
+            assert_eq!(algorithm.to_string().parse(), Ok(algorithm));
+        }
+        assert!("best".parse::<Algorithm>().is_err());
+    }
+}
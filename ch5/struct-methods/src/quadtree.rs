@@ -0,0 +1,403 @@
+/*
+
+A quadtree over positioned Rectangles.
+
+Checking every pair of n rectangles for overlaps takes n * (n - 1) / 2 checks, which is 50 million for 10,000
+rectangles. A quadtree splits space into four quadrants, each of those into four more, and so on, and files every
+rectangle under the smallest quadrant that holds it entirely. Rectangles in different quadrants can't overlap, so
+queries only look at the few quadrants that matter.
+
+A quadrant is split once it holds more than NODE_CAPACITY rectangles, moving down those that fit inside one of its
+four new children. Rectangles that straddle a split stay where they are, which is why a quadrant can keep more than
+NODE_CAPACITY. Removing a rectangle never merges quadrants back together.
+
+The tree covers the area given to `QuadTree::new`. Rectangles outside it are still stored and found by every query,
+but at the root, so a tree whose area fits its rectangles works best.
+
+*/
+
+use crate::rectangle::Rectangle;
+
+// How many rectangles a quadrant holds before it is split.
+const NODE_CAPACITY: usize = 8;
+// How many times quadrants are split at most, so piles of identical rectangles can't split forever.
+const MAX_DEPTH: usize = 16;
+
+// A handle to a rectangle in a QuadTree, returned by `insert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RectId(usize);
+
+// The area a node covers, as u64 so that splitting it can't overflow.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    x: u64,
+    y: u64,
+    right: u64,
+    bottom: u64,
+}
+
+impl Region {
+    fn holds(&self, rect: &Rectangle) -> bool {
+        self.x <= rect.x as u64 && rect.right() <= self.right && self.y <= rect.y as u64 && rect.bottom() <= self.bottom
+    }
+
+    fn intersects(&self, rect: &Rectangle) -> bool {
+        (rect.x as u64) < self.right && self.x < rect.right() && (rect.y as u64) < self.bottom && self.y < rect.bottom()
+    }
+
+    fn contains_point(&self, x: u32, y: u32) -> bool {
+        self.x <= x as u64 && (x as u64) < self.right && self.y <= y as u64 && (y as u64) < self.bottom
+    }
+
+    fn quadrants(&self) -> [Region; 4] {
+        let mid_x = self.x + (self.right - self.x) / 2;
+        let mid_y = self.y + (self.bottom - self.y) / 2;
+        [
+            Region { right: mid_x, bottom: mid_y, ..*self },
+            Region { x: mid_x, bottom: mid_y, ..*self },
+            Region { right: mid_x, y: mid_y, ..*self },
+            Region { x: mid_x, y: mid_y, ..*self },
+        ]
+    }
+}
+
+struct Node {
+    region: Region,
+    depth: usize,
+    items: Vec<RectId>,
+    children: Option<[usize; 4]>,
+}
+
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    // Indexed by RectId: each rectangle and the node it is filed under, or None once removed.
+    entries: Vec<Option<(Rectangle, usize)>>,
+    len: usize,
+}
+
+impl QuadTree {
+    pub fn new(area: Rectangle) -> QuadTree {
+        let root = Node {
+            region: Region {
+                x: area.x as u64,
+                y: area.y as u64,
+                right: area.right(),
+                bottom: area.bottom(),
+            },
+            depth: 0,
+            items: Vec::new(),
+            children: None,
+        };
+        QuadTree {
+            nodes: vec![root],
+            entries: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, id: RectId) -> Option<&Rectangle> {
+        self.entries.get(id.0)?.as_ref().map(|(rect, _)| rect)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (RectId, &Rectangle)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| entry.as_ref().map(|(rect, _)| (RectId(i), rect)))
+    }
+
+    pub fn insert(&mut self, rect: Rectangle) -> RectId {
+        let id = RectId(self.entries.len());
+        self.entries.push(None);
+        self.len += 1;
+        self.file(id, rect, 0);
+        id
+    }
+
+    // file puts `id` under the deepest node below `node` that holds it, splitting that node if it gets too full.
+    fn file(&mut self, id: RectId, rect: Rectangle, mut node: usize) {
+        while let Some(children) = self.nodes[node].children {
+            match children.iter().find(|&&child| self.nodes[child].region.holds(&rect)) {
+                Some(&child) => node = child,
+                None => break,
+            }
+        }
+
+        self.nodes[node].items.push(id);
+        self.entries[id.0] = Some((rect, node));
+        if self.nodes[node].children.is_none()
+            && self.nodes[node].items.len() > NODE_CAPACITY
+            && self.nodes[node].depth < MAX_DEPTH
+        {
+            self.split(node);
+        }
+    }
+
+    fn split(&mut self, node: usize) {
+        let depth = self.nodes[node].depth + 1;
+        let first = self.nodes.len();
+        for region in self.nodes[node].region.quadrants().iter() {
+            self.nodes.push(Node {
+                region: *region,
+                depth,
+                items: Vec::new(),
+                children: None,
+            });
+        }
+        self.nodes[node].children = Some([first, first + 1, first + 2, first + 3]);
+
+        // Move every rectangle that now fits in a child down into it.
+        let items = std::mem::take(&mut self.nodes[node].items);
+        for id in items {
+            let (rect, _) = self.entries[id.0].expect("filed rectangles have entries");
+            self.file(id, rect, node);
+        }
+    }
+
+    pub fn remove(&mut self, id: RectId) -> Option<Rectangle> {
+        let (rect, node) = self.entries.get_mut(id.0)?.take()?;
+        let items = &mut self.nodes[node].items;
+        let position = items.iter().position(|&item| item == id).expect("a rectangle is filed where its entry says");
+        items.swap_remove(position);
+        self.len -= 1;
+        Some(rect)
+    }
+
+    fn rect(&self, id: RectId) -> &Rectangle {
+        &self.entries[id.0].as_ref().expect("filed rectangles have entries").0
+    }
+
+    // visit calls `f` with every rectangle filed in a node whose region passes `enter`, or at the root, which also
+    // holds the rectangles outside the tree's area.
+    fn visit<E, F>(&self, enter: E, mut f: F)
+    where
+        E: Fn(&Region) -> bool,
+        F: FnMut(RectId, &Rectangle),
+    {
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            for &id in &node.items {
+                f(id, self.rect(id));
+            }
+            if let Some(children) = node.children {
+                stack.extend(children.iter().filter(|&&child| enter(&self.nodes[child].region)));
+            }
+        }
+    }
+
+    // at_point returns the rectangles containing the point (x, y), in insertion order.
+    pub fn at_point(&self, x: u32, y: u32) -> Vec<RectId> {
+        let mut found = Vec::new();
+        self.visit(
+            |region| region.contains_point(x, y),
+            |id, rect| {
+                if rect.contains_point(x, y) {
+                    found.push(id);
+                }
+            },
+        );
+        found.sort_unstable();
+        found
+    }
+
+    // in_range returns the rectangles that overlap `range`, in insertion order.
+    pub fn in_range(&self, range: &Rectangle) -> Vec<RectId> {
+        let mut found = Vec::new();
+        self.visit(
+            |region| region.intersects(range),
+            |id, rect| {
+                if rect.intersects(range) {
+                    found.push(id);
+                }
+            },
+        );
+        found.sort_unstable();
+        found
+    }
+
+    // overlapping_pairs returns every pair of rectangles that overlap, each pair once with the earlier inserted
+    // rectangle first, sorted. Two rectangles can only overlap if one is filed at or above the other, since sibling
+    // regions don't overlap, so each rectangle is only checked against those on its way up to the root.
+    pub fn overlapping_pairs(&self) -> Vec<(RectId, RectId)> {
+        let mut pairs = Vec::new();
+        let mut above = Vec::new();
+        self.pairs_below(0, &mut above, &mut pairs);
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn pairs_below(&self, node: usize, above: &mut Vec<RectId>, pairs: &mut Vec<(RectId, RectId)>) {
+        let node = &self.nodes[node];
+        let before = above.len();
+        for &id in &node.items {
+            let rect = self.rect(id);
+            for &other in above.iter() {
+                if rect.intersects(self.rect(other)) {
+                    pairs.push((id.min(other), id.max(other)));
+                }
+            }
+            above.push(id);
+        }
+
+        if let Some(children) = node.children {
+            for &child in children.iter() {
+                self.pairs_below(child, above, pairs);
+            }
+        }
+        above.truncate(before);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    // Model is the brute force version of a QuadTree: every rectangle still in the tree, by id, checked one by one.
+    struct Model(Vec<Option<Rectangle>>);
+
+    impl Model {
+        fn live(&self) -> impl Iterator<Item = (RectId, &Rectangle)> {
+            self.0
+                .iter()
+                .enumerate()
+                .filter_map(|(i, rect)| rect.as_ref().map(|rect| (RectId(i), rect)))
+        }
+
+        fn at_point(&self, x: u32, y: u32) -> Vec<RectId> {
+            self.live().filter(|(_, rect)| rect.contains_point(x, y)).map(|(id, _)| id).collect()
+        }
+
+        fn in_range(&self, range: &Rectangle) -> Vec<RectId> {
+            self.live().filter(|(_, rect)| rect.intersects(range)).map(|(id, _)| id).collect()
+        }
+
+        fn overlapping_pairs(&self) -> Vec<(RectId, RectId)> {
+            let live: Vec<(RectId, &Rectangle)> = self.live().collect();
+            let mut pairs = Vec::new();
+            for (i, &(a, first)) in live.iter().enumerate() {
+                for &(b, second) in &live[i + 1..] {
+                    if first.intersects(second) {
+                        pairs.push((a, b));
+                    }
+                }
+            }
+            pairs
+        }
+    }
+
+    // rectangle is mostly inside the 256 by 256 area the tests' trees cover, sometimes past its edges, and
+    // sometimes empty.
+    fn rectangle(random: &mut Random) -> Rectangle {
+        Rectangle::new(random.below(40), random.below(40)).at(random.below(300), random.below(300))
+    }
+
+    fn check(tree: &QuadTree, model: &Model, random: &mut Random) {
+        assert_eq!(tree.len(), model.live().count());
+        assert!(tree.iter().eq(model.live()));
+        for _ in 0..200 {
+            let (x, y) = (random.below(320), random.below(320));
+            assert_eq!(tree.at_point(x, y), model.at_point(x, y), "at ({}, {})", x, y);
+            let range = rectangle(random);
+            assert_eq!(tree.in_range(&range), model.in_range(&range), "in {:?}", range);
+        }
+        assert_eq!(tree.overlapping_pairs(), model.overlapping_pairs());
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let mut random = Random(47);
+        for _ in 0..20 {
+            let mut tree = QuadTree::new(Rectangle::square(256));
+            let mut model = Model(Vec::new());
+            for _ in 0..300 {
+                let rect = rectangle(&mut random);
+                assert_eq!(tree.insert(rect), RectId(model.0.len()));
+                model.0.push(Some(rect));
+            }
+            check(&tree, &model, &mut random);
+
+            // Removing a third of them, some twice, leaves the rest to be found just the same.
+            for _ in 0..150 {
+                let id = RectId(random.below(300) as usize);
+                assert_eq!(tree.remove(id), model.0[id.0].take());
+                assert_eq!(tree.get(id), None);
+            }
+            check(&tree, &model, &mut random);
+
+            // New rectangles fill in around the removed ones.
+            for _ in 0..100 {
+                let rect = rectangle(&mut random);
+                tree.insert(rect);
+                model.0.push(Some(rect));
+            }
+            check(&tree, &model, &mut random);
+        }
+    }
+
+    #[test]
+    fn removing() {
+        let mut tree = QuadTree::new(Rectangle::square(100));
+        let a = tree.insert(Rectangle::new(10, 10));
+        let b = tree.insert(Rectangle::new(10, 10).at(5, 5));
+        assert_eq!(tree.overlapping_pairs(), vec![(a, b)]);
+
+        assert_eq!(tree.remove(a), Some(Rectangle::new(10, 10)));
+        assert_eq!(tree.remove(a), None);
+        assert_eq!(tree.remove(RectId(99)), None);
+        assert_eq!((tree.len(), tree.get(a), tree.get(b)), (1, None, Some(&Rectangle::new(10, 10).at(5, 5))));
+        assert_eq!(tree.at_point(1, 1), vec![]);
+        assert_eq!(tree.at_point(6, 6), vec![b]);
+        assert_eq!(tree.in_range(&Rectangle::new(100, 100)), vec![b]);
+        assert!(tree.overlapping_pairs().is_empty());
+
+        assert_eq!(tree.remove(b), Some(Rectangle::new(10, 10).at(5, 5)));
+        assert!(tree.is_empty());
+        assert!(tree.in_range(&Rectangle::new(100, 100)).is_empty());
+    }
+
+    #[test]
+    fn rectangles_outside_the_area() {
+        let mut tree = QuadTree::new(Rectangle::square(100).at(100, 100));
+        let inside = tree.insert(Rectangle::new(10, 10).at(150, 150));
+        let straddling = tree.insert(Rectangle::new(20, 20).at(90, 90));
+        let far = tree.insert(Rectangle::new(10, 10).at(1000, 5));
+        let at_the_end = tree.insert(Rectangle::new(5, 5).at(u32::MAX - 5, u32::MAX - 5));
+        for i in 0..NODE_CAPACITY * 4 {
+            tree.insert(Rectangle::new(1, 1).at(101 + i as u32, 101));
+        }
+
+        assert_eq!(tree.at_point(95, 95), vec![straddling]);
+        assert_eq!(tree.at_point(1005, 5), vec![far]);
+        assert_eq!(tree.at_point(u32::MAX - 1, u32::MAX - 1), vec![at_the_end]);
+        assert_eq!(tree.at_point(155, 155), vec![inside]);
+        assert_eq!(tree.in_range(&Rectangle::new(2000, 100)), vec![straddling, far]);
+        // The straddling rectangle reaches the first nine of the small ones, at x = 101 to 109.
+        let pairs: Vec<(RectId, RectId)> = (4..13).map(|i| (straddling, RectId(i))).collect();
+        assert_eq!(tree.overlapping_pairs(), pairs);
+    }
+
+    #[test]
+    fn piles_of_identical_rectangles() {
+        let mut tree = QuadTree::new(Rectangle::square(1 << 20));
+        let rect = Rectangle::new(1, 1).at(3, 3);
+        let count = NODE_CAPACITY * (MAX_DEPTH + 2);
+        let ids: Vec<RectId> = (0..count).map(|_| tree.insert(rect)).collect();
+
+        assert!(tree.nodes.iter().all(|node| node.depth <= MAX_DEPTH));
+        assert_eq!(tree.nodes.iter().map(|node| node.items.len()).sum::<usize>(), count);
+        assert_eq!(tree.at_point(3, 3), ids);
+        assert!(tree.at_point(4, 3).is_empty());
+        assert_eq!(tree.in_range(&Rectangle::new(5, 5)), ids);
+        assert_eq!(tree.overlapping_pairs().len(), count * (count - 1) / 2);
+    }
+}
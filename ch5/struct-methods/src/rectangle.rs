@@ -0,0 +1,398 @@
+/*
+
+Positioned rectangles.
+
+A Rectangle has a position as well as a size: (x, y) is its top left corner, and x grows to the right and y grows
+downwards, as on a screen. A rectangle covers the points from x up to but not including x + width, and from y up to
+but not including y + height, so two rectangles that only share an edge don't overlap, and a 0x0 rectangle covers
+nothing at all.
+
+Every coordinate and dimension is a u32, but the far edges (`right` and `bottom`), areas and perimeters are computed
+as u64, so they can't overflow no matter how large the rectangle is. Operations whose result might not fit in u32s,
+like `translate`, `scale` and `union`, return None when it doesn't instead of wrapping around.
+
+Rectangles are written as `WIDTHxHEIGHT@X,Y`, e.g. `30x50@10,20`, and the position is left out when it is the
+origin, e.g. `30x50`. FromStr reads either form back, so `r.to_string().parse()` always gives back `r`. The
+alternate form, `{:#}`, draws the rectangle as box art instead; see `src/ascii.rs`.
+
+*/
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ascii;
+use crate::fit::Fit;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rectangle {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// gcd returns the greatest common divisor of `a` and `b`, using Euclid's algorithm.
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Rectangle {
+    // at returns the same rectangle moved so its top left corner is at (x, y).
+    pub fn at(self, x: u32, y: u32) -> Rectangle {
+        Rectangle { x, y, ..self }
+    }
+
+    // right is the x coordinate just past the rectangle's right edge.
+    pub fn right(&self) -> u64 {
+        self.x as u64 + self.width as u64
+    }
+
+    // bottom is the y coordinate just past the rectangle's bottom edge.
+    pub fn bottom(&self) -> u64 {
+        self.y as u64 + self.height as u64
+    }
+
+    pub fn area(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+
+    pub fn perimeter(&self) -> u64 {
+        2 * (self.width as u64 + self.height as u64)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // aspect_ratio returns width:height in lowest terms, e.g. (16, 9) for a 1920x1080 rectangle, or None if either
+    // dimension is 0.
+    pub fn aspect_ratio(&self) -> Option<(u32, u32)> {
+        if self.is_empty() {
+            return None;
+        }
+        let divisor = gcd(self.width, self.height);
+        Some((self.width / divisor, self.height / divisor))
+    }
+
+    // can_hold reports whether `other` fits inside this rectangle without turning it. A rectangle can hold an
+    // identical one; `can_hold_with` in `src/fit.rs` takes other rules.
+    pub fn can_hold(&self, other: &Rectangle) -> bool {
+        self.can_hold_with(other, Fit::INCLUSIVE)
+    }
+
+    pub fn contains_point(&self, x: u32, y: u32) -> bool {
+        self.x <= x && (x as u64) < self.right() && self.y <= y && (y as u64) < self.bottom()
+    }
+
+    // contains reports whether `other` lies entirely inside this rectangle, edges included.
+    pub fn contains(&self, other: &Rectangle) -> bool {
+        self.x <= other.x && other.right() <= self.right() && self.y <= other.y && other.bottom() <= self.bottom()
+    }
+
+    // intersects reports whether the rectangles overlap. Rectangles that only touch along an edge don't.
+    pub fn intersects(&self, other: &Rectangle) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    // intersection returns the area the two rectangles share, or None if they don't overlap.
+    pub fn intersection(&self, other: &Rectangle) -> Option<Rectangle> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x as u64 || bottom <= y as u64 {
+            return None;
+        }
+
+        // Both edges lie within one of the rectangles, so the differences fit in a u32.
+        Some(Rectangle {
+            x,
+            y,
+            width: (right - x as u64) as u32,
+            height: (bottom - y as u64) as u32,
+        })
+    }
+
+    // union returns the smallest rectangle containing both rectangles, or None if it would be wider or taller than
+    // u32::MAX. Empty rectangles are ignored, as they don't cover anything.
+    pub fn union(&self, other: &Rectangle) -> Option<Rectangle> {
+        if other.is_empty() {
+            return Some(*self);
+        }
+        if self.is_empty() {
+            return Some(*other);
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Some(Rectangle {
+            x,
+            y,
+            width: u32::try_from(right - x as u64).ok()?,
+            height: u32::try_from(bottom - y as u64).ok()?,
+        })
+    }
+
+    // bounding_box returns the smallest rectangle containing every rectangle, or None if there are none or it
+    // doesn't fit in u32s.
+    pub fn bounding_box<'a, I>(rectangles: I) -> Option<Rectangle>
+    where
+        I: IntoIterator<Item = &'a Rectangle>,
+    {
+        let mut rectangles = rectangles.into_iter();
+        let first = *rectangles.next()?;
+        rectangles.try_fold(first, |bounds, rectangle| bounds.union(rectangle))
+    }
+
+    // translate moves the rectangle by (dx, dy), or returns None if that would move it off either end of the u32
+    // coordinate space.
+    pub fn translate(&self, dx: i64, dy: i64) -> Option<Rectangle> {
+        let x = u32::try_from((self.x as i64).checked_add(dx)?).ok()?;
+        let y = u32::try_from((self.y as i64).checked_add(dy)?).ok()?;
+        Some(self.at(x, y))
+    }
+
+    // scale multiplies the width and height by `factor`, keeping the top left corner where it is. It returns None if
+    // either dimension overflows.
+    pub fn scale(&self, factor: u32) -> Option<Rectangle> {
+        Some(Rectangle {
+            width: self.width.checked_mul(factor)?,
+            height: self.height.checked_mul(factor)?,
+            ..*self
+        })
+    }
+}
+
+// We can also define multiple impl blocks for the same struct:
+impl Rectangle {
+    // new returns a rectangle at the origin.
+    pub fn new(width: u32, height: u32) -> Rectangle {
+        Rectangle {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+
+    // Not all impl functions require taking `self` as the first parameter:
+    pub fn square(size: u32) -> Rectangle {
+        Rectangle::new(size, size)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseRectangleError {
+    // There was no 'x' between the width and the height.
+    MissingSize,
+    // There was an '@' but not an `X,Y` position after it.
+    MissingPosition,
+    InvalidNumber(String),
+}
+
+impl fmt::Display for ParseRectangleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRectangleError::MissingSize => write!(f, "expected a size like 30x50"),
+            ParseRectangleError::MissingPosition => write!(f, "expected a position like 30x50@10,20 after '@'"),
+            ParseRectangleError::InvalidNumber(s) => write!(f, "{:?} is not a number from 0 to {}", s, u32::MAX),
+        }
+    }
+}
+
+impl Error for ParseRectangleError {}
+
+// pair parses `a<separator>b` into two numbers.
+fn pair(s: &str, separator: char, missing: ParseRectangleError) -> Result<(u32, u32), ParseRectangleError> {
+    let (a, b) = s.split_once(separator).ok_or(missing)?;
+    let number = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .map_err(|_| ParseRectangleError::InvalidNumber(n.trim().to_string()))
+    };
+    Ok((number(a)?, number(b)?))
+}
+
+impl FromStr for Rectangle {
+    type Err = ParseRectangleError;
+
+    fn from_str(s: &str) -> Result<Rectangle, ParseRectangleError> {
+        let (size, position) = match s.trim().split_once('@') {
+            Some((size, position)) => (size, Some(position)),
+            None => (s.trim(), None),
+        };
+
+        let (width, height) = pair(size, 'x', ParseRectangleError::MissingSize)?;
+        let (x, y) = match position {
+            Some(position) => pair(position, ',', ParseRectangleError::MissingPosition)?,
+            None => (0, 0),
+        };
+        Ok(Rectangle { x, y, width, height })
+    }
+}
+
+impl fmt::Display for Rectangle {
+    // `{}` writes `30x50@10,20`, and `{:#}` draws the rectangle `{:#60}` columns wide (80 by default).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            let columns = f.width().unwrap_or(ascii::DEFAULT_COLUMNS);
+            let art = ascii::draw(&[Rectangle { x: 0, y: 0, ..*self }], columns);
+            return f.write_str(art.trim_end_matches('\n'));
+        }
+
+        write!(f, "{}x{}", self.width, self.height)?;
+        if self.x != 0 || self.y != 0 {
+            write!(f, "@{},{}", self.x, self.y)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    // coordinate is mostly small, so rectangles overlap often, and sometimes close to u32::MAX, where edges and
+    // unions stop fitting in u32s.
+    fn coordinate(random: &mut Random) -> u32 {
+        match random.below(4) {
+            0 => u32::MAX - random.below(16),
+            1 => random.next(),
+            _ => random.below(16),
+        }
+    }
+
+    fn rectangle(random: &mut Random) -> Rectangle {
+        Rectangle {
+            x: coordinate(random),
+            y: coordinate(random),
+            width: coordinate(random),
+            height: coordinate(random),
+        }
+    }
+
+    fn pairs() -> impl Iterator<Item = (Rectangle, Rectangle)> {
+        let mut random = Random(43);
+        (0..20_000).map(move |_| (rectangle(&mut random), rectangle(&mut random)))
+    }
+
+    #[test]
+    fn intersection_is_inside_both() {
+        for (a, b) in pairs() {
+            assert_eq!(a.intersection(&b), b.intersection(&a));
+            match a.intersection(&b) {
+                Some(i) => {
+                    assert!(!i.is_empty(), "{:?} {:?}", a, b);
+                    assert!(i.area() <= a.area() && i.area() <= b.area(), "{:?} {:?}", a, b);
+                    assert!(a.contains(&i) && b.contains(&i), "{:?} {:?}", a, b);
+                    assert!(a.contains_point(i.x, i.y) && b.contains_point(i.x, i.y));
+                }
+                None => assert!(
+                    a.right().min(b.right()) <= a.x.max(b.x) as u64
+                        || a.bottom().min(b.bottom()) <= a.y.max(b.y) as u64,
+                    "{:?} {:?}",
+                    a,
+                    b
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn union_contains_both() {
+        for (a, b) in pairs() {
+            // Two empty rectangles have nothing to compare, and union keeps the first.
+            if !a.is_empty() || !b.is_empty() {
+                assert_eq!(a.union(&b), b.union(&a));
+            }
+            let union = match a.union(&b) {
+                Some(union) => union,
+                None => {
+                    // Only too wide or too tall a union is missing.
+                    let width = a.right().max(b.right()) - a.x.min(b.x) as u64;
+                    let height = a.bottom().max(b.bottom()) - a.y.min(b.y) as u64;
+                    assert!(width > u32::MAX as u64 || height > u32::MAX as u64, "{:?} {:?}", a, b);
+                    continue;
+                }
+            };
+            for r in [a, b].iter().filter(|r| !r.is_empty()) {
+                assert!(union.contains(r), "{:?} {:?} {:?}", a, b, union);
+                assert!(union.area() >= r.area());
+            }
+            if let Some(i) = a.intersection(&b) {
+                assert!(union.contains(&i));
+            }
+        }
+    }
+
+    #[test]
+    fn union_is_the_smallest_bounding_rectangle() {
+        for (a, b) in pairs().filter(|(a, b)| !a.is_empty() && !b.is_empty()) {
+            if let Some(union) = a.union(&b) {
+                assert_eq!(union.x, a.x.min(b.x));
+                assert_eq!(union.y, a.y.min(b.y));
+                assert_eq!(union.right(), a.right().max(b.right()));
+                assert_eq!(union.bottom(), a.bottom().max(b.bottom()));
+                assert_eq!(Rectangle::bounding_box(&[a, b]), Some(union));
+            }
+        }
+    }
+
+    #[test]
+    fn edges_and_empty_rectangles() {
+        let a = Rectangle::new(10, 10);
+        // Sharing only an edge isn't overlapping.
+        assert_eq!(a.intersection(&Rectangle::new(10, 10).at(10, 0)), None);
+        assert_eq!(a.intersection(&Rectangle::new(5, 5).at(5, 5)), Some(Rectangle::new(5, 5).at(5, 5)));
+        assert_eq!(a.intersection(&Rectangle::new(0, 5).at(5, 5)), None);
+        assert_eq!(a.union(&Rectangle::new(0, 0).at(100, 100)), Some(a));
+        assert_eq!(Rectangle::new(0, 3).union(&a.at(4, 4)), Some(a.at(4, 4)));
+        assert_eq!(a.union(&Rectangle::new(1, 1).at(u32::MAX, 0)), None);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for (a, b) in pairs() {
+            for r in &[a, b, a.at(0, 0), a.at(0, b.y), a.at(b.x, 0)] {
+                assert_eq!(r.to_string().parse(), Ok(*r), "{}", r);
+            }
+        }
+        for r in &[Rectangle::default(), Rectangle::new(u32::MAX, u32::MAX).at(u32::MAX, u32::MAX)] {
+            assert_eq!(r.to_string().parse(), Ok(*r), "{}", r);
+        }
+    }
+
+    #[test]
+    fn display_leaves_out_the_origin() {
+        assert_eq!(Rectangle::new(30, 50).at(10, 20).to_string(), "30x50@10,20");
+        assert_eq!(Rectangle::new(30, 50).to_string(), "30x50");
+        assert_eq!(Rectangle::new(30, 50).at(0, 5).to_string(), "30x50@0,5");
+        assert_eq!(Rectangle::new(0, 0).to_string(), "0x0");
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(" 30 x 50 @ 10 , 20 ".parse(), Ok(Rectangle::new(30, 50).at(10, 20)));
+        assert_eq!("30x50@0,0".parse(), Ok(Rectangle::new(30, 50)));
+
+        let invalid = |s: &str| Err(ParseRectangleError::InvalidNumber(s.to_string()));
+        assert_eq!("30".parse::<Rectangle>(), Err(ParseRectangleError::MissingSize));
+        assert_eq!("30x50@10".parse::<Rectangle>(), Err(ParseRectangleError::MissingPosition));
+        assert_eq!("30x".parse::<Rectangle>(), invalid(""));
+        assert_eq!("x50".parse::<Rectangle>(), invalid(""));
+        assert_eq!("30x50@".parse::<Rectangle>(), Err(ParseRectangleError::MissingPosition));
+        assert_eq!("30x-1".parse::<Rectangle>(), invalid("-1"));
+        assert_eq!("4294967296x1".parse::<Rectangle>(), invalid("4294967296"));
+        assert_eq!("30x50@1,2,3".parse::<Rectangle>(), invalid("2,3"));
+    }
+
+}
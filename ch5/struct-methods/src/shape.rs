@@ -0,0 +1,723 @@
+/*
+
+Shapes beyond Rectangle.
+
+Rectangle's methods are inherent: they belong to Rectangle alone. The Shape trait names the methods every shape has,
+so squares, circles, triangles and polygons can sit side by side in a `Vec<Box<dyn Shape>>` and be asked the same
+questions. Rectangle implements it too; where the trait and an inherent method share a name, like `area`, calling
+it on a Rectangle picks the inherent method, and calling it through `dyn Shape` picks the trait's.
+
+Shapes other than rectangles and squares can have fractional coordinates, so the trait works in f64 throughout.
+A shape's boundary counts as part of it: a point on the edge is contained, and a shape can hold an identical one.
+
+Polygon areas use the shoelace formula, which sums the signed areas of the trapezoids under each edge:
+
+area = |x1*y2 - x2*y1 + x2*y3 - x3*y2 + ... + xn*y1 - x1*yn| / 2
+
+It works for any simple polygon, convex or not, whichever way round its points are listed.
+
+*/
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::rectangle::Rectangle;
+
+// How many points along a circle `outline` samples.
+const CIRCLE_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn distance(&self, other: Point) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    // around returns the smallest box containing every point, or None if there are none.
+    pub fn around<I: IntoIterator<Item = Point>>(points: I) -> Option<BoundingBox> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(BoundingBox { min: first, max: first }, |bounds, p| BoundingBox {
+            min: Point::new(bounds.min.x.min(p.x), bounds.min.y.min(p.y)),
+            max: Point::new(bounds.max.x.max(p.x), bounds.max.y.max(p.y)),
+        }))
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::around([self.min, self.max, other.min, other.max].iter().copied())
+            .expect("four points were given")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeError {
+    TooFewPoints { count: usize },
+    NegativeRadius(f64),
+    // NaN or infinite.
+    InvalidRadius(f64),
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShapeError::TooFewPoints { count } => {
+                write!(f, "a polygon needs at least 3 points, but {} were given", count)
+            }
+            ShapeError::NegativeRadius(radius) => write!(f, "a circle can't have a negative radius ({})", radius),
+            ShapeError::InvalidRadius(radius) => write!(f, "a circle's radius must be a finite number, not {}", radius),
+        }
+    }
+}
+
+impl Error for ShapeError {}
+
+pub trait Shape: fmt::Debug {
+    fn name(&self) -> &'static str;
+
+    fn area(&self) -> f64;
+
+    fn perimeter(&self) -> f64;
+
+    fn bounding_box(&self) -> BoundingBox;
+
+    fn contains_point(&self, point: Point) -> bool;
+
+    // outline returns points along the shape's boundary: the corners of a polygon, or evenly spaced samples of a
+    // curve.
+    fn outline(&self) -> Vec<Point>;
+
+    // can_hold reports whether `other`, where it is, lies entirely inside this shape. Every edge of its outline has
+    // to lie inside this shape, which is checked by cutting the edge wherever it meets this shape's outline: an edge
+    // bridging a notch in a concave polygon has both ends inside, but the piece across the notch isn't. No corner of
+    // this shape may poke into `other` either, which matters when `other` is a circle, whose outline is only
+    // sampled. This is exact for polygons; for circles it is only as precise as their sampled outline.
+    fn can_hold(&self, other: &dyn Shape) -> bool {
+        let outline = self.outline();
+        let inner = other.outline();
+        edges(&inner).all(|(a, b)| segment_inside(|point| self.contains_point(point), &outline, a, b))
+            && outline
+                .into_iter()
+                .all(|corner| !other.contains_point(corner) || on_boundary(&inner, corner))
+    }
+}
+
+// on_boundary reports whether `point` lies on an edge of the closed polygon `points`.
+fn on_boundary(points: &[Point], point: Point) -> bool {
+    edges(points).any(|(a, b)| {
+        let cross = cross(a, b, point);
+        let within = a.x.min(b.x) <= point.x
+            && point.x <= a.x.max(b.x)
+            && a.y.min(b.y) <= point.y
+            && point.y <= a.y.max(b.y);
+        within && cross.abs() <= 1e-9 * (1.0 + a.distance(b))
+    })
+}
+
+fn cross(o: Point, a: Point, b: Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+// crossings returns where along the segment from `a` to `b`, as fractions from 0 at `a` to 1 at `b`, it meets the
+// segment from `c` to `d`. Where the two overlap along a line, the ends of the overlap are returned.
+fn crossings(a: Point, b: Point, c: Point, d: Point) -> Vec<f64> {
+    let (rx, ry) = (b.x - a.x, b.y - a.y);
+    let (sx, sy) = (d.x - c.x, d.y - c.y);
+    let length = rx * rx + ry * ry;
+    if length == 0.0 {
+        return Vec::new();
+    }
+
+    let denominator = rx * sy - ry * sx;
+    if denominator.abs() > 1e-12 * length.sqrt() * sx.hypot(sy) {
+        let t = ((c.x - a.x) * sy - (c.y - a.y) * sx) / denominator;
+        let u = ((c.x - a.x) * ry - (c.y - a.y) * rx) / denominator;
+        return if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) { vec![t] } else { Vec::new() };
+    }
+    if cross(a, b, c).abs() > 1e-9 * length.sqrt() {
+        // Parallel, but on different lines.
+        return Vec::new();
+    }
+    [c, d]
+        .iter()
+        .map(|p| ((p.x - a.x) * rx + (p.y - a.y) * ry) / length)
+        .filter(|t| (0.0..=1.0).contains(t))
+        .collect()
+}
+
+// segment_inside reports whether the whole segment from `a` to `b` is inside a shape, given the shape's
+// `contains` and its outline. The segment is cut wherever it meets the outline, and each piece is checked at its
+// middle: a piece can't cross the outline between two cuts, so its middle is inside exactly when all of it is.
+fn segment_inside<F: Fn(Point) -> bool>(contains: F, outline: &[Point], a: Point, b: Point) -> bool {
+    let mut cuts = vec![0.0, 1.0];
+    for (c, d) in edges(outline) {
+        cuts.extend(crossings(a, b, c, d));
+    }
+    cuts.sort_by(|x, y| x.partial_cmp(y).expect("cuts are never NaN"));
+
+    let at = |t: f64| Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+    contains(a) && contains(b) && cuts.windows(2).all(|pair| contains(at((pair[0] + pair[1]) / 2.0)))
+}
+
+// edges pairs every point with the next one, wrapping around to the first.
+fn edges(points: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    points
+        .iter()
+        .copied()
+        .zip(points.iter().copied().cycle().skip(1))
+}
+
+// shoelace returns the area of the simple polygon `points`.
+fn shoelace(points: &[Point]) -> f64 {
+    edges(points).map(|(a, b)| a.x * b.y - b.x * a.y).sum::<f64>().abs() / 2.0
+}
+
+fn polygon_perimeter(points: &[Point]) -> f64 {
+    edges(points).map(|(a, b)| a.distance(b)).sum()
+}
+
+// polygon_contains uses the even-odd rule: a ray from `point` crosses the boundary an odd number of times if the
+// point is inside. Points on the boundary count as inside.
+fn polygon_contains(points: &[Point], point: Point) -> bool {
+    if on_boundary(points, point) {
+        return true;
+    }
+
+    let mut inside = false;
+    for (a, b) in edges(points) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let crossing = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < crossing {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn corners(rect: &Rectangle) -> Vec<Point> {
+    let (x, y) = (rect.x as f64, rect.y as f64);
+    let (right, bottom) = (rect.right() as f64, rect.bottom() as f64);
+    vec![
+        Point::new(x, y),
+        Point::new(right, y),
+        Point::new(right, bottom),
+        Point::new(x, bottom),
+    ]
+}
+
+impl Shape for Rectangle {
+    fn name(&self) -> &'static str {
+        "rectangle"
+    }
+
+    fn area(&self) -> f64 {
+        Rectangle::area(self) as f64
+    }
+
+    fn perimeter(&self) -> f64 {
+        Rectangle::perimeter(self) as f64
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(corners(self)).expect("a rectangle has four corners")
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        let bounds = Shape::bounding_box(self);
+        bounds.min.x <= point.x && point.x <= bounds.max.x && bounds.min.y <= point.y && point.y <= bounds.max.y
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        corners(self)
+    }
+}
+
+// A Square is a Rectangle with one size, which it converts into. It is a separate type so that code taking a
+// Square can rely on the sides being equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Square {
+    pub x: u32,
+    pub y: u32,
+    pub size: u32,
+}
+
+impl From<Square> for Rectangle {
+    fn from(square: Square) -> Rectangle {
+        Rectangle::square(square.size).at(square.x, square.y)
+    }
+}
+
+impl Shape for Square {
+    fn name(&self) -> &'static str {
+        "square"
+    }
+
+    fn area(&self) -> f64 {
+        Shape::area(&Rectangle::from(*self))
+    }
+
+    fn perimeter(&self) -> f64 {
+        Shape::perimeter(&Rectangle::from(*self))
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        Shape::bounding_box(&Rectangle::from(*self))
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        Shape::contains_point(&Rectangle::from(*self), point)
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        corners(&Rectangle::from(*self))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    center: Point,
+    radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Result<Circle, ShapeError> {
+        if !radius.is_finite() {
+            return Err(ShapeError::InvalidRadius(radius));
+        }
+        if radius < 0.0 {
+            return Err(ShapeError::NegativeRadius(radius));
+        }
+        Ok(Circle { center, radius })
+    }
+
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+impl Shape for Circle {
+    fn name(&self) -> &'static str {
+        "circle"
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * PI * self.radius
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        let (c, r) = (self.center, self.radius);
+        BoundingBox {
+            min: Point::new(c.x - r, c.y - r),
+            max: Point::new(c.x + r, c.y + r),
+        }
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        self.center.distance(point) <= self.radius * (1.0 + 1e-12)
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        (0..CIRCLE_SAMPLES)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f64 / CIRCLE_SAMPLES as f64;
+                Point::new(
+                    self.center.x + self.radius * angle.cos(),
+                    self.center.y + self.radius * angle.sin(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Triangle(pub Point, pub Point, pub Point);
+
+impl Triangle {
+    fn points(&self) -> [Point; 3] {
+        [self.0, self.1, self.2]
+    }
+}
+
+impl Shape for Triangle {
+    fn name(&self) -> &'static str {
+        "triangle"
+    }
+
+    fn area(&self) -> f64 {
+        shoelace(&self.points())
+    }
+
+    fn perimeter(&self) -> f64 {
+        polygon_perimeter(&self.points())
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(self.points().iter().copied()).expect("a triangle has three points")
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        polygon_contains(&self.points(), point)
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        self.points().to_vec()
+    }
+}
+
+// A simple polygon: its edges join each point to the next and the last back to the first, and don't cross each
+// other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    points: Vec<Point>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Point>) -> Result<Polygon, ShapeError> {
+        if points.len() < 3 {
+            return Err(ShapeError::TooFewPoints { count: points.len() });
+        }
+        Ok(Polygon { points })
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+}
+
+impl Shape for Polygon {
+    fn name(&self) -> &'static str {
+        "polygon"
+    }
+
+    fn area(&self) -> f64 {
+        shoelace(&self.points)
+    }
+
+    fn perimeter(&self) -> f64 {
+        polygon_perimeter(&self.points)
+    }
+
+    fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(self.points.iter().copied()).expect("a polygon has at least three points")
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        polygon_contains(&self.points, point)
+    }
+
+    fn outline(&self) -> Vec<Point> {
+        self.points.clone()
+    }
+}
+
+// A Scene is any mix of shapes, each identified by its position in the scene.
+#[derive(Debug, Default)]
+pub struct Scene {
+    shapes: Vec<Box<dyn Shape>>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    // add puts a shape in the scene and returns its index.
+    pub fn add<S: Shape + 'static>(&mut self, shape: S) -> usize {
+        self.shapes.push(Box::new(shape));
+        self.shapes.len() - 1
+    }
+
+    pub fn shapes(&self) -> &[Box<dyn Shape>] {
+        &self.shapes
+    }
+
+    // total_area adds up the area of every shape. Where shapes overlap, the overlap is counted once per shape.
+    pub fn total_area(&self) -> f64 {
+        self.shapes.iter().map(|shape| shape.area()).sum()
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        self.shapes
+            .iter()
+            .map(|shape| shape.bounding_box())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    // shapes_at returns the indexes of the shapes containing `point`.
+    pub fn shapes_at(&self, point: Point) -> Vec<usize> {
+        (0..self.shapes.len())
+            .filter(|&i| self.shapes[i].contains_point(point))
+            .collect()
+    }
+
+    // holdings returns every pair (outer, inner) where shape `outer` can hold shape `inner`.
+    pub fn holdings(&self) -> Vec<(usize, usize)> {
+        let mut holdings = Vec::new();
+        for (i, outer) in self.shapes.iter().enumerate() {
+            for (j, inner) in self.shapes.iter().enumerate() {
+                // Skipping shapes that can't fit by area avoids most of the outline checks.
+                if i != j && inner.area() <= outer.area() && outer.can_hold(inner.as_ref()) {
+                    holdings.push((i, j));
+                }
+            }
+        }
+        holdings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coordinates: &[(f64, f64)]) -> Vec<Point> {
+        coordinates.iter().map(|&(x, y)| Point::new(x, y)).collect()
+    }
+
+    fn polygon(coordinates: &[(f64, f64)]) -> Polygon {
+        Polygon::new(points(coordinates)).unwrap()
+    }
+
+    #[test]
+    fn unit_square() {
+        let square = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(square.area(), 1.0);
+        assert_eq!(square.perimeter(), 4.0);
+        assert_eq!(Shape::area(&Rectangle::square(1)), 1.0);
+        assert_eq!(Shape::area(&Square { x: 0, y: 0, size: 1 }), 1.0);
+    }
+
+    #[test]
+    fn right_triangle() {
+        let corners = [(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)];
+        let triangle = Triangle(Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(0.0, 3.0));
+        assert_eq!(triangle.area(), 6.0);
+        assert_eq!(triangle.perimeter(), 12.0);
+        assert_eq!(polygon(&corners).area(), 6.0);
+    }
+
+    #[test]
+    fn clockwise_and_counter_clockwise_agree() {
+        // An L shape, which isn't convex, listed one way round and then the other.
+        let l_shape = [(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)];
+        let mut reversed = l_shape;
+        reversed.reverse();
+        assert_eq!(polygon(&l_shape).area(), 3.0);
+        assert_eq!(polygon(&reversed).area(), 3.0);
+
+        // The signed sum before taking the absolute value has opposite signs for the two directions.
+        let signed = |coordinates: &[(f64, f64)]| -> f64 {
+            edges(&points(coordinates)).map(|(a, b)| a.x * b.y - b.x * a.y).sum()
+        };
+        assert_eq!(signed(&l_shape), -signed(&reversed));
+
+        // Starting from a different corner doesn't matter either.
+        let mut rotated = l_shape;
+        rotated.rotate_left(2);
+        assert_eq!(polygon(&rotated).area(), 3.0);
+    }
+
+    #[test]
+    fn area_doesnt_depend_on_position() {
+        let triangle = [(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)];
+        for &(dx, dy) in &[(10.0, 0.0), (-7.5, 2.25), (1000.0, -1000.0)] {
+            let moved: Vec<(f64, f64)> = triangle.iter().map(|&(x, y)| (x + dx, y + dy)).collect();
+            assert_eq!(polygon(&moved).area(), 6.0);
+        }
+    }
+
+    #[test]
+    fn degenerate_polygons_have_no_area() {
+        // Every point on one line.
+        assert_eq!(polygon(&[(0.0, 0.0), (1.0, 1.0), (3.0, 3.0)]).area(), 0.0);
+        assert_eq!(
+            Triangle(Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(5.0, 0.0)).area(),
+            0.0
+        );
+        // Every point the same.
+        assert_eq!(polygon(&[(2.0, 2.0), (2.0, 2.0), (2.0, 2.0)]).area(), 0.0);
+        // Going out along a line and back again.
+        assert_eq!(polygon(&[(0.0, 0.0), (4.0, 0.0), (2.0, 0.0), (0.0, 0.0)]).area(), 0.0);
+        // A repeated corner adds nothing to a real polygon.
+        assert_eq!(polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]).area(), 1.0);
+        assert_eq!(
+            Polygon::new(points(&[(0.0, 0.0), (1.0, 1.0)])),
+            Err(ShapeError::TooFewPoints { count: 2 })
+        );
+    }
+
+    // The U polygon from (0, 0) to (3, 3), with a notch from (1, 1) to (2, 3) cut out of its top.
+    fn u_shape() -> Polygon {
+        polygon(&[
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (3.0, 3.0),
+            (2.0, 3.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 3.0),
+            (0.0, 3.0),
+        ])
+    }
+
+    fn circle(x: f64, y: f64, radius: f64) -> Circle {
+        Circle::new(Point::new(x, y), radius).unwrap()
+    }
+
+    #[test]
+    fn contains_point_includes_the_boundary() {
+        let rect = Rectangle::new(10, 10);
+        assert!(Shape::contains_point(&rect, Point::new(0.0, 0.0)));
+        assert!(Shape::contains_point(&rect, Point::new(10.0, 5.0)));
+        assert!(!Shape::contains_point(&rect, Point::new(10.01, 5.0)));
+
+        let triangle = Triangle(Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(0.0, 3.0));
+        assert!(triangle.contains_point(Point::new(1.0, 1.0)));
+        assert!(triangle.contains_point(Point::new(2.0, 1.5)));
+        assert!(!triangle.contains_point(Point::new(3.0, 2.0)));
+
+        let u = u_shape();
+        assert!(u.contains_point(Point::new(0.5, 2.0)));
+        assert!(u.contains_point(Point::new(1.5, 0.5)));
+        assert!(u.contains_point(Point::new(1.0, 2.0)));
+        assert!(!u.contains_point(Point::new(1.5, 2.0)));
+        // Points level with the notch's corners, where a ray passes through vertices.
+        assert!(u.contains_point(Point::new(0.5, 1.0)));
+        assert!(u.contains_point(Point::new(2.5, 1.0)));
+        assert!(!u.contains_point(Point::new(-1.0, 1.0)));
+        assert!(!u.contains_point(Point::new(4.0, 3.0)));
+
+        let round = circle(0.0, 0.0, 2.0);
+        assert!(round.contains_point(Point::new(0.0, 2.0)));
+        assert!(round.contains_point(Point::new(1.0, 1.0)));
+        assert!(!round.contains_point(Point::new(1.5, 1.5)));
+    }
+
+    #[test]
+    fn can_hold_a_polygon_inside_a_concave_one() {
+        let u = u_shape();
+        assert!(u.can_hold(&u));
+        assert!(u.can_hold(&Triangle(Point::new(0.25, 0.25), Point::new(2.75, 0.25), Point::new(0.25, 0.75))));
+        // Lying along the U's own edges is still inside it.
+        assert!(u.can_hold(&Triangle(Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(1.5, 1.0))));
+
+        // Every corner of this triangle is inside the U, but its long edge crosses the notch.
+        let bridge = Triangle(Point::new(0.5, 2.0), Point::new(2.5, 2.0), Point::new(0.5, 2.5));
+        assert!(bridge.outline().iter().all(|&corner| u.contains_point(corner)));
+        assert!(!u.can_hold(&bridge));
+
+        // The same goes for an edge along the top of the U, across the notch's opening.
+        let lid = Triangle(Point::new(0.5, 3.0), Point::new(2.5, 3.0), Point::new(0.5, 2.5));
+        assert!(!u.can_hold(&lid));
+    }
+
+    #[test]
+    fn can_hold_circles() {
+        let square = Rectangle::new(10, 10);
+        assert!(Shape::can_hold(&square, &circle(5.0, 5.0, 4.0)));
+        assert!(Shape::can_hold(&square, &circle(5.0, 5.0, 5.0)));
+        assert!(!Shape::can_hold(&square, &circle(5.0, 5.0, 6.0)));
+        assert!(!Shape::can_hold(&square, &circle(9.0, 5.0, 2.0)));
+
+        // A 7 by 7 square's corners are 4.95 from its center, a 7.2 by 7.2 one's 5.09.
+        let round = circle(5.0, 5.0, 5.0);
+        assert!(round.can_hold(&polygon(&[(1.5, 1.5), (8.5, 1.5), (8.5, 8.5), (1.5, 8.5)])));
+        assert!(!round.can_hold(&polygon(&[(1.4, 1.4), (8.6, 1.4), (8.6, 8.6), (1.4, 8.6)])));
+        assert!(round.can_hold(&circle(6.0, 5.0, 3.0)));
+        assert!(!round.can_hold(&circle(8.0, 5.0, 3.0)));
+    }
+
+    #[test]
+    fn circles() {
+        let round = circle(1.0, 2.0, 3.0);
+        assert_eq!(round.center(), Point::new(1.0, 2.0));
+        assert_eq!(round.radius(), 3.0);
+        assert_eq!(round.area(), 9.0 * PI);
+        assert_eq!(round.perimeter(), 6.0 * PI);
+        assert_eq!(
+            round.bounding_box(),
+            BoundingBox {
+                min: Point::new(-2.0, -1.0),
+                max: Point::new(4.0, 5.0),
+            }
+        );
+        assert_eq!(round.outline().len(), CIRCLE_SAMPLES);
+        assert!(round.outline().iter().all(|&point| (point.distance(round.center()) - 3.0).abs() < 1e-9));
+
+        assert_eq!(circle(0.0, 0.0, 0.0).area(), 0.0);
+        assert_eq!(Circle::new(Point::new(0.0, 0.0), -1.0), Err(ShapeError::NegativeRadius(-1.0)));
+        assert_eq!(
+            Circle::new(Point::new(0.0, 0.0), f64::INFINITY),
+            Err(ShapeError::InvalidRadius(f64::INFINITY))
+        );
+        // NaN isn't equal to itself, so the error is matched rather than compared.
+        assert!(matches!(
+            Circle::new(Point::new(0.0, 0.0), f64::NAN),
+            Err(ShapeError::InvalidRadius(radius)) if radius.is_nan()
+        ));
+    }
+
+    #[test]
+    fn scenes() {
+        let mut scene = Scene::new();
+        assert_eq!(scene.total_area(), 0.0);
+        assert_eq!(scene.bounding_box(), None);
+        assert!(scene.shapes_at(Point::new(0.0, 0.0)).is_empty());
+        assert!(scene.holdings().is_empty());
+
+        assert_eq!(scene.add(Rectangle::new(10, 10)), 0);
+        assert_eq!(scene.add(circle(5.0, 5.0, 2.0)), 1);
+        assert_eq!(scene.add(Triangle(Point::new(20.0, 20.0), Point::new(30.0, 20.0), Point::new(20.0, 30.0))), 2);
+        assert_eq!(scene.shapes().len(), 3);
+
+        assert_eq!(scene.total_area(), 100.0 + 4.0 * PI + 50.0);
+        assert_eq!(
+            scene.bounding_box(),
+            Some(BoundingBox {
+                min: Point::new(0.0, 0.0),
+                max: Point::new(30.0, 30.0),
+            })
+        );
+        assert_eq!(scene.shapes_at(Point::new(5.0, 5.0)), vec![0, 1]);
+        assert_eq!(scene.shapes_at(Point::new(1.0, 1.0)), vec![0]);
+        assert_eq!(scene.shapes_at(Point::new(21.0, 21.0)), vec![2]);
+        assert!(scene.shapes_at(Point::new(15.0, 15.0)).is_empty());
+        assert_eq!(scene.holdings(), vec![(0, 1)]);
+
+        // Two copies of one shape hold each other.
+        scene.add(circle(5.0, 5.0, 2.0));
+        assert_eq!(scene.holdings(), vec![(0, 1), (0, 3), (1, 3), (3, 1)]);
+    }
+}
@@ -0,0 +1,152 @@
+/*
+
+A minimal SVG writer for looking at rectangles.
+
+Svg collects elements and writes them out as a standalone document through Display, e.g.
+
+let mut svg = Svg::new(100, 50);
+svg.rect(&Rectangle::new(30, 20), "steelblue");
+svg.label(15, 10, "panel");
+fs::write("out.svg", svg.to_string())?;
+
+Coordinates are the same as Rectangle's: (0, 0) is the top left corner and y grows downwards.
+
+*/
+
+use std::fmt;
+
+use crate::rectangle::Rectangle;
+
+// fill picks a color for the `index`th shape. Stepping the hue by the golden angle keeps neighbouring indexes far
+// apart in color.
+pub fn fill(index: usize) -> String {
+    format!("hsl({}, 65%, 70%)", (index * 137) % 360)
+}
+
+pub struct Svg {
+    width: u64,
+    height: u64,
+    elements: Vec<String>,
+}
+
+// escape replaces the characters that have a meaning in XML.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl Svg {
+    pub fn new(width: u64, height: u64) -> Svg {
+        Svg {
+            width,
+            height,
+            elements: Vec::new(),
+        }
+    }
+
+    // scene draws every rectangle where it is, each in its own color and labelled with its size and position. The
+    // image runs from the origin to the furthest right and bottom edges, so positions are kept.
+    pub fn scene(rectangles: &[Rectangle]) -> Svg {
+        let width = rectangles.iter().map(Rectangle::right).max().unwrap_or(0);
+        let height = rectangles.iter().map(Rectangle::bottom).max().unwrap_or(0);
+        let mut svg = Svg::new(width, height);
+        for (i, rect) in rectangles.iter().enumerate() {
+            svg.rect(rect, &fill(i));
+        }
+        // Labels go on top of every rectangle, so overlapping rectangles can't hide them.
+        for rect in rectangles {
+            svg.label(
+                rect.x as u64 + rect.width as u64 / 2,
+                rect.y as u64 + rect.height as u64 / 2,
+                &rect.to_string(),
+            );
+        }
+        svg
+    }
+
+    // rect draws a rectangle filled with `fill`, which can be any SVG color, e.g. "red", "#ff0000" or
+    // "hsl(0, 100%, 50%)".
+    pub fn rect(&mut self, rect: &Rectangle, fill: &str) {
+        self.elements.push(format!(
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="black" stroke-width="1"/>"#,
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            escape(fill)
+        ));
+    }
+
+    // label writes `text` centered on (x, y).
+    pub fn label(&mut self, x: u64, y: u64, text: &str) {
+        self.elements.push(format!(
+            r#"<text x="{}" y="{}" font-family="monospace" font-size="12" text-anchor="middle" dominant-baseline="middle">{}</text>"#,
+            x,
+            y,
+            escape(text)
+        ));
+    }
+}
+
+impl fmt::Display for Svg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = self.width,
+            h = self.height
+        )?;
+        for element in &self.elements {
+            writeln!(f, "  {}", element)?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(escape(r#"<a href="x">&</a>"#), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        // Already escaped text is escaped again, so it shows up as typed.
+        assert_eq!(escape("&amp;"), "&amp;amp;");
+
+        let mut svg = Svg::new(10, 10);
+        svg.rect(&Rectangle::new(1, 1), r#"red" onload="alert(1)"#);
+        svg.label(5, 5, "</text><script>");
+        let document = svg.to_string();
+        assert!(document.contains(r#"fill="red&quot; onload=&quot;alert(1)""#));
+        assert!(document.contains(">&lt;/text&gt;&lt;script&gt;</text>"));
+        assert!(!document.contains("<script>"));
+    }
+
+    #[test]
+    fn scenes() {
+        let svg = Svg::scene(&[Rectangle::new(30, 20), Rectangle::new(10, 10).at(25, 15)]);
+        let document = svg.to_string();
+        let lines: Vec<&str> = document.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="35" height="25" viewBox="0 0 35 25">"#
+        );
+        assert_eq!(lines.last(), Some(&"</svg>"));
+        // Both rectangles, then both labels.
+        assert_eq!(lines.len(), 6);
+        assert!(lines[1].starts_with(r#"  <rect x="0" y="0" width="30" height="20" fill="hsl(0, 65%, 70%)""#));
+        assert!(lines[2].starts_with(r#"  <rect x="25" y="15" width="10" height="10" fill="hsl(137, 65%, 70%)""#));
+        assert!(lines[3].starts_with(r#"  <text x="15" y="10""#) && lines[3].ends_with(">30x20</text>"));
+        assert!(lines[4].starts_with(r#"  <text x="30" y="20""#) && lines[4].ends_with(">10x10@25,15</text>"));
+    }
+}
@@ -0,0 +1,21 @@
+/*
+
+Helpers shared by the tests in this crate, and by the benchmark, which includes this file as a module of its own.
+
+*/
+
+// Random is a small linear congruential generator. Every test seeds its own, so a failing case fails the same way
+// each time it runs, and the benchmark measures the same work every time.
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self) -> u32 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) as u32
+    }
+
+    // below returns a number from 0 up to but not including `n`.
+    pub fn below(&mut self, n: u32) -> u32 {
+        self.next() % n
+    }
+}
@@ -0,0 +1,265 @@
+/*
+
+`#[derive(Patch)]`, struct update syntax at runtime. See `src/patch.rs` in the structs crate for what it generates
+and how to use it.
+
+The derive reads the struct with syn and writes the Diff and Patch types with quote. Named and tuple structs are
+both supported, with any generics and any other attributes on the struct and its fields. Two attributes belong to
+the derive itself:
+
+#[patch(diff = AccountDiff, patch = AccountPatch)]  on the struct, to name the generated types. They default to
+                                                    the struct's name followed by Diff and Patch.
+#[patch(skip)]                                      on a field, to leave it out of diffs and patches. Applying a
+                                                    patch always takes a skipped field from the base value.
+
+The generated `to_json` uses the structs crate's json module. Where that crate is found is looked up in the
+Cargo.toml of the crate being compiled with proc-macro-crate, so it works under a renamed dependency and from
+inside the structs crate itself.
+
+*/
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, Index, Member, Type};
+
+#[proc_macro_derive(Patch, attributes(patch))]
+pub fn derive_patch(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+// Field is one field of the struct being derived, along with where it goes in the Diff and Patch types.
+struct Field {
+    // How the field is reached on the struct: a name, or an index for a tuple struct.
+    member: Member,
+    // How the field is reached on the Diff and Patch types. It differs from `member` only for a tuple struct with
+    // skipped fields, since the generated types leave those out and so number their fields differently.
+    patched: Member,
+    // The name `changed` reports, e.g. "email" or "1".
+    name: String,
+    ty: Type,
+}
+
+// Split is the struct's fields, divided into those that are patched and those marked `#[patch(skip)]`.
+struct Split {
+    patched: Vec<Field>,
+    skipped: Vec<(Member, Type)>,
+}
+
+// Names holds the struct level `#[patch(...)]` settings.
+struct Names {
+    diff: Ident,
+    patch: Ident,
+}
+
+fn names(input: &DeriveInput) -> syn::Result<Names> {
+    let mut names = Names {
+        diff: format_ident!("{}Diff", input.ident),
+        patch: format_ident!("{}Patch", input.ident),
+    };
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("patch")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("diff") {
+                names.diff = meta.value()?.parse()?;
+            } else if meta.path.is_ident("patch") {
+                names.patch = meta.value()?.parse()?;
+            } else {
+                return Err(meta.error("expected `diff = Name` or `patch = Name`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(names)
+}
+
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("patch")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+// json_module returns the path to the structs crate's json module as seen from the crate being compiled.
+fn json_module() -> syn::Result<TokenStream2> {
+    let found = crate_name("structs").map_err(|e| {
+        syn::Error::new(Span::call_site(), format!("Patch needs the structs crate as a dependency: {}", e))
+    })?;
+    match found {
+        // A binary in the structs package is a crate of its own, which reaches the library by name like any
+        // other dependent does. proc-macro-crate can't tell it apart from the library.
+        FoundCrate::Itself if std::env::var_os("CARGO_BIN_NAME").is_some() => Ok(quote!(::structs::json)),
+        FoundCrate::Itself => Ok(quote!(crate::json)),
+        FoundCrate::Name(name) => {
+            let name = Ident::new(&name, Span::call_site());
+            Ok(quote!(::#name::json))
+        }
+    }
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Split> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "Patch can only be derived for structs")),
+    };
+    if let Fields::Unit = fields {
+        return Err(syn::Error::new_spanned(&input.ident, "Patch needs a struct with fields"));
+    }
+
+    let mut patched = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        };
+        if is_skipped(field)? {
+            skipped.push((member, field.ty.clone()));
+            continue;
+        }
+
+        let (patched_member, name) = match &field.ident {
+            Some(ident) => (member.clone(), ident.to_string()),
+            None => (Member::Unnamed(Index::from(patched.len())), i.to_string()),
+        };
+        patched.push(Field {
+            member,
+            patched: patched_member,
+            name,
+            ty: field.ty.clone(),
+        });
+    }
+    Ok(Split { patched, skipped })
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Names { diff, patch } = names(input)?;
+    let Split { patched: fields, skipped } = fields(input)?;
+    let json = json_module()?;
+    let tuple = matches!(&input.data, Data::Struct(data) if matches!(data.fields, Fields::Unnamed(_)));
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let members: Vec<&Member> = fields.iter().map(|field| &field.member).collect();
+    let patched: Vec<&Member> = fields.iter().map(|field| &field.patched).collect();
+    let names: Vec<&str> = fields.iter().map(|field| field.name.as_str()).collect();
+    let types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let skipped_members: Vec<&Member> = skipped.iter().map(|(member, _)| member).collect();
+    let skipped_types: Vec<&Type> = skipped.iter().map(|(_, ty)| ty).collect();
+
+    // Every field must be Clone to be copied out of a base value, and patched fields must also be PartialEq to be
+    // diffed. Bounding the field types rather than the type parameters keeps a Point<T> patchable for any T that
+    // works.
+    let mut generics = input.generics.clone();
+    let bounds = generics.make_where_clause();
+    for ty in &types {
+        bounds.predicates.push(syn::parse_quote!(#ty: ::core::clone::Clone + ::core::cmp::PartialEq));
+    }
+    for ty in &skipped_types {
+        bounds.predicates.push(syn::parse_quote!(#ty: ::core::clone::Clone));
+    }
+
+    // Braces work for building tuple structs too, e.g. `Point { 0: x, 1: y, 2: z }`, so only the definitions
+    // differ between the two kinds of struct.
+    let bools = types.iter().map(|_| quote!(bool));
+    let definitions = if tuple {
+        quote! {
+            #vis struct #diff(#(pub #bools),*);
+
+            #[derive(Debug, Clone, PartialEq)]
+            #vis struct #patch #impl_generics (#(pub ::core::option::Option<#types>),*) #where_clause;
+        }
+    } else {
+        quote! {
+            #vis struct #diff { #(pub #patched: bool),* }
+
+            #[derive(Debug, Clone, PartialEq)]
+            #vis struct #patch #impl_generics #where_clause { #(pub #patched: ::core::option::Option<#types>),* }
+        }
+    };
+
+    Ok(quote! {
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        #definitions
+
+        impl #diff {
+            // changed lists the names of the fields that differ, in declaration order.
+            pub fn changed(&self) -> ::std::vec::Vec<&'static str> {
+                let mut changed = ::std::vec::Vec::new();
+                #(if self.#patched { changed.push(#names); })*
+                changed
+            }
+
+            pub fn is_empty(&self) -> bool {
+                true #(&& !self.#patched)*
+            }
+        }
+
+        // Default is written out because deriving it would require every type parameter to implement Default.
+        impl #impl_generics ::core::default::Default for #patch #ty_generics #where_clause {
+            fn default() -> Self {
+                #patch { #(#patched: ::core::option::Option::None),* }
+            }
+        }
+
+        impl #impl_generics #patch #ty_generics #bounds {
+            // apply builds a new value from the patched fields, taking every other field from `base`.
+            pub fn apply(&self, base: &#name #ty_generics) -> #name #ty_generics {
+                #name {
+                    #(#members: self.#patched.clone().unwrap_or_else(|| base.#members.clone()),)*
+                    #(#skipped_members: base.#skipped_members.clone(),)*
+                }
+            }
+
+            pub fn is_empty(&self) -> bool {
+                true #(&& self.#patched.is_none())*
+            }
+
+            // to_json lists only the patched fields. It fails only when a field can't be written as JSON.
+            //
+            // The bounds are higher-ranked so that the compiler checks them where to_json is called rather than
+            // here. A plain `Vec<u8>: ToValue` would be a false bound on a concrete type, which is an error even
+            // if to_json is never called, and would stop structs with such fields from deriving Patch at all.
+            pub fn to_json(&self) -> ::core::result::Result<#json::Object, #json::JsonError>
+            where
+                #(for<'json> #types: #json::ToValue,)*
+            {
+                let mut object = #json::Object::new();
+                #(
+                    if let ::core::option::Option::Some(value) = &self.#patched {
+                        object = object.with(#names, #json::ToValue::to_value(value)?);
+                    }
+                )*
+                ::core::result::Result::Ok(object)
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #bounds {
+            pub fn diff(&self, other: &Self) -> #diff {
+                #diff { #(#patched: self.#members != other.#members),* }
+            }
+
+            // patch_to returns the patch that turns `self` into `other`.
+            pub fn patch_to(&self, other: &Self) -> #patch #ty_generics {
+                #patch {
+                    #(#patched: if self.#members != other.#members {
+                        ::core::option::Option::Some(other.#members.clone())
+                    } else {
+                        ::core::option::Option::None
+                    }),*
+                }
+            }
+        }
+    })
+}
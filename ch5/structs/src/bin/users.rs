@@ -0,0 +1,79 @@
+/*
+
+A small account CLI on top of UserStore.
+
+users [--store FILE] add USERNAME EMAIL    // create a user, reading their password from stdin
+users [--store FILE] login USERNAME        // check a password read from stdin and record the sign-in
+
+The store defaults to `users.jsonl` in the current directory. Passwords are read as the first line of stdin, e.g.
+`echo 'correct horse 42' | users login alice`, so they never show up in the process list or shell history.
+
+Hashing is deliberately slow, and unoptimized it takes seconds per password, so run it with
+`cargo run --release --bin users -- ...`.
+
+*/
+
+use std::env;
+use std::error::Error;
+use std::io::{self, BufRead};
+use std::process;
+
+use structs::lifecycle::{self, SignInPolicy};
+use structs::password::{HashParams, PasswordPolicy};
+use structs::store::UserStore;
+use structs::user::build_user;
+use structs::validation::{Email, Username};
+
+const USAGE: &str = "usage: users [--store FILE] (add USERNAME EMAIL | login USERNAME)";
+
+fn read_password() -> io::Result<String> {
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\n', '\r']).to_string())
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (path, args) = match args {
+        [flag, path, rest @ ..] if flag == "--store" => (path.as_str(), rest),
+        _ => ("users.jsonl", args),
+    };
+    let mut store = UserStore::open(path)?;
+
+    match args {
+        [command, username, email] if command == "add" => {
+            let mut user = build_user(Email::parse(email)?, Username::parse(username)?);
+            let password = read_password()?;
+
+            // The user is created with their password in one change, so no user is ever stored without one.
+            user.password_hash = Some(PasswordPolicy::default().hash(&password, &HashParams::default())?);
+            let username = user.username.clone();
+            store.create(user)?;
+            println!("added {}", username);
+        }
+        [command, username] if command == "login" => {
+            let password = read_password()?;
+            let user = store.authenticate(
+                username,
+                &password,
+                lifecycle::now(),
+                &SignInPolicy::default(),
+                &HashParams::default(),
+            )?;
+            println!("welcome back, {} (sign-in #{})", user.username, user.sign_in_count);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("users: {}", e);
+        process::exit(1);
+    }
+}
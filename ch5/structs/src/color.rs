@@ -0,0 +1,571 @@
+/*
+
+Colors for theming terminal output.
+
+Color is still the tuple struct from the chapter, but with u8 components instead of i32, so every Color is a valid
+8-bit RGB value. Values that arrive as wider integers go through `Color::try_from((r, g, b))`, which rejects
+anything outside 0-255 instead of silently wrapping it.
+
+Colors can be parsed from the forms CSS uses:
+
+#ff8800 or #f80         hex, with 6 or 3 digits
+rgb(255, 136, 0)        decimal components, separated by commas or spaces
+orange                  one of the names in NAMED
+
+and converted to and from HSL and HSV, which are easier to adjust by hand: lightening a color is a change to one
+number in HSL, but to all three in RGB.
+
+`contrast_ratio` follows WCAG 2.x: text needs a ratio of at least 4.5 against its background to be readable
+(`MIN_TEXT_CONTRAST`), or 3 for large text. `paint` wraps text in 24-bit ANSI escape codes, and `ansi256` picks the
+closest color in the 256 color palette for terminals that don't support 24-bit color.
+
+*/
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::patch::Patch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Patch)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+// The minimum contrast ratio WCAG AA asks for between normal sized text and its background.
+pub const MIN_TEXT_CONTRAST: f64 = 4.5;
+
+pub const RESET: &str = "\x1b[0m";
+
+pub const NAMED: &[(&str, Color)] = &[
+    ("black", Color(0, 0, 0)),
+    ("silver", Color(192, 192, 192)),
+    ("gray", Color(128, 128, 128)),
+    ("white", Color(255, 255, 255)),
+    ("maroon", Color(128, 0, 0)),
+    ("red", Color(255, 0, 0)),
+    ("purple", Color(128, 0, 128)),
+    ("fuchsia", Color(255, 0, 255)),
+    ("green", Color(0, 128, 0)),
+    ("lime", Color(0, 255, 0)),
+    ("olive", Color(128, 128, 0)),
+    ("yellow", Color(255, 255, 0)),
+    ("navy", Color(0, 0, 128)),
+    ("blue", Color(0, 0, 255)),
+    ("teal", Color(0, 128, 128)),
+    ("aqua", Color(0, 255, 255)),
+    ("orange", Color(255, 165, 0)),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColorError {
+    OutOfRange { value: i64 },
+    InvalidHex(String),
+    InvalidRgb(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::OutOfRange { value } => write!(f, "color component {} is outside 0-255", value),
+            ColorError::InvalidHex(s) => write!(f, "{:?} is not a #rgb or #rrggbb color", s),
+            ColorError::InvalidRgb(s) => write!(f, "{:?} is not an rgb(r, g, b) color", s),
+            ColorError::UnknownName(s) => write!(f, "unknown color name {:?}", s),
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+// Hue is in degrees, 0 up to 360. Saturation and lightness are fractions from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    pub hue: f64,
+    pub saturation: f64,
+    pub lightness: f64,
+}
+
+// Hue is in degrees, 0 up to 360. Saturation and value are fractions from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub hue: f64,
+    pub saturation: f64,
+    pub value: f64,
+}
+
+fn component(value: i64) -> Result<u8, ColorError> {
+    u8::try_from(value).map_err(|_| ColorError::OutOfRange { value })
+}
+
+// to_u8 scales a fraction from 0 to 1 to a component.
+fn to_u8(fraction: f64) -> u8 {
+    (fraction.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+// hue returns the hue in degrees of a color whose largest component is `max` and whose chroma (max - min) is
+// `chroma`. Grays have no hue, and get 0.
+fn hue(r: f64, g: f64, b: f64, max: f64, chroma: f64) -> f64 {
+    if chroma == 0.0 {
+        return 0.0;
+    }
+    let sector = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    sector * 60.0
+}
+
+// from_hue is the half of the HSL and HSV to RGB conversions they share: a hue, a chroma and the amount `m` to add to
+// every component.
+fn from_hue(hue: f64, chroma: f64, m: f64) -> Color {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Color(to_u8(r + m), to_u8(g + m), to_u8(b + m))
+}
+
+// The component levels of the 6x6x6 color cube in the 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn nearest_cube_level(c: u8) -> usize {
+    (0..CUBE_LEVELS.len())
+        .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+        .unwrap_or(0)
+}
+
+impl Color {
+    pub fn red(&self) -> u8 {
+        self.0
+    }
+
+    pub fn green(&self) -> u8 {
+        self.1
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.2
+    }
+
+    pub fn named(name: &str) -> Option<Color> {
+        NAMED
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, color)| color)
+    }
+
+    // name returns the name of this color, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        NAMED.iter().find(|(_, color)| color == self).map(|&(name, _)| name)
+    }
+
+    pub fn to_hsl(&self) -> Hsl {
+        let (r, g, b) = self.fractions();
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let lightness = (max + min) / 2.0;
+        let saturation = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        Hsl {
+            hue: hue(r, g, b, max, chroma),
+            saturation,
+            lightness,
+        }
+    }
+
+    pub fn to_hsv(&self) -> Hsv {
+        let (r, g, b) = self.fractions();
+        let max = r.max(g).max(b);
+        let chroma = max - r.min(g).min(b);
+        Hsv {
+            hue: hue(r, g, b, max, chroma),
+            saturation: if max == 0.0 { 0.0 } else { chroma / max },
+            value: max,
+        }
+    }
+
+    fn fractions(&self) -> (f64, f64, f64) {
+        (self.0 as f64 / 255.0, self.1 as f64 / 255.0, self.2 as f64 / 255.0)
+    }
+
+    // blend mixes in `amount` of `other`: 0 is this color, 1 is `other` and 0.5 is halfway between the two.
+    pub fn blend(&self, other: Color, amount: f64) -> Color {
+        let amount = amount.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * amount).round() as u8;
+        Color(mix(self.0, other.0), mix(self.1, other.1), mix(self.2, other.2))
+    }
+
+    // lighten raises the HSL lightness by `amount`, e.g. 0.1 for ten percentage points. A negative amount darkens.
+    pub fn lighten(&self, amount: f64) -> Color {
+        let mut hsl = self.to_hsl();
+        hsl.lightness = (hsl.lightness + amount).clamp(0.0, 1.0);
+        Color::from(hsl)
+    }
+
+    pub fn darken(&self, amount: f64) -> Color {
+        self.lighten(-amount)
+    }
+
+    // relative_luminance is how bright the color looks, from 0 for black to 1 for white, as WCAG defines it.
+    pub fn relative_luminance(&self) -> f64 {
+        let linear = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.0) + 0.7152 * linear(self.1) + 0.0722 * linear(self.2)
+    }
+
+    // contrast_ratio ranges from 1, for two identical colors, to 21, for black against white. The order of the
+    // colors doesn't matter.
+    pub fn contrast_ratio(&self, other: Color) -> f64 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+
+    // readable_on reports whether text in this color is readable on `background`.
+    pub fn readable_on(&self, background: Color) -> bool {
+        self.contrast_ratio(background) >= MIN_TEXT_CONTRAST
+    }
+
+    // fg returns the 24-bit ANSI escape code that sets this as the text color.
+    pub fn fg(&self) -> String {
+        format!("\x1b[38;2;{};{};{}m", self.0, self.1, self.2)
+    }
+
+    // bg returns the 24-bit ANSI escape code that sets this as the background color.
+    pub fn bg(&self) -> String {
+        format!("\x1b[48;2;{};{};{}m", self.0, self.1, self.2)
+    }
+
+    // paint wraps `text` in this color, resetting the terminal's colors after it.
+    pub fn paint(&self, text: &str) -> String {
+        format!("{}{}{}", self.fg(), text, RESET)
+    }
+
+    // ansi256 returns the closest color in the 256 color palette, choosing between the 6x6x6 color cube (16-231)
+    // and the gray ramp (232-255). The first 16 colors are left out, since terminals let users redefine them.
+    pub fn ansi256(&self) -> u8 {
+        let (r, g, b) = (
+            nearest_cube_level(self.0),
+            nearest_cube_level(self.1),
+            nearest_cube_level(self.2),
+        );
+        let cube = Color(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+        // The gray ramp runs from 8 to 238 in steps of 10.
+        let average = (self.0 as u32 + self.1 as u32 + self.2 as u32) / 3;
+        let step = (average.saturating_sub(3) / 10).min(23);
+        let gray_level = (8 + step * 10) as u8;
+        let gray = Color(gray_level, gray_level, gray_level);
+
+        if self.distance(gray) < self.distance(cube) {
+            232 + step as u8
+        } else {
+            16 + (36 * r + 6 * g + b) as u8
+        }
+    }
+
+    // distance is the squared distance between two colors in RGB space.
+    fn distance(&self, other: Color) -> u32 {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+        d(self.0, other.0) + d(self.1, other.1) + d(self.2, other.2)
+    }
+}
+
+impl TryFrom<(i64, i64, i64)> for Color {
+    type Error = ColorError;
+
+    fn try_from((r, g, b): (i64, i64, i64)) -> Result<Color, ColorError> {
+        Ok(Color(component(r)?, component(g)?, component(b)?))
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Color {
+        let chroma = (1.0 - (2.0 * hsl.lightness - 1.0).abs()) * hsl.saturation;
+        from_hue(hsl.hue, chroma, hsl.lightness - chroma / 2.0)
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Color {
+        let chroma = hsv.value * hsv.saturation;
+        from_hue(hsv.hue, chroma, hsv.value - chroma)
+    }
+}
+
+fn parse_hex(s: &str) -> Result<Color, ColorError> {
+    let invalid = || ColorError::InvalidHex(s.to_string());
+    let digits = &s[1..];
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).map_err(|_| invalid());
+    match digits.len() {
+        // Each digit of #rgb is doubled, so #f80 is #ff8800.
+        3 => Ok(Color(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+        6 => {
+            let pair = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid());
+            Ok(Color(pair(0)?, pair(2)?, pair(4)?))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_rgb(s: &str) -> Result<Color, ColorError> {
+    let invalid = || ColorError::InvalidRgb(s.to_string());
+    let inner = s
+        .strip_prefix("rgb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+
+    // Components are separated either by commas, with optional spaces around them, or by spaces alone. Every comma
+    // needs a component on both sides, so "rgb(1,,2,3)" and "rgb(1,2,3,)" are rejected rather than read as 1, 2, 3.
+    let parts: Vec<&str> = if inner.contains(',') {
+        inner.split(',').map(str::trim).collect()
+    } else {
+        inner.split_whitespace().collect()
+    };
+    let components = parts
+        .iter()
+        .map(|part| part.parse::<i64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<i64>, ColorError>>()?;
+    match components[..] {
+        [r, g, b] => Color::try_from((r, g, b)),
+        _ => Err(invalid()),
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Color, ColorError> {
+        let s = s.trim();
+        if s.starts_with('#') {
+            parse_hex(s)
+        } else if s.starts_with("rgb(") {
+            parse_rgb(s)
+        } else {
+            Color::named(s).ok_or_else(|| ColorError::UnknownName(s.to_string()))
+        }
+    }
+}
+
+// Display writes the #rrggbb form, which FromStr reads back.
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl fmt::Display for Hsl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "hsl({:.0}, {:.0}%, {:.0}%)",
+            self.hue,
+            self.saturation * 100.0,
+            self.lightness * 100.0
+        )
+    }
+}
+
+impl fmt::Display for Hsv {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "hsv({:.0}, {:.0}%, {:.0}%)",
+            self.hue,
+            self.saturation * 100.0,
+            self.value * 100.0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_components() {
+        for spec in &[
+            "rgb(30,144,255)",
+            "rgb(30, 144, 255)",
+            "rgb( 30 ,144 , 255 )",
+            "rgb(30 144 255)",
+            " rgb(30  144\t255) ",
+        ] {
+            assert_eq!(spec.parse(), Ok(Color(30, 144, 255)), "{:?}", spec);
+        }
+    }
+
+    #[test]
+    fn empty_rgb_components_are_rejected() {
+        for spec in &[
+            "rgb(1,,2,3)",
+            "rgb(1,2,3,)",
+            "rgb(,1,2,3)",
+            "rgb(1, ,2)",
+            "rgb(1,2)",
+            "rgb(1, 2 3)",
+            "rgb()",
+            "rgb( )",
+            "rgb(1 2 3 4)",
+            "rgb(1.5, 2, 3)",
+        ] {
+            assert_eq!(
+                spec.parse::<Color>(),
+                Err(ColorError::InvalidRgb(spec.to_string())),
+                "{:?}",
+                spec
+            );
+        }
+        assert_eq!("rgb(1, 256, 0)".parse::<Color>(), Err(ColorError::OutOfRange { value: 256 }));
+        assert_eq!("rgb(-1 0 0)".parse::<Color>(), Err(ColorError::OutOfRange { value: -1 }));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for &color in &[Color(0, 0, 0), Color(255, 136, 0), Color(30, 144, 255), Color(255, 255, 255)] {
+            assert_eq!(color.to_string().parse(), Ok(color));
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn pure_hues() {
+        let hues = [
+            (Color(255, 0, 0), 0.0),
+            (Color(255, 255, 0), 60.0),
+            (Color(0, 255, 0), 120.0),
+            (Color(0, 255, 255), 180.0),
+            (Color(0, 0, 255), 240.0),
+            (Color(255, 0, 255), 300.0),
+        ];
+        for &(color, hue) in &hues {
+            let expected = Hsl {
+                hue,
+                saturation: 1.0,
+                lightness: 0.5,
+            };
+            assert_eq!(color.to_hsl(), expected, "{}", color);
+            assert_eq!(Color::from(expected), color);
+            let expected = Hsv {
+                hue,
+                saturation: 1.0,
+                value: 1.0,
+            };
+            assert_eq!(color.to_hsv(), expected, "{}", color);
+            assert_eq!(Color::from(expected), color);
+        }
+
+        // Grays have no hue or saturation.
+        for &(gray, lightness) in &[(Color(0, 0, 0), 0.0), (Color(255, 255, 255), 1.0)] {
+            let expected = Hsl {
+                hue: 0.0,
+                saturation: 0.0,
+                lightness,
+            };
+            assert_eq!(gray.to_hsl(), expected);
+            assert_eq!((gray.to_hsv().saturation, gray.to_hsv().value), (0.0, lightness));
+        }
+
+        let hsl = Color(30, 144, 255).to_hsl();
+        assert!(close(hsl.hue, 209.6) && close(hsl.saturation, 1.0) && close(hsl.lightness, 285.0 / 510.0));
+        // Hues wrap around.
+        assert_eq!(
+            Color::from(Hsl {
+                hue: 360.0 + 120.0,
+                saturation: 1.0,
+                lightness: 0.5,
+            }),
+            Color(0, 255, 0)
+        );
+    }
+
+    #[test]
+    fn hsl_and_hsv_round_trip() {
+        for r in (0..=255).step_by(15) {
+            for g in (0..=255).step_by(15) {
+                for b in (0..=255).step_by(15) {
+                    let color = Color(r, g, b);
+                    assert_eq!(Color::from(color.to_hsl()), color);
+                    assert_eq!(Color::from(color.to_hsv()), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blending() {
+        let (black, white) = (Color(0, 0, 0), Color(255, 255, 255));
+        assert_eq!(black.blend(white, 0.0), black);
+        assert_eq!(black.blend(white, 1.0), white);
+        assert_eq!(black.blend(white, 0.5), Color(128, 128, 128));
+        assert_eq!(Color(255, 0, 0).blend(Color(0, 0, 255), 0.25), Color(191, 0, 64));
+        // Amounts past either end are clamped.
+        assert_eq!(black.blend(white, 2.0), white);
+        assert_eq!(black.blend(white, -1.0), black);
+
+        let red = Color(255, 0, 0);
+        assert_eq!(red.lighten(0.25), Color(255, 128, 128));
+        assert_eq!(red.lighten(0.5), white);
+        assert_eq!(red.darken(0.25), Color(128, 0, 0));
+        assert_eq!(red.darken(1.0), black);
+    }
+
+    #[test]
+    fn contrast() {
+        let (black, white) = (Color(0, 0, 0), Color(255, 255, 255));
+        assert_eq!((black.relative_luminance(), white.relative_luminance()), (0.0, 1.0));
+        assert!(close(black.contrast_ratio(white), 21.0));
+        assert!(close(white.contrast_ratio(black), 21.0));
+        assert_eq!(Color(30, 144, 255).contrast_ratio(Color(30, 144, 255)), 1.0);
+
+        assert!(black.readable_on(white) && white.readable_on(black));
+        assert!(!white.readable_on(white));
+        // The darkest gray that isn't readable on white is 119, at a contrast of about 4.48.
+        assert!(Color(118, 118, 118).readable_on(white));
+        assert!(!Color(119, 119, 119).readable_on(white));
+    }
+
+    #[test]
+    fn ansi256() {
+        // The corners of the color cube.
+        assert_eq!(Color(0, 0, 0).ansi256(), 16);
+        assert_eq!(Color(255, 0, 0).ansi256(), 196);
+        assert_eq!(Color(0, 0, 255).ansi256(), 21);
+        assert_eq!(Color(255, 255, 255).ansi256(), 231);
+        // Grays the cube has are taken from it.
+        assert_eq!(Color(95, 95, 95).ansi256(), 59);
+        // The gray ramp, from 8 to 238 in steps of 10.
+        for step in 0..24u8 {
+            let level = 8 + step * 10;
+            assert_eq!(Color(level, level, level).ansi256(), 232 + step, "{}", level);
+        }
+        assert_eq!(Color(128, 128, 128).ansi256(), 244);
+        assert_eq!(Color(13, 12, 14).ansi256(), 233);
+        assert_eq!(Color(245, 245, 245).ansi256(), 255);
+    }
+}
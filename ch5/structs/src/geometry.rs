@@ -0,0 +1,557 @@
+/*
+
+3D geometry on Point.
+
+Point is still the tuple struct from the chapter, but generic over its coordinate type, so Point<i32> and Point<f64>
+share one implementation. Any type implementing Coordinate works; i32, i64, f32 and f64 do.
+
+Points and vectors are different types, even though both hold three coordinates. A point is a position and a vector
+is a displacement, and the operators only allow what makes sense for each:
+
+point - point    = vector       the displacement from one point to the other
+point + vector   = point        a point moved by a displacement
+vector + vector  = vector
+vector * scalar  = vector
+
+so adding two points, which has no meaning, doesn't compile. Color (see `src/color.rs`) is kept apart from Point the
+same way: both are three numbers in a tuple struct, but they're different types, so a Color can't be passed where a
+Point is expected. The doctests on `patch::DistinctTypes` check that this keeps holding for their patches too.
+
+Point derives Patch (see `src/patch.rs`), so `PointPatch(None, Some(-3), Some(7)).apply(&origin)` moves a point
+along y and z and leaves x where it was.
+
+Integer coordinates overflow like integers do everywhere else: `+`, `-` and `*` panic on overflow in debug builds.
+Distances are computed in f64 instead, so they can't overflow.
+
+*/
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::patch::Patch;
+
+pub trait Coordinate: Copy + PartialOrd + fmt::Debug + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> {
+    const ZERO: Self;
+
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! coordinate {
+    ($($t:ty),*) => {
+        $(
+            impl Coordinate for $t {
+                const ZERO: $t = 0 as $t;
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+coordinate!(i32, i64, f32, f64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Patch)]
+pub struct Point<T>(pub T, pub T, pub T);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Vector<T>(pub T, pub T, pub T);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    // Straight line distance.
+    Euclidean,
+    // The sum of the distances along each axis, as if moving along a grid.
+    Manhattan,
+    // The largest distance along any one axis, as a king moves on a chessboard.
+    Chebyshev,
+}
+
+impl Metric {
+    pub fn distance<T: Coordinate>(self, a: &Point<T>, b: &Point<T>) -> f64 {
+        let d = |a: T, b: T| (a.to_f64() - b.to_f64()).abs();
+        let (dx, dy, dz) = (d(a.0, b.0), d(a.1, b.1), d(a.2, b.2));
+        match self {
+            Metric::Euclidean => (dx * dx + dy * dy + dz * dz).sqrt(),
+            Metric::Manhattan => dx + dy + dz,
+            Metric::Chebyshev => dx.max(dy).max(dz),
+        }
+    }
+}
+
+// min and max for PartialOrd, since floats aren't Ord.
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+impl<T: Coordinate> Point<T> {
+    pub fn origin() -> Point<T> {
+        Point(T::ZERO, T::ZERO, T::ZERO)
+    }
+
+    // axis returns the x (0), y (1) or z (2) coordinate.
+    pub fn axis(&self, axis: usize) -> T {
+        match axis {
+            0 => self.0,
+            1 => self.1,
+            2 => self.2,
+            _ => panic!("a 3D point has no axis {}", axis),
+        }
+    }
+
+    pub fn distance(&self, other: &Point<T>, metric: Metric) -> f64 {
+        metric.distance(self, other)
+    }
+
+    // distance_squared is the squared Euclidean distance, which is exact for integer coordinates.
+    pub fn distance_squared(&self, other: &Point<T>) -> T {
+        (*other - *self).length_squared()
+    }
+
+    // to_f64 converts the coordinates, e.g. so that a Point<i32> can be compared with a Point<f64>.
+    pub fn to_f64(&self) -> Point<f64> {
+        Point(self.0.to_f64(), self.1.to_f64(), self.2.to_f64())
+    }
+}
+
+impl<T: Coordinate> Vector<T> {
+    pub fn dot(&self, other: &Vector<T>) -> T {
+        self.0 * other.0 + self.1 * other.1 + self.2 * other.2
+    }
+
+    // cross returns a vector perpendicular to both `self` and `other`, following the right hand rule.
+    pub fn cross(&self, other: &Vector<T>) -> Vector<T> {
+        Vector(
+            self.1 * other.2 - self.2 * other.1,
+            self.2 * other.0 - self.0 * other.2,
+            self.0 * other.1 - self.1 * other.0,
+        )
+    }
+
+    pub fn length_squared(&self) -> T {
+        self.dot(self)
+    }
+
+    pub fn length(&self) -> f64 {
+        let (x, y, z) = (self.0.to_f64(), self.1.to_f64(), self.2.to_f64());
+        (x * x + y * y + z * z).sqrt()
+    }
+}
+
+impl<T: Coordinate> Sub for Point<T> {
+    type Output = Vector<T>;
+
+    fn sub(self, other: Point<T>) -> Vector<T> {
+        Vector(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl<T: Coordinate> Add<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+
+    fn add(self, v: Vector<T>) -> Point<T> {
+        Point(self.0 + v.0, self.1 + v.1, self.2 + v.2)
+    }
+}
+
+impl<T: Coordinate> Sub<Vector<T>> for Point<T> {
+    type Output = Point<T>;
+
+    fn sub(self, v: Vector<T>) -> Point<T> {
+        Point(self.0 - v.0, self.1 - v.1, self.2 - v.2)
+    }
+}
+
+impl<T: Coordinate> Add for Vector<T> {
+    type Output = Vector<T>;
+
+    fn add(self, other: Vector<T>) -> Vector<T> {
+        Vector(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+impl<T: Coordinate> Sub for Vector<T> {
+    type Output = Vector<T>;
+
+    fn sub(self, other: Vector<T>) -> Vector<T> {
+        Vector(self.0 - other.0, self.1 - other.1, self.2 - other.2)
+    }
+}
+
+impl<T: Coordinate> Mul<T> for Vector<T> {
+    type Output = Vector<T>;
+
+    fn mul(self, scalar: T) -> Vector<T> {
+        Vector(self.0 * scalar, self.1 * scalar, self.2 * scalar)
+    }
+}
+
+impl<T: Coordinate + Neg<Output = T>> Neg for Vector<T> {
+    type Output = Vector<T>;
+
+    fn neg(self) -> Vector<T> {
+        Vector(-self.0, -self.1, -self.2)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Point<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.0, self.1, self.2)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Vector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{}, {}, {}>", self.0, self.1, self.2)
+    }
+}
+
+// An axis-aligned bounding box: every point with each coordinate between min's and max's, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds<T> {
+    pub min: Point<T>,
+    pub max: Point<T>,
+}
+
+impl<T: Coordinate> Bounds<T> {
+    // new returns the box with `a` and `b` as opposite corners, in either order.
+    pub fn new(a: Point<T>, b: Point<T>) -> Bounds<T> {
+        Bounds {
+            min: Point(min(a.0, b.0), min(a.1, b.1), min(a.2, b.2)),
+            max: Point(max(a.0, b.0), max(a.1, b.1), max(a.2, b.2)),
+        }
+    }
+
+    // around returns the smallest box containing every point, or None if there are none.
+    pub fn around<'a, I>(points: I) -> Option<Bounds<T>>
+    where
+        I: IntoIterator<Item = &'a Point<T>>,
+        T: 'a,
+    {
+        let mut points = points.into_iter();
+        let first = *points.next()?;
+        Some(points.fold(Bounds::new(first, first), |bounds, &point| bounds.expand(point)))
+    }
+
+    pub fn contains(&self, point: &Point<T>) -> bool {
+        (0..3).all(|axis| self.min.axis(axis) <= point.axis(axis) && point.axis(axis) <= self.max.axis(axis))
+    }
+
+    // intersects reports whether the boxes share at least one point. Boxes that only touch do.
+    pub fn intersects(&self, other: &Bounds<T>) -> bool {
+        (0..3).all(|axis| self.min.axis(axis) <= other.max.axis(axis) && other.min.axis(axis) <= self.max.axis(axis))
+    }
+
+    pub fn intersection(&self, other: &Bounds<T>) -> Option<Bounds<T>> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Some(Bounds {
+            min: Point(
+                max(self.min.0, other.min.0),
+                max(self.min.1, other.min.1),
+                max(self.min.2, other.min.2),
+            ),
+            max: Point(
+                min(self.max.0, other.max.0),
+                min(self.max.1, other.max.1),
+                min(self.max.2, other.max.2),
+            ),
+        })
+    }
+
+    // union returns the smallest box containing both boxes.
+    pub fn union(&self, other: &Bounds<T>) -> Bounds<T> {
+        self.expand(other.min).expand(other.max)
+    }
+
+    // expand returns the smallest box containing this box and `point`.
+    pub fn expand(&self, point: Point<T>) -> Bounds<T> {
+        Bounds {
+            min: Point(min(self.min.0, point.0), min(self.min.1, point.1), min(self.min.2, point.2)),
+            max: Point(max(self.max.0, point.0), max(self.max.1, point.1), max(self.max.2, point.2)),
+        }
+    }
+
+    pub fn size(&self) -> Vector<T> {
+        self.max - self.min
+    }
+
+    pub fn volume(&self) -> T {
+        let size = self.size();
+        size.0 * size.1 * size.2
+    }
+}
+
+/*
+
+PointSet answers nearest neighbour queries with a k-d tree. Each level of the tree splits the points at the median
+along one axis, cycling through x, y and z, so a query can skip any subtree that lies entirely on the far side of a
+split that is further away than the best match found so far. The distance along a single axis is never more than
+the Euclidean, Manhattan or Chebyshev distance, so the same pruning works for all three metrics.
+
+*/
+
+struct Node<T> {
+    point: Point<T>,
+    axis: usize,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+pub struct PointSet<T> {
+    nodes: Vec<Node<T>>,
+    root: Option<usize>,
+}
+
+impl<T: Coordinate> PointSet<T> {
+    pub fn new(mut points: Vec<Point<T>>) -> PointSet<T> {
+        let mut set = PointSet {
+            nodes: Vec::with_capacity(points.len()),
+            root: None,
+        };
+        set.root = set.build(&mut points, 0);
+        set
+    }
+
+    fn build(&mut self, points: &mut [Point<T>], depth: usize) -> Option<usize> {
+        if points.is_empty() {
+            return None;
+        }
+
+        let axis = depth % 3;
+        let compare = |a: &Point<T>, b: &Point<T>| a.axis(axis).partial_cmp(&b.axis(axis)).unwrap_or(Ordering::Equal);
+        points.sort_by(compare);
+        let median = points.len() / 2;
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            point: points[median],
+            axis,
+            left: None,
+            right: None,
+        });
+        let (left, rest) = points.split_at_mut(median);
+        self.nodes[index].left = self.build(left, depth + 1);
+        self.nodes[index].right = self.build(&mut rest[1..], depth + 1);
+        Some(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Point<T>> {
+        self.nodes.iter().map(|node| &node.point)
+    }
+
+    pub fn bounds(&self) -> Option<Bounds<T>> {
+        Bounds::around(self.iter())
+    }
+
+    // nearest returns the point closest to `query` under `metric` and its distance, or None if the set is empty.
+    // When several points are equally close, any one of them may be returned.
+    pub fn nearest(&self, query: &Point<T>, metric: Metric) -> Option<(Point<T>, f64)> {
+        let mut best = None;
+        self.search(self.root, query, metric, &mut best);
+        best.map(|(index, distance)| (self.nodes[index].point, distance))
+    }
+
+    fn search(&self, node: Option<usize>, query: &Point<T>, metric: Metric, best: &mut Option<(usize, f64)>) {
+        let index = match node {
+            Some(index) => index,
+            None => return,
+        };
+        let node = &self.nodes[index];
+
+        let distance = metric.distance(query, &node.point);
+        if best.is_none_or(|(_, best)| distance < best) {
+            *best = Some((index, distance));
+        }
+
+        let offset = query.axis(node.axis).to_f64() - node.point.axis(node.axis).to_f64();
+        let (near, far) = if offset < 0.0 {
+            (node.left, node.right)
+        } else {
+            (node.right, node.left)
+        };
+        self.search(near, query, metric, best);
+        if best.is_none_or(|(_, best)| offset.abs() < best) {
+            self.search(far, query, metric, best);
+        }
+    }
+}
+
+impl<T: Coordinate> FromIterator<Point<T>> for PointSet<T> {
+    fn from_iter<I: IntoIterator<Item = Point<T>>>(points: I) -> PointSet<T> {
+        PointSet::new(points.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    const METRICS: [Metric; 3] = [Metric::Euclidean, Metric::Manhattan, Metric::Chebyshev];
+
+    #[test]
+    fn dot_and_cross() {
+        let (i, j, k) = (Vector(1, 0, 0), Vector(0, 1, 0), Vector(0, 0, 1));
+        assert_eq!(i.cross(&j), k);
+        assert_eq!(j.cross(&k), i);
+        assert_eq!(k.cross(&i), j);
+        assert_eq!(j.cross(&i), -k);
+        assert_eq!(i.dot(&j), 0);
+
+        let (a, b) = (Vector(1, 2, 3), Vector(4, 5, 6));
+        assert_eq!(a.dot(&b), 32);
+        assert_eq!(a.cross(&b), Vector(-3, 6, -3));
+        // The cross product is perpendicular to both.
+        assert_eq!(a.cross(&b).dot(&a), 0);
+        assert_eq!(a.cross(&b).dot(&b), 0);
+        assert_eq!(a.cross(&a), Vector(0, 0, 0));
+
+        assert_eq!(Vector(3, 4, 0).length_squared(), 25);
+        assert_eq!(Vector(3.0, 4.0, 12.0).length(), 13.0);
+        assert_eq!(a * 2 + b - a, Vector(5, 7, 9));
+    }
+
+    #[test]
+    fn points_and_vectors() {
+        let (p, q) = (Point(1, 2, 3), Point(4, 6, 3));
+        assert_eq!(q - p, Vector(3, 4, 0));
+        assert_eq!(p + (q - p), q);
+        assert_eq!(q - (q - p), p);
+        assert_eq!(Point::<i64>::origin(), Point(0, 0, 0));
+        assert_eq!(p.to_f64(), Point(1.0, 2.0, 3.0));
+        assert_eq!((p.axis(0), p.axis(1), p.axis(2)), (1, 2, 3));
+        assert_eq!(q.to_string(), "(4, 6, 3)");
+        assert_eq!((q - p).to_string(), "<3, 4, 0>");
+    }
+
+    #[test]
+    fn distances() {
+        let (a, b) = (Point(0, 0, 0), Point(1, -2, 2));
+        assert_eq!(a.distance(&b, Metric::Euclidean), 3.0);
+        assert_eq!(a.distance(&b, Metric::Manhattan), 5.0);
+        assert_eq!(a.distance(&b, Metric::Chebyshev), 2.0);
+        assert_eq!(a.distance_squared(&b), 9);
+        for &metric in &METRICS {
+            assert_eq!(a.distance(&b, metric), b.distance(&a, metric));
+            assert_eq!(a.distance(&a, metric), 0.0);
+        }
+
+        // Distances are taken in f64, so the corners of the i32 range don't overflow.
+        let (low, high) = (Point(i32::MIN, 0, 0), Point(i32::MAX, 0, 0));
+        assert_eq!(low.distance(&high, Metric::Chebyshev), u32::MAX as f64);
+        assert_eq!(low.distance(&high, Metric::Euclidean), u32::MAX as f64);
+    }
+
+    #[test]
+    fn bounds() {
+        let bounds = Bounds::new(Point(4, 0, 5), Point(0, 3, 1));
+        assert_eq!((bounds.min, bounds.max), (Point(0, 0, 1), Point(4, 3, 5)));
+        assert_eq!(bounds.size(), Vector(4, 3, 4));
+        assert_eq!(bounds.volume(), 48);
+        assert!(bounds.contains(&Point(0, 3, 5)));
+        assert!(bounds.contains(&Point(2, 2, 2)));
+        assert!(!bounds.contains(&Point(2, 2, 0)));
+
+        let touching = Bounds::new(Point(4, 3, 5), Point(9, 9, 9));
+        assert!(bounds.intersects(&touching));
+        assert_eq!(bounds.intersection(&touching), Some(Bounds::new(Point(4, 3, 5), Point(4, 3, 5))));
+        let apart = Bounds::new(Point(5, 0, 0), Point(9, 9, 9));
+        assert!(!bounds.intersects(&apart));
+        assert_eq!(bounds.intersection(&apart), None);
+        let overlapping = Bounds::new(Point(2, 1, 0), Point(9, 2, 3));
+        assert_eq!(bounds.intersection(&overlapping), Some(Bounds::new(Point(2, 1, 1), Point(4, 2, 3))));
+
+        assert_eq!(bounds.union(&apart), Bounds::new(Point(0, 0, 0), Point(9, 9, 9)));
+        assert_eq!(bounds.expand(Point(2, 2, 2)), bounds);
+        assert_eq!(bounds.expand(Point(-1, 2, 7)), Bounds::new(Point(-1, 0, 1), Point(4, 3, 7)));
+
+        let points = [Point(1.5, -2.0, 0.0), Point(-0.5, 4.0, 1.0), Point(0.0, 0.0, -3.0)];
+        assert_eq!(
+            Bounds::around(&points),
+            Some(Bounds::new(Point(-0.5, -2.0, -3.0), Point(1.5, 4.0, 1.0)))
+        );
+        assert_eq!(Bounds::<f64>::around(&[]), None);
+        assert_eq!(Bounds::around(&points[..1]).map(|b| b.volume()), Some(0.0));
+    }
+
+    // brute_force is what PointSet::nearest is checked against: the smallest distance from `query` to any point.
+    fn brute_force<T: Coordinate>(points: &[Point<T>], query: &Point<T>, metric: Metric) -> Option<f64> {
+        points
+            .iter()
+            .map(|point| metric.distance(query, point))
+            .min_by(|a, b| a.partial_cmp(b).unwrap())
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        let mut random = Random(42);
+        for _ in 0..200 {
+            // Coordinates from a small range, so there are plenty of ties and duplicate points.
+            let mut coordinate = || random.next() as i32 % 16 - 8;
+            let count = coordinate() + 8;
+            let points: Vec<Point<i32>> = (0..count)
+                .map(|_| Point(coordinate(), coordinate(), coordinate()))
+                .collect();
+            let set: PointSet<i32> = points.iter().copied().collect();
+            assert_eq!(set.len(), points.len());
+            assert_eq!(set.bounds(), Bounds::around(&points));
+
+            for _ in 0..50 {
+                let query = Point(coordinate() * 2, coordinate() * 2, coordinate() * 2);
+                for &metric in &METRICS {
+                    let found = set.nearest(&query, metric);
+                    assert_eq!(found.map(|(_, distance)| distance), brute_force(&points, &query, metric));
+                    // Of several equally near points, any will do, as long as it is one of them.
+                    if let Some((point, distance)) = found {
+                        assert!(points.contains(&point));
+                        assert_eq!(metric.distance(&query, &point), distance);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_in_small_sets() {
+        let empty: PointSet<f64> = PointSet::new(Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(empty.nearest(&Point(0.0, 0.0, 0.0), Metric::Euclidean), None);
+        assert_eq!(empty.bounds(), None);
+
+        let one: PointSet<f64> = PointSet::new(vec![Point(1.0, 2.0, 2.0)]);
+        assert_eq!(one.nearest(&Point(0.0, 0.0, 0.0), Metric::Euclidean), Some((Point(1.0, 2.0, 2.0), 3.0)));
+
+        // Two points the same distance away in every metric.
+        let tied: PointSet<i64> = vec![Point(-1, 0, 0), Point(1, 0, 0), Point(5, 5, 5)].into_iter().collect();
+        for &metric in &METRICS {
+            let (point, distance) = tied.nearest(&Point(0, 0, 0), metric).unwrap();
+            assert!(point == Point(-1, 0, 0) || point == Point(1, 0, 0));
+            assert_eq!(distance, 1.0);
+        }
+    }
+}
@@ -0,0 +1,547 @@
+/*
+
+Just enough JSON for flat records.
+
+The account store writes one JSON object per line (JSON lines). Records here are flat: every value is a string, an
+integer, a bool or null, so that's all this module encodes and decodes. Fields keep the order they were written in,
+which keeps the files stable and easy to diff.
+
+*/
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Value {
+        Value::Number(n)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Value {
+        Value::Number(i64::from(n))
+    }
+}
+
+impl From<u32> for Value {
+    fn from(n: u32) -> Value {
+        Value::Number(i64::from(n))
+    }
+}
+
+impl From<u8> for Value {
+    fn from(n: u8) -> Value {
+        Value::Number(i64::from(n))
+    }
+}
+
+// Counters and timestamps are u64 in memory, but JSON numbers here are i64, so the few u64s past i64::MAX can't be
+// written and are an error rather than wrapping around to negative numbers.
+impl TryFrom<u64> for Value {
+    type Error = JsonError;
+
+    fn try_from(n: u64) -> Result<Value, JsonError> {
+        i64::try_from(n)
+            .map(Value::Number)
+            .map_err(|_| JsonError::OutOfRange(n.to_string()))
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Value {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+// ToValue converts a field into a JSON value. A patch from `#[derive(Patch)]` uses it to write itself out as JSON,
+// so its `to_json` is there when every field type implements it. It fails only for values JSON numbers can't hold.
+pub trait ToValue {
+    fn to_value(&self) -> Result<Value, JsonError>;
+}
+
+impl<T> ToValue for T
+where
+    T: Clone + Into<Value>,
+{
+    fn to_value(&self) -> Result<Value, JsonError> {
+        Ok(self.clone().into())
+    }
+}
+
+impl ToValue for u64 {
+    fn to_value(&self) -> Result<Value, JsonError> {
+        Value::try_from(*self)
+    }
+}
+
+impl ToValue for Option<u64> {
+    fn to_value(&self) -> Result<Value, JsonError> {
+        self.map_or(Ok(Value::Null), Value::try_from)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+    UnexpectedEnd,
+    Unexpected { found: char, at: usize },
+    InvalidNumber(String),
+    // A number too big for a JSON record to hold.
+    OutOfRange(String),
+    InvalidEscape(String),
+    MissingField(String),
+    WrongType { field: String, expected: &'static str },
+    InvalidField { field: String, message: String },
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::UnexpectedEnd => write!(f, "unexpected end of input"),
+            JsonError::Unexpected { found, at } => write!(f, "unexpected {:?} at byte {}", found, at),
+            JsonError::InvalidNumber(n) => write!(f, "invalid number {:?}", n),
+            JsonError::OutOfRange(n) => write!(f, "number {} is too large to store", n),
+            JsonError::InvalidEscape(e) => write!(f, "invalid escape {:?}", e),
+            JsonError::MissingField(field) => write!(f, "missing field {:?}", field),
+            JsonError::WrongType { field, expected } => write!(f, "field {:?} should be {}", field, expected),
+            JsonError::InvalidField { field, message } => write!(f, "field {:?} is invalid: {}", field, message),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+// Object is a decoded JSON object, with typed getters for reading records back into structs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Object {
+    fields: Vec<(String, Value)>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object::default()
+    }
+
+    // with appends a field, for building an object in one expression.
+    pub fn with<V: Into<Value>>(mut self, key: &str, value: V) -> Object {
+        self.fields.push((key.to_string(), value.into()));
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.fields.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    fn require(&self, key: &str) -> Result<&Value, JsonError> {
+        self.get(key).ok_or_else(|| JsonError::MissingField(key.to_string()))
+    }
+
+    fn wrong_type(key: &str, expected: &'static str) -> JsonError {
+        JsonError::WrongType {
+            field: key.to_string(),
+            expected,
+        }
+    }
+
+    pub fn string(&self, key: &str) -> Result<&str, JsonError> {
+        match self.require(key)? {
+            Value::String(s) => Ok(s),
+            _ => Err(Object::wrong_type(key, "a string")),
+        }
+    }
+
+    pub fn number(&self, key: &str) -> Result<i64, JsonError> {
+        match self.require(key)? {
+            Value::Number(n) => Ok(*n),
+            _ => Err(Object::wrong_type(key, "a number")),
+        }
+    }
+
+    // unsigned reads a number field into an unsigned type, e.g. `object.unsigned::<u64>("count")`. A negative or
+    // too large number is an error rather than wrapping around.
+    pub fn unsigned<T: TryFrom<i64>>(&self, key: &str) -> Result<T, JsonError> {
+        let n = self.number(key)?;
+        T::try_from(n).map_err(|_| Object::out_of_range(key, n))
+    }
+
+    fn out_of_range(key: &str, n: i64) -> JsonError {
+        JsonError::InvalidField {
+            field: key.to_string(),
+            message: format!("{} is out of range", n),
+        }
+    }
+
+    pub fn bool(&self, key: &str) -> Result<bool, JsonError> {
+        match self.require(key)? {
+            Value::Bool(b) => Ok(*b),
+            _ => Err(Object::wrong_type(key, "a bool")),
+        }
+    }
+
+    // parsed reads a string field and parses it into a validated type, e.g. `object.parsed::<Email>("email")`.
+    pub fn parsed<T>(&self, key: &str) -> Result<T, JsonError>
+    where
+        T: std::str::FromStr,
+        T::Err: fmt::Display,
+    {
+        self.string(key)?.parse().map_err(|e: T::Err| JsonError::InvalidField {
+            field: key.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    // optional_number treats a missing field the same as null, so older records without the field still load.
+    pub fn optional_number(&self, key: &str) -> Result<Option<i64>, JsonError> {
+        match self.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Number(n)) => Ok(Some(*n)),
+            Some(_) => Err(Object::wrong_type(key, "a number or null")),
+        }
+    }
+
+    // optional_unsigned is unsigned for fields that may be missing or null.
+    pub fn optional_unsigned<T: TryFrom<i64>>(&self, key: &str) -> Result<Option<T>, JsonError> {
+        match self.optional_number(key)? {
+            Some(n) => T::try_from(n).map(Some).map_err(|_| Object::out_of_range(key, n)),
+            None => Ok(None),
+        }
+    }
+
+    // optional_string treats a missing field the same as null, so older records without the field still load.
+    pub fn optional_string(&self, key: &str) -> Result<Option<&str>, JsonError> {
+        match self.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(Object::wrong_type(key, "a string or null")),
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl fmt::Display for Object {
+    // Objects display as compact single-line JSON, ready to be written as one JSON lines record.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::from("{");
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            write_string(&mut out, key);
+            out.push(':');
+            match value {
+                Value::Null => out.push_str("null"),
+                Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+                Value::Number(n) => out.push_str(&n.to_string()),
+                Value::String(s) => write_string(&mut out, s),
+            }
+        }
+        out.push('}');
+        f.write_str(&out)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Result<char, JsonError> {
+        let c = self.peek().ok_or(JsonError::UnexpectedEnd)?;
+        self.pos += c.len_utf8();
+        Ok(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_whitespace() {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        let at = self.pos;
+        match self.next()? {
+            c if c == expected => Ok(()),
+            found => Err(JsonError::Unexpected { found, at }),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, JsonError> {
+        if self.input[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            let at = self.pos;
+            Err(JsonError::Unexpected { found: self.next()?, at })
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.next()? {
+                '"' => return Ok(s),
+                '\\' => match self.next()? {
+                    '"' => s.push('"'),
+                    '\\' => s.push('\\'),
+                    '/' => s.push('/'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'u' => s.push(self.unicode_escape()?),
+                    other => return Err(JsonError::InvalidEscape(format!("\\{}", other))),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    // hex4 reads the four hex digits of a `\u` escape.
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let hex = self.input.get(self.pos..self.pos + 4).ok_or(JsonError::UnexpectedEnd)?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(JsonError::InvalidEscape(format!("\\u{}", hex)));
+        }
+        self.pos += 4;
+        u32::from_str_radix(hex, 16).map_err(|_| JsonError::InvalidEscape(format!("\\u{}", hex)))
+    }
+
+    // unicode_escape reads what follows `\u`. Chars outside the Basic Multilingual Plane are escaped as a UTF-16
+    // surrogate pair, e.g. "\ud83d\ude00" for U+1F600, and a surrogate on its own isn't a char at all.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let start = self.pos - 2;
+        let high = self.hex4()?;
+        let c = match high {
+            0xD800..=0xDBFF if self.input[self.pos..].starts_with("\\u") => {
+                self.pos += 2;
+                match self.hex4()? {
+                    low @ 0xDC00..=0xDFFF => char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)),
+                    _ => None,
+                }
+            }
+            _ => char::from_u32(high),
+        };
+        c.ok_or_else(|| JsonError::InvalidEscape(self.input[start..self.pos].to_string()))
+    }
+
+    fn number(&mut self) -> Result<Value, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let text = &self.input[start..self.pos];
+        text.parse()
+            .map(Value::Number)
+            .map_err(|_| JsonError::InvalidNumber(text.to_string()))
+    }
+
+    fn value(&mut self) -> Result<Value, JsonError> {
+        self.skip_whitespace();
+        match self.peek().ok_or(JsonError::UnexpectedEnd)? {
+            '"' => self.string().map(Value::String),
+            't' => self.keyword("true", Value::Bool(true)),
+            'f' => self.keyword("false", Value::Bool(false)),
+            'n' => self.keyword("null", Value::Null),
+            '-' | '0'..='9' => self.number(),
+            found => Err(JsonError::Unexpected { found, at: self.pos }),
+        }
+    }
+
+    fn object(&mut self) -> Result<Object, JsonError> {
+        let mut object = Object::new();
+        self.expect('{')?;
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(object);
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.expect(':')?;
+            let value = self.value()?;
+            object.fields.push((key, value));
+
+            self.skip_whitespace();
+            let at = self.pos;
+            match self.next()? {
+                ',' => continue,
+                '}' => return Ok(object),
+                found => return Err(JsonError::Unexpected { found, at }),
+            }
+        }
+    }
+}
+
+// parse_object parses a single flat JSON object, such as one line of a JSON lines file.
+pub fn parse_object(input: &str) -> Result<Object, JsonError> {
+    let mut parser = Parser { input, pos: 0 };
+    let object = parser.object()?;
+
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(object),
+        Some(found) => Err(JsonError::Unexpected { found, at: parser.pos }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn u64_past_i64_max_is_an_error() {
+        assert_eq!(Value::try_from(0u64), Ok(Value::Number(0)));
+        assert_eq!(Value::try_from(i64::MAX as u64), Ok(Value::Number(i64::MAX)));
+        assert_eq!(
+            Value::try_from(i64::MAX as u64 + 1),
+            Err(JsonError::OutOfRange(String::from("9223372036854775808")))
+        );
+        assert!(u64::MAX.to_value().is_err());
+        assert_eq!(Some(7u64).to_value(), Ok(Value::Number(7)));
+        assert_eq!(None::<u64>.to_value(), Ok(Value::Null));
+        assert!(Some(u64::MAX).to_value().is_err());
+    }
+
+    #[test]
+    fn negative_numbers_are_not_read_as_unsigned() {
+        let object = parse_object(r#"{"count": -1, "big": 4294967296, "ok": 42, "none": null}"#).unwrap();
+        assert!(matches!(
+            object.unsigned::<u64>("count"),
+            Err(JsonError::InvalidField { ref field, .. }) if field == "count"
+        ));
+        assert!(object.unsigned::<u32>("big").is_err());
+        assert_eq!(object.unsigned::<u64>("big"), Ok(4_294_967_296));
+        assert_eq!(object.unsigned::<u32>("ok"), Ok(42));
+        assert!(object.optional_unsigned::<u64>("count").is_err());
+        assert_eq!(object.optional_unsigned::<u64>("none"), Ok(None));
+        assert_eq!(object.optional_unsigned::<u64>("missing"), Ok(None));
+    }
+
+    #[test]
+    fn objects_round_trip() {
+        let object = Object::new()
+            .with("name", "quote \" backslash \\ newline \n tab \t bell \u{7} é")
+            .with("count", i64::MIN)
+            .with("max", i64::MAX)
+            .with("flag", true)
+            .with("nothing", None::<String>);
+        let text = object.to_string();
+        assert_eq!(parse_object(&text), Ok(object));
+    }
+
+    #[test]
+    fn numbers_out_of_i64_range_are_invalid() {
+        assert_eq!(
+            parse_object(r#"{"n": 9223372036854775808}"#),
+            Err(JsonError::InvalidNumber(String::from("9223372036854775808")))
+        );
+        assert_eq!(
+            parse_object(r#"{"n": -}"#),
+            Err(JsonError::InvalidNumber(String::from("-")))
+        );
+    }
+
+    #[test]
+    fn malformed_objects_are_rejected() {
+        assert_eq!(parse_object(""), Err(JsonError::UnexpectedEnd));
+        assert_eq!(parse_object(r#"{"a": 1"#), Err(JsonError::UnexpectedEnd));
+        assert_eq!(
+            parse_object(r#"{"a": 1} x"#),
+            Err(JsonError::Unexpected { found: 'x', at: 9 })
+        );
+        assert_eq!(
+            parse_object(r#"{"a": "\q"}"#),
+            Err(JsonError::InvalidEscape(String::from("\\q")))
+        );
+        assert_eq!(parse_object(r#"{"a": tru}"#), Err(JsonError::Unexpected { found: 't', at: 6 }));
+        assert_eq!(
+            parse_object(r#"{"a": "\u+123"}"#),
+            Err(JsonError::InvalidEscape(String::from("\\u+123")))
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs() {
+        let object = parse_object(r#"{"a": "\ud83d\ude00 \uD834\uDD1E \u00e9"}"#).unwrap();
+        assert_eq!(object.string("a"), Ok("\u{1F600} \u{1D11E} \u{E9}"));
+
+        // Text outside the Basic Multilingual Plane round-trips, whether it was escaped or not.
+        let object = Object::new().with("a", "\u{1F600}\u{10FFFF}\u{10000}");
+        assert_eq!(parse_object(&object.to_string()), Ok(object.clone()));
+        assert_eq!(parse_object(r#"{"a": "\ud83d\ude00\udbff\udfff\ud800\udc00"}"#), Ok(object));
+
+        // Surrogates only make a char in pairs, high then low.
+        for (input, escape) in &[
+            (r#"{"a": "\ud83d"}"#, r#"\ud83d"#),
+            (r#"{"a": "\ud83dx"}"#, r#"\ud83d"#),
+            (r#"{"a": "\ude00"}"#, r#"\ude00"#),
+            (r#"{"a": "\ude00\ud83d"}"#, r#"\ude00"#),
+            (r#"{"a": "\ud83d\ud83d"}"#, r#"\ud83d\ud83d"#),
+            (r#"{"a": "\ud83d\u0041"}"#, r#"\ud83d\u0041"#),
+        ] {
+            assert_eq!(parse_object(input), Err(JsonError::InvalidEscape(escape.to_string())), "{}", input);
+        }
+        assert_eq!(parse_object(r#"{"a": "\ud83d\ude"#), Err(JsonError::UnexpectedEnd));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod color;
+pub mod geometry;
+pub mod json;
+pub mod lifecycle;
+pub mod password;
+pub mod patch;
+pub mod store;
+#[cfg(test)]
+mod test_support;
+pub mod user;
+pub mod validation;
@@ -0,0 +1,349 @@
+/*
+
+Sign-in tracking and the account lifecycle.
+
+An account is always in exactly one AccountState, which an enum encodes better than the old `active: bool` could
+(a locked account and a deactivated account are both "not active", for different reasons):
+
+                 too many failed sign-ins
+    Active  ──────────────────────────────▶  Locked
+      ▲  ◀──────────────────────────────────  │
+      │                 unlock                │
+      │ reactivate                            │ deactivate
+      │                                       ▼
+      └──────────────────────────────────  Deactivated  ◀── deactivate (from Active)
+
+`transition` is the whole state machine: a match on the current state and the requested Transition. Any pair not
+listed there is illegal and returns an error without changing the User. Each legal transition returns an
+AuditEntry describing it, so callers can keep a log of every state change.
+
+Timestamps are seconds since the Unix epoch and are passed in rather than read from the clock, which keeps every
+method here deterministic.
+
+*/
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::json::{JsonError, Object, Value};
+use crate::user::User;
+use crate::validation::Username;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountState {
+    Active,
+    Locked,
+    Deactivated,
+}
+
+impl fmt::Display for AccountState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AccountState::Active => "active",
+            AccountState::Locked => "locked",
+            AccountState::Deactivated => "deactivated",
+        })
+    }
+}
+
+impl FromStr for AccountState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AccountState, String> {
+        match s {
+            "active" => Ok(AccountState::Active),
+            "locked" => Ok(AccountState::Locked),
+            "deactivated" => Ok(AccountState::Deactivated),
+            _ => Err(format!("unknown account state {:?}", s)),
+        }
+    }
+}
+
+impl From<AccountState> for Value {
+    fn from(state: AccountState) -> Value {
+        Value::String(state.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    Lock,
+    Unlock,
+    Deactivate,
+    Reactivate,
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Transition::Lock => "lock",
+            Transition::Unlock => "unlock",
+            Transition::Deactivate => "deactivate",
+            Transition::Reactivate => "reactivate",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleError {
+    // The account can't sign in because it isn't active.
+    NotActive(AccountState),
+    IllegalTransition { from: AccountState, transition: Transition },
+}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LifecycleError::NotActive(state) => write!(f, "account is {}", state),
+            LifecycleError::IllegalTransition { from, transition } => {
+                write!(f, "can't {} an account that is {}", transition, from)
+            }
+        }
+    }
+}
+
+impl Error for LifecycleError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SignInPolicy {
+    // How many failed sign-ins in a row lock the account.
+    pub max_failed_attempts: u32,
+}
+
+impl Default for SignInPolicy {
+    fn default() -> SignInPolicy {
+        SignInPolicy { max_failed_attempts: 5 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub at: u64,
+    pub username: Username,
+    pub transition: Transition,
+    pub from: AccountState,
+    pub to: AccountState,
+}
+
+impl AuditEntry {
+    pub fn to_json(&self) -> Result<Object, JsonError> {
+        Ok(Object::new()
+            .with("at", Value::try_from(self.at)?)
+            .with("username", self.username.as_str())
+            .with("transition", self.transition.to_string())
+            .with("from", self.from.to_string())
+            .with("to", self.to.to_string()))
+    }
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {} ({} -> {})",
+            self.at, self.username, self.transition, self.from, self.to
+        )
+    }
+}
+
+// now returns the current time as seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl User {
+    pub fn is_active(&self) -> bool {
+        self.state == AccountState::Active
+    }
+
+    // transition moves the account to a new state, or returns an error if `transition` isn't allowed from the
+    // current state.
+    pub fn transition(&mut self, transition: Transition, now: u64) -> Result<AuditEntry, LifecycleError> {
+        let from = self.state;
+        let to = match (from, transition) {
+            (AccountState::Active, Transition::Lock) => AccountState::Locked,
+            (AccountState::Locked, Transition::Unlock) => AccountState::Active,
+            (AccountState::Active, Transition::Deactivate) => AccountState::Deactivated,
+            (AccountState::Locked, Transition::Deactivate) => AccountState::Deactivated,
+            (AccountState::Deactivated, Transition::Reactivate) => AccountState::Active,
+            _ => return Err(LifecycleError::IllegalTransition { from, transition }),
+        };
+
+        self.state = to;
+        // Coming back to Active starts with a clean slate of failed attempts.
+        if to == AccountState::Active {
+            self.failed_sign_ins = 0;
+        }
+
+        Ok(AuditEntry {
+            at: now,
+            username: self.username.clone(),
+            transition,
+            from,
+            to,
+        })
+    }
+
+    // sign_in records a successful sign-in. Only active accounts can sign in.
+    pub fn sign_in(&mut self, now: u64) -> Result<(), LifecycleError> {
+        if !self.is_active() {
+            return Err(LifecycleError::NotActive(self.state));
+        }
+
+        self.sign_in_count += 1;
+        self.last_sign_in = Some(now);
+        self.failed_sign_ins = 0;
+        Ok(())
+    }
+
+    // fail_sign_in records a failed sign-in attempt, locking the account once `policy` allows no more. The audit
+    // entry for the lock is returned when that happens.
+    pub fn fail_sign_in(&mut self, now: u64, policy: &SignInPolicy) -> Result<Option<AuditEntry>, LifecycleError> {
+        if !self.is_active() {
+            return Err(LifecycleError::NotActive(self.state));
+        }
+
+        self.failed_sign_ins = self.failed_sign_ins.saturating_add(1);
+        if self.failed_sign_ins >= policy.max_failed_attempts {
+            return self.transition(Transition::Lock, now).map(Some);
+        }
+        Ok(None)
+    }
+}
+
+// state_from_json reads an account's state, falling back to the `active` flag written by older versions of the
+// store.
+pub fn state_from_json(object: &Object) -> Result<AccountState, JsonError> {
+    if object.get("state").is_some() {
+        return object.parsed("state");
+    }
+
+    Ok(if object.bool("active")? {
+        AccountState::Active
+    } else {
+        AccountState::Deactivated
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::build_user;
+    use crate::validation::Email;
+
+    const STATES: [AccountState; 3] = [AccountState::Active, AccountState::Locked, AccountState::Deactivated];
+    const TRANSITIONS: [Transition; 4] = [
+        Transition::Lock,
+        Transition::Unlock,
+        Transition::Deactivate,
+        Transition::Reactivate,
+    ];
+
+    fn user(state: AccountState) -> User {
+        let mut user = build_user(Email::parse("a@example.com").unwrap(), Username::parse("alice").unwrap());
+        user.state = state;
+        user.failed_sign_ins = 2;
+        user
+    }
+
+    // allowed is the state machine drawn at the top of the file, written out as a table.
+    fn allowed(from: AccountState, transition: Transition) -> Option<AccountState> {
+        let table = [
+            (AccountState::Active, Transition::Lock, AccountState::Locked),
+            (AccountState::Active, Transition::Deactivate, AccountState::Deactivated),
+            (AccountState::Locked, Transition::Unlock, AccountState::Active),
+            (AccountState::Locked, Transition::Deactivate, AccountState::Deactivated),
+            (AccountState::Deactivated, Transition::Reactivate, AccountState::Active),
+        ];
+        table
+            .iter()
+            .find(|&&(f, t, _)| f == from && t == transition)
+            .map(|&(_, _, to)| to)
+    }
+
+    #[test]
+    fn every_transition() {
+        for &from in &STATES {
+            for &transition in &TRANSITIONS {
+                let mut user = user(from);
+                let before = user.clone();
+                let result = user.transition(transition, 42);
+                match allowed(from, transition) {
+                    Some(to) => {
+                        assert_eq!(
+                            result,
+                            Ok(AuditEntry {
+                                at: 42,
+                                username: Username::parse("alice").unwrap(),
+                                transition,
+                                from,
+                                to,
+                            })
+                        );
+                        assert_eq!(user.state, to);
+                        let failed = if to == AccountState::Active { 0 } else { 2 };
+                        assert_eq!(user.failed_sign_ins, failed, "{} from {}", transition, from);
+                    }
+                    None => {
+                        assert_eq!(result, Err(LifecycleError::IllegalTransition { from, transition }));
+                        assert_eq!(user, before, "{} from {}", transition, from);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn locks_at_exactly_max_failed_attempts() {
+        for max_failed_attempts in 1..=4 {
+            let policy = SignInPolicy { max_failed_attempts };
+            let mut user = user(AccountState::Active);
+            user.failed_sign_ins = 0;
+            for attempt in 1..max_failed_attempts {
+                assert_eq!(user.fail_sign_in(attempt as u64, &policy), Ok(None));
+                assert_eq!(user.failed_sign_ins, attempt);
+                assert!(user.is_active());
+            }
+
+            let entry = user.fail_sign_in(99, &policy).unwrap().expect("the last attempt locks the account");
+            assert_eq!((entry.at, entry.transition), (99, Transition::Lock));
+            assert_eq!(user.state, AccountState::Locked);
+            assert_eq!(user.failed_sign_ins, max_failed_attempts);
+
+            // A locked account can't fail, or succeed, any further.
+            assert_eq!(
+                user.fail_sign_in(100, &policy),
+                Err(LifecycleError::NotActive(AccountState::Locked))
+            );
+            assert_eq!(user.sign_in(100), Err(LifecycleError::NotActive(AccountState::Locked)));
+            assert_eq!(user.failed_sign_ins, max_failed_attempts);
+        }
+    }
+
+    #[test]
+    fn signing_in_clears_failed_attempts() {
+        let mut user = user(AccountState::Active);
+        let count = user.sign_in_count;
+        assert_eq!(user.sign_in(7), Ok(()));
+        assert_eq!(user.failed_sign_ins, 0);
+        assert_eq!(user.sign_in_count, count + 1);
+        assert_eq!(user.last_sign_in, Some(7));
+    }
+
+    #[test]
+    fn failed_attempts_saturate() {
+        let mut user = user(AccountState::Active);
+        user.failed_sign_ins = u32::MAX;
+        let policy = SignInPolicy {
+            max_failed_attempts: u32::MAX,
+        };
+        assert!(user.fail_sign_in(1, &policy).unwrap().is_some());
+        assert_eq!(user.failed_sign_ins, u32::MAX);
+    }
+}
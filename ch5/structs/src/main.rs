@@ -1,51 +1,63 @@
-#[derive(Debug)]
-struct User {
-    username: String, // Using String means the User instance "owns" its username and email fields. We could use &str (string slice) to share this, instead. This would require the use of lifetimes in Rust to share that data in the User struct.
-    email: String,
-    sign_in_count: u64,
-    active: bool,
-}
+use std::env;
+use std::error::Error;
+use std::path::Path;
 
-fn build_user(email: String, username: String) -> User {
-    User {
-        email, // when field names are the same as parameter names, we can reuse the parameter without duplicating.
-        username,
-        active: true,
-        sign_in_count: 1,
-    }
-}
+use structs::color::{Color, ColorPatch};
+use structs::geometry::{Bounds, Metric, Point, PointPatch, PointSet, Vector};
+use structs::lifecycle::{self, AccountState, SignInPolicy, Transition};
+use structs::store::{StoreError, UserStore};
+use structs::user::{build_user, User};
+use structs::validation::{Email, Username};
 
-// Structs can also be defined as tuple structs:
-#[derive(Debug)]
-struct Color(i32, i32, i32);
-#[derive(Debug)]
-struct Point(i32, i32, i32);
+// User and build_user live in `src/user.rs` so that the account store in `src/store.rs` can use them too.
 
-fn main() {
+// Structs can also be defined as tuple structs, like Color in `src/color.rs` and Point in `src/geometry.rs`.
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Username and Email can only be created through `parse`, which validates (and lower cases) the input.
     let user1 = User {
-        email: String::from("someone@example.com"),
-        username: String::from("someusername123"),
-        active: true,
+        email: Email::parse("someone@example.com")?,
+        username: Username::parse("someusername123")?,
+        state: AccountState::Active,
         sign_in_count: 1,
+        last_sign_in: None,
+        failed_sign_ins: 0,
+        password_hash: None,
     };
 
-    let user2 = build_user(String::from("someone2@example.com"), String::from("someusername1234"));
+    let user2 = build_user(Email::parse("Someone2@Example.com")?, Username::parse("SomeUsername1234")?);
 
     // We can also create users from other users' field values.
     let user3 = User {
-        email: String::from("another@example.com"),
-        username: String::from("anotherusername567"),
-        active: user1.active,
+        email: Email::parse("another@example.com")?,
+        username: Username::parse("anotherusername567")?,
+        state: user1.state,
         sign_in_count: user1.sign_in_count,
+        last_sign_in: user1.last_sign_in,
+        failed_sign_ins: user1.failed_sign_ins,
+        password_hash: None,
     };
 
     // Including a shorthand syntax for taking the remaining fields from a user struct:
     let user4 = User {
-        email: String::from("another4@example.com"),
-        username: String::from("anotherusername568"),
+        email: Email::parse("another4@example.com")?,
+        username: Username::parse("anotherusername568")?,
+        password_hash: None, // Option<PasswordHash> isn't Copy, so taking it from user1 would move it and leave user1 partially moved.
         ..user1
     };
 
+    // Invalid input is rejected with an error describing what's wrong with it.
+    for email in &["", "no-at-sign", "two..dots@example.com", "someone@localhost", "someone@-bad-.com"] {
+        if let Err(e) = Email::parse(email) {
+            println!("{:?}: {}", email, e);
+        }
+    }
+    for username in &["ab", "1st_user", "white space"] {
+        if let Err(e) = Username::parse(username) {
+            println!("{:?}: {}", username, e);
+        }
+    }
+
     println!("user1: {:?}", user1);
     println!("user2: {:?}", user2);
     println!("user3: {:?}", user3);
@@ -54,6 +66,143 @@ fn main() {
     let black = Color(0,0,0);
     let origin = Point(0,0,0);
 
-    println!("black: {:?}", black);
-    println!("origin: {:?}", origin);
+    println!("black: ({}, {}, {})", black.0, black.1, black.2);
+    println!("origin: ({}, {}, {})", origin.0, origin.1, origin.2);
+
+    // diff says which fields differ, and a patch carries the new values for just those fields. Applying user1's
+    // patch to user4 gives user4 back, the runtime version of `User { email, username, password_hash, ..user1 }`.
+    let patch = user1.patch_to(&user4);
+    println!("user1 -> user4 changes: {:?}", user1.diff(&user4).changed());
+    println!("user1 -> user4 patch: {}", patch.to_json()?);
+    assert_eq!(patch.apply(&user1), user4);
+
+    let red = ColorPatch(Some(255), None, None).apply(&black);
+    println!("black -> red changes: {:?}, patch: {}", black.diff(&red).changed(), black.patch_to(&red).to_json()?);
+    let shifted = PointPatch(None, Some(-3), Some(7)).apply(&origin);
+    println!("origin -> shifted: {}, patch: {}", shifted, origin.patch_to(&shifted).to_json()?);
+
+    // Subtracting two Points gives the Vector between them, and a Point plus a Vector is another Point.
+    let moved = origin + Vector(4, -3, 12);
+    let v = moved - origin;
+    println!(
+        "{} -> {}: {}, euclidean {}, manhattan {}, chebyshev {}",
+        origin,
+        moved,
+        v,
+        origin.distance(&moved, Metric::Euclidean),
+        origin.distance(&moved, Metric::Manhattan),
+        origin.distance(&moved, Metric::Chebyshev)
+    );
+    println!(
+        "{} . {} = {}, {} x {} = {}",
+        v,
+        Vector(1, 0, 0),
+        v.dot(&Vector(1, 0, 0)),
+        Vector(1.0, 0.0, 0.0),
+        Vector(0.0, 1.0, 0.0),
+        Vector(1.0, 0.0, 0.0).cross(&Vector(0.0, 1.0, 0.0))
+    );
+
+    let a = Bounds::new(origin, Point(10, 10, 10));
+    let b = Bounds::new(Point(5, 5, 5), Point(15, 20, 8));
+    println!(
+        "{:?} and {:?}: intersection {:?}, union volume {}",
+        a,
+        b,
+        a.intersection(&b),
+        a.union(&b).volume()
+    );
+
+    let stars: PointSet<f64> = (0..1000)
+        .map(|i| {
+            let i = i as f64;
+            Point((i * 7.3) % 100.0, (i * 13.7) % 100.0, (i * 29.1) % 100.0)
+        })
+        .collect();
+    let query = Point(33.3, 66.6, 12.5);
+    if let Some((star, distance)) = stars.nearest(&query, Metric::Euclidean) {
+        println!("nearest of {} stars to {}: {} at {:.3}", stars.len(), query, star, distance);
+    }
+
+    // Color is a tuple struct with behavior: parsing, HSL and HSV, blending, contrast checks and terminal output.
+    let theme = ["#f80", "rgb(30, 144, 255)", "teal", "White", "#1e1e2e"];
+    for spec in &theme {
+        let color: Color = spec.parse()?;
+        println!(
+            "{:<18} {} {} {} ansi256={} {}",
+            spec,
+            color,
+            color.to_hsl(),
+            color.to_hsv(),
+            color.ansi256(),
+            color.paint("sample")
+        );
+    }
+    for spec in &["#12345", "rgb(300, 0, 0)", "chartreuse"] {
+        if let Err(e) = spec.parse::<Color>() {
+            println!("{:?}: {}", spec, e);
+        }
+    }
+
+    let background: Color = "#1e1e2e".parse()?;
+    for name in &["navy", "orange", "silver"] {
+        let text = Color::named(name).unwrap_or(black);
+        println!(
+            "{} on {}: contrast {:.2}, readable: {}",
+            name,
+            background,
+            text.contrast_ratio(background),
+            text.readable_on(background)
+        );
+    }
+    let navy = Color::named("navy").unwrap_or(black);
+    let purple = navy.blend(red, 0.5);
+    println!(
+        "navy + red = {} ({:?}), lighter: {}, back from HSL: {}",
+        purple,
+        purple.name(),
+        purple.lighten(0.2),
+        Color::from(purple.to_hsl())
+    );
+
+    // Users can outlive main by going into a UserStore, which saves them to a JSON lines file.
+    let path = env::temp_dir().join("structs-users.jsonl");
+    store_users(&path, vec![user1, user2])?;
+
+    Ok(())
+}
+
+fn store_users(path: &Path, users: Vec<User>) -> Result<(), StoreError> {
+    let mut store = UserStore::open(path)?;
+    for user in users {
+        // The users are already there if this has run before, and that's fine.
+        match store.create(user) {
+            Ok(()) | Err(StoreError::DuplicateUsername(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let now = lifecycle::now();
+    store.sign_in("someusername123", now)?;
+
+    // Three failed sign-ins in a row lock the account, after which even the right password can't sign in.
+    let policy = SignInPolicy { max_failed_attempts: 3 };
+    for _ in 0..policy.max_failed_attempts {
+        store.fail_sign_in("someusername1234", now, &policy)?;
+    }
+    if let Err(e) = store.sign_in("someusername1234", now) {
+        println!("someusername1234 can't sign in: {}", e);
+    }
+
+    // Illegal transitions are rejected without changing anything.
+    if let Err(e) = store.transition("someusername1234", Transition::Reactivate, now) {
+        println!("someusername1234: {}", e);
+    }
+    store.transition("someusername1234", Transition::Unlock, now)?;
+
+    let reloaded = UserStore::open(path)?;
+    for user in reloaded.users() {
+        println!("stored: {:?}", user);
+    }
+    Ok(())
 }
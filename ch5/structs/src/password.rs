@@ -0,0 +1,380 @@
+/*
+
+Password hashing for Users.
+
+Passwords are never stored. A PasswordHash holds PBKDF2-HMAC-SHA256 of the password with a random 16 byte salt,
+along with the iteration count used, so a stored hash can still be checked after the default parameters change.
+It is stored as a single string:
+
+pbkdf2-sha256$<iterations>$<salt as hex>$<hash as hex>
+
+Verifying compares the hashes in constant time, so how long a failed check takes doesn't reveal how many leading
+bytes matched. When the stored iteration count falls behind HashParams, `needs_rehash` says so and the caller can
+hash the password again the next time the user signs in with it.
+
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::json::Value;
+
+const ALGORITHM: &str = "pbkdf2-sha256";
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashParams {
+    pub iterations: u32,
+}
+
+impl Default for HashParams {
+    // 600,000 iterations is OWASP's recommendation for PBKDF2-HMAC-SHA256.
+    fn default() -> HashParams {
+        HashParams { iterations: 600_000 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordPolicy {
+    pub min_len: usize,
+    pub max_len: usize,
+    pub require_letter: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> PasswordPolicy {
+        PasswordPolicy {
+            min_len: 12,
+            max_len: 128,
+            require_letter: true,
+            require_digit: true,
+            require_symbol: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordError {
+    TooShort { len: usize, min: usize },
+    TooLong { len: usize, max: usize },
+    MissingLetter,
+    MissingDigit,
+    MissingSymbol,
+    Malformed(String),
+    Random(String),
+}
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordError::TooShort { len, min } => {
+                write!(f, "password is {} chars long, but must be at least {}", len, min)
+            }
+            PasswordError::TooLong { len, max } => {
+                write!(f, "password is {} chars long, but must be at most {}", len, max)
+            }
+            PasswordError::MissingLetter => write!(f, "password must contain a letter"),
+            PasswordError::MissingDigit => write!(f, "password must contain a digit"),
+            PasswordError::MissingSymbol => write!(f, "password must contain a symbol"),
+            PasswordError::Malformed(reason) => write!(f, "malformed password hash: {}", reason),
+            PasswordError::Random(reason) => write!(f, "could not generate a salt: {}", reason),
+        }
+    }
+}
+
+impl Error for PasswordError {}
+
+impl PasswordPolicy {
+    pub fn check(&self, password: &str) -> Result<(), PasswordError> {
+        let len = password.chars().count();
+        if len < self.min_len {
+            return Err(PasswordError::TooShort { len, min: self.min_len });
+        }
+        if len > self.max_len {
+            return Err(PasswordError::TooLong { len, max: self.max_len });
+        }
+        if self.require_letter && !password.chars().any(char::is_alphabetic) {
+            return Err(PasswordError::MissingLetter);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            return Err(PasswordError::MissingDigit);
+        }
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            return Err(PasswordError::MissingSymbol);
+        }
+        Ok(())
+    }
+
+    // hash checks a new password against the policy before hashing it.
+    pub fn hash(&self, password: &str, params: &HashParams) -> Result<PasswordHash, PasswordError> {
+        self.check(password)?;
+        PasswordHash::new(password, params)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct PasswordHash {
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    hash: [u8; HASH_LEN],
+}
+
+// Debug leaves out the salt and hash, so a User can be printed without leaking them into logs.
+impl fmt::Debug for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PasswordHash({}, {} iterations)", ALGORITHM, self.iterations)
+    }
+}
+
+// A hash is written out as a placeholder, so patches and audit trails never carry one. The store writes the real
+// hash with `to_string` instead.
+impl From<PasswordHash> for Value {
+    fn from(_: PasswordHash) -> Value {
+        Value::String(String::from("[redacted]"))
+    }
+}
+
+fn derive(password: &str, salt: &[u8], iterations: u32) -> [u8; HASH_LEN] {
+    let mut hash = [0; HASH_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut hash);
+    hash
+}
+
+impl PasswordHash {
+    // new hashes `password` with a fresh random salt. It doesn't check a PasswordPolicy, since rehashing an existing
+    // password shouldn't fail just because the policy got stricter; new passwords go through `PasswordPolicy::hash`.
+    pub fn new(password: &str, params: &HashParams) -> Result<PasswordHash, PasswordError> {
+        let mut salt = [0; SALT_LEN];
+        getrandom::getrandom(&mut salt).map_err(|e| PasswordError::Random(e.to_string()))?;
+
+        Ok(PasswordHash {
+            iterations: params.iterations,
+            salt,
+            hash: derive(password, &salt, params.iterations),
+        })
+    }
+
+    // dummy is a hash no password matches, costing as much to verify as a real one made with `params`. Checking
+    // passwords for unknown users against it makes them take about as long as checking them for real users whose
+    // hashes were made with `params`.
+    pub fn dummy(params: &HashParams) -> PasswordHash {
+        PasswordHash {
+            iterations: params.iterations,
+            salt: [0; SALT_LEN],
+            hash: [0; HASH_LEN],
+        }
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        let hash = derive(password, &self.salt, self.iterations);
+        // ct_eq compares every byte no matter where the first difference is.
+        bool::from(hash.ct_eq(&self.hash))
+    }
+
+    // needs_rehash reports whether this hash was made with weaker parameters than `params`.
+    pub fn needs_rehash(&self, params: &HashParams) -> bool {
+        self.iterations < params.iterations
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// from_hex reads exactly N bytes of lower case hex, the only form Display writes.
+fn from_hex<const N: usize>(s: &str) -> Result<[u8; N], PasswordError> {
+    if s.len() != N * 2 || !s.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+        return Err(PasswordError::Malformed(format!("expected {} lower case hex digits", N * 2)));
+    }
+
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16)
+            .map_err(|_| PasswordError::Malformed(format!("invalid hex {:?}", s)))?;
+    }
+    Ok(bytes)
+}
+
+impl fmt::Display for PasswordHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}${}${}${}",
+            ALGORITHM,
+            self.iterations,
+            to_hex(&self.salt),
+            to_hex(&self.hash)
+        )
+    }
+}
+
+impl FromStr for PasswordHash {
+    type Err = PasswordError;
+
+    fn from_str(s: &str) -> Result<PasswordHash, PasswordError> {
+        let parts: Vec<&str> = s.split('$').collect();
+        if parts.len() != 4 {
+            return Err(PasswordError::Malformed(String::from("expected 4 '$' separated parts")));
+        }
+        if parts[0] != ALGORITHM {
+            return Err(PasswordError::Malformed(format!("unsupported algorithm {:?}", parts[0])));
+        }
+
+        // The count is written as plain digits, so "+5" and "007" are as malformed as "five". Zero iterations
+        // would store the password almost as is.
+        let invalid = || PasswordError::Malformed(format!("invalid iteration count {:?}", parts[1]));
+        if !parts[1].chars().all(|c| c.is_ascii_digit()) || parts[1].starts_with('0') {
+            return Err(invalid());
+        }
+        let iterations = parts[1].parse().map_err(|_| invalid())?;
+
+        Ok(PasswordHash {
+            iterations,
+            salt: from_hex(parts[2])?,
+            hash: from_hex(parts[3])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    // Hashing is slow on purpose, so the tests use a handful of iterations.
+    fn quick_hash(password: &str, iterations: u32) -> PasswordHash {
+        PasswordHash::new(password, &HashParams { iterations }).unwrap()
+    }
+
+    #[test]
+    fn round_trips_through_its_string_form() {
+        let mut random = Random(37);
+        let pool: Vec<char> = "abcXYZ019 !$é日".chars().collect();
+        for _ in 0..200 {
+            let password = random.string(&pool, 20);
+            let iterations = 1 + (random.next() % 50) as u32;
+            let hash = quick_hash(&password, iterations);
+
+            let parsed: PasswordHash = hash.to_string().parse().unwrap();
+            assert_eq!(parsed, hash);
+            assert_eq!(parsed.iterations(), iterations);
+            assert!(parsed.verify(&password));
+            assert!(!parsed.verify(&format!("{}x", password)));
+        }
+    }
+
+    #[test]
+    fn anything_that_parses_displays_the_same() {
+        let mut random = Random(38);
+        let hash = quick_hash("correct horse 42", 3).to_string();
+        let pool: Vec<char> = "0123456789abcdefABCDEF$+- xg".chars().collect();
+        for _ in 0..20_000 {
+            // Mutate a valid hash: replace, insert or delete one char, or fuzz from scratch.
+            let mut chars: Vec<char> = hash.chars().collect();
+            let at = random.next() % chars.len();
+            let c = pool[random.next() % pool.len()];
+            let input: String = match random.next() % 4 {
+                0 => {
+                    chars[at] = c;
+                    chars.into_iter().collect()
+                }
+                1 => {
+                    chars.insert(at, c);
+                    chars.into_iter().collect()
+                }
+                2 => {
+                    chars.remove(at);
+                    chars.into_iter().collect()
+                }
+                _ => random.string(&pool, 120),
+            };
+
+            if let Ok(parsed) = input.parse::<PasswordHash>() {
+                assert_eq!(parsed.to_string(), input);
+                assert!(parsed.iterations() > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_hashes() {
+        let hash = quick_hash("correct horse 42", 3).to_string();
+        let (salt, digest) = {
+            let parts: Vec<&str> = hash.split('$').collect();
+            (parts[2].to_string(), parts[3].to_string())
+        };
+        let with = |algorithm: &str, iterations: &str, salt: &str, digest: &str| {
+            format!("{}${}${}${}", algorithm, iterations, salt, digest)
+        };
+
+        let malformed = [
+            String::new(),
+            String::from("$$$"),
+            with("pbkdf2-sha512", "3", &salt, &digest),
+            with(ALGORITHM, "0", &salt, &digest),
+            with(ALGORITHM, "", &salt, &digest),
+            with(ALGORITHM, "+3", &salt, &digest),
+            with(ALGORITHM, "03", &salt, &digest),
+            with(ALGORITHM, "-3", &salt, &digest),
+            with(ALGORITHM, "4294967296", &salt, &digest),
+            with(ALGORITHM, "3", "", &digest),
+            with(ALGORITHM, "3", &salt[1..], &digest),
+            with(ALGORITHM, "3", &format!("{}00", salt), &digest),
+            with(ALGORITHM, "3", &salt.to_uppercase(), &digest),
+            with(ALGORITHM, "3", &format!("+f{}", &salt[2..]), &digest),
+            with(ALGORITHM, "3", &salt, &digest[..digest.len() - 1]),
+            format!("{}$extra", hash),
+        ];
+        for input in malformed.iter() {
+            assert!(
+                matches!(input.parse::<PasswordHash>(), Err(PasswordError::Malformed(_))),
+                "{:?} parsed",
+                input
+            );
+        }
+        assert!(with(ALGORITHM, "4294967295", &salt, &digest).parse::<PasswordHash>().is_ok());
+    }
+
+    #[test]
+    fn debug_and_json_leave_out_the_hash() {
+        let hash = quick_hash("correct horse 42", 3);
+        let text = hash.to_string();
+        let salt = text.split('$').nth(2).unwrap();
+        assert!(!format!("{:?}", hash).contains(salt));
+        assert_eq!(Value::from(hash), Value::String(String::from("[redacted]")));
+    }
+
+    #[test]
+    fn policy_counts_chars() {
+        let policy = PasswordPolicy::default();
+        assert_eq!(policy.check(""), Err(PasswordError::TooShort { len: 0, min: 12 }));
+        assert_eq!(
+            policy.check(&"a1".repeat(65)),
+            Err(PasswordError::TooLong { len: 130, max: 128 })
+        );
+        // Twelve chars, but more than twelve bytes.
+        assert_eq!(policy.check("ééééééééééé1"), Ok(()));
+        assert_eq!(policy.check("123456789012"), Err(PasswordError::MissingLetter));
+        assert_eq!(policy.check("abcdefghijkl"), Err(PasswordError::MissingDigit));
+    }
+
+    #[test]
+    fn dummy_hash_matches_nothing() {
+        let dummy = PasswordHash::dummy(&HashParams { iterations: 3 });
+        assert_eq!(dummy.iterations(), 3);
+        for password in &["", "password", "right password 1"] {
+            assert!(!dummy.verify(password));
+        }
+    }
+}
@@ -0,0 +1,169 @@
+/*
+
+Struct update syntax at runtime.
+
+`User { email, username, ..user1 }` builds a new User from a few new field values plus everything else from user1.
+`#[derive(Patch)]` generates types that do the same thing with values only known at runtime. Deriving it, e.g.
+
+#[derive(Debug, Clone, PartialEq, Patch)]
+pub struct Account {
+    pub name: String,
+    pub balance: i64,
+}
+
+adds:
+
+* AccountDiff, with a `bool` per field saying whether that field differs between two Accounts (`a.diff(&b)`)
+* AccountPatch, with an `Option` per field, where `Some` is a new value and `None` keeps the old one. This is the
+  runtime version of struct update syntax: `patch.apply(&account)` is `Account { ..changed fields.., ..account }`
+* `a.patch_to(&b)`, the patch that turns `a` into `b`, and `patch.to_json()` for writing it to an audit trail
+
+Tuple structs get tuple Diffs and Patches, so Color's patch is `ColorPatch(Some(255), None, None)`. Generic structs
+get generic Patches: Point<T> has a PointPatch<T>. The derive itself lives in the patch-derive crate, since a derive
+has to be compiled as a crate of its own.
+
+Every patched field type must implement Clone and PartialEq. Fields that can't be written as JSON are fine, but
+`to_json` can only be called when every patched field type implements `json::ToValue`. Two attributes change
+what's generated:
+
+#[patch(diff = AccountChanges, patch = AccountUpdate)]  on the struct, to name the generated types
+#[patch(skip)]                                          on a field, to leave it out of diffs and patches
+
+*/
+
+pub use patch_derive::Patch;
+
+/// Color and Point<u8> both hold three u8s, but they're distinct types, and so are their Diffs and Patches. A
+/// Point can't be used as a Color:
+///
+/// ```compile_fail
+/// use structs::color::Color;
+/// use structs::geometry::Point;
+///
+/// let red: Color = Point(255u8, 0, 0);
+/// ```
+///
+/// a ColorPatch can't be applied to a Point:
+///
+/// ```compile_fail
+/// use structs::color::ColorPatch;
+/// use structs::geometry::Point;
+///
+/// ColorPatch(Some(255), None, None).apply(&Point(0u8, 0, 0));
+/// ```
+///
+/// and a Color can't be diffed against a Point:
+///
+/// ```compile_fail
+/// use structs::color::Color;
+/// use structs::geometry::Point;
+///
+/// Color(0, 0, 0).diff(&Point(0u8, 0, 0));
+/// ```
+///
+/// while the same code compiles when the types match:
+///
+/// ```
+/// use structs::color::{Color, ColorPatch};
+/// use structs::geometry::{Point, PointPatch};
+///
+/// let red: Color = Color(255, 0, 0);
+/// assert_eq!(ColorPatch(Some(255), None, None).apply(&Color(0, 0, 0)), red);
+/// assert_eq!(PointPatch(Some(255), None, None).apply(&Point(0u8, 0, 0)), Point(255, 0, 0));
+/// assert!(Color(0, 0, 0).diff(&red).0);
+/// ```
+#[cfg(doctest)]
+pub struct DistinctTypes;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::{self, Value};
+
+    #[derive(Debug, Clone, PartialEq, Patch)]
+    #[patch(diff = AccountChanges, patch = AccountUpdate)]
+    struct Account {
+        /// Doc comments and other attributes on fields are left alone.
+        #[allow(dead_code)]
+        name: String,
+        balance: i64,
+        #[patch(skip)]
+        opened: u64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Patch)]
+    struct Labeled<'a, T>(&'a str, #[patch(skip)] u32, T)
+    where
+        T: Copy;
+
+    // Vec<u8> has no JSON form, so BlobPatch has no usable to_json, but everything else still works.
+    #[derive(Debug, Clone, PartialEq, Patch)]
+    struct Blob {
+        name: String,
+        bytes: Vec<u8>,
+    }
+
+    fn account(name: &str, balance: i64, opened: u64) -> Account {
+        Account {
+            name: name.to_string(),
+            balance,
+            opened,
+        }
+    }
+
+    #[test]
+    fn named_fields() {
+        let before = account("alice", 10, 1);
+        let after = account("alice", -5, 2);
+
+        let changes: AccountChanges = before.diff(&after);
+        assert_eq!(changes, AccountChanges { name: false, balance: true });
+        assert_eq!(changes.changed(), vec!["balance"]);
+        assert!(!changes.is_empty());
+        assert!(before.diff(&before).is_empty());
+
+        let update: AccountUpdate = before.patch_to(&after);
+        assert_eq!(update, AccountUpdate { name: None, balance: Some(-5) });
+        assert_eq!(update.to_json(), Ok(json::Object::new().with("balance", -5i64)));
+        // The skipped field always comes from the base value.
+        assert_eq!(update.apply(&before), account("alice", -5, 1));
+        assert!(AccountUpdate::default().is_empty());
+        assert_eq!(AccountUpdate::default().apply(&after), after);
+    }
+
+    #[test]
+    fn tuple_fields_with_generics() {
+        let before = Labeled("x", 1, 2.5);
+        let after = Labeled("y", 7, 2.5);
+
+        // Fields are named by their index in the struct, even after a skipped one.
+        assert_eq!(before.diff(&after).changed(), vec!["0"]);
+        assert_eq!(before.diff(&Labeled("x", 1, 3.0)).changed(), vec!["2"]);
+
+        let patch: LabeledPatch<f64> = LabeledPatch(None, Some(9.0));
+        assert_eq!(patch.apply(&after), Labeled("y", 7, 9.0));
+        assert_eq!(before.patch_to(&after).apply(&before), Labeled("y", 1, 2.5));
+        assert!(LabeledPatch::<f64>::default().is_empty());
+
+        let patch = Labeled("a", 0, true).patch_to(&Labeled("b", 0, false));
+        assert_eq!(
+            patch.to_json().map(|object| object.get("2").cloned()),
+            Ok(Some(Value::Bool(false)))
+        );
+    }
+
+    #[test]
+    fn fields_without_json() {
+        let before = Blob {
+            name: String::from("a"),
+            bytes: vec![1, 2, 3],
+        };
+        let after = Blob {
+            bytes: vec![4],
+            ..before.clone()
+        };
+        assert_eq!(before.diff(&after).changed(), vec!["bytes"]);
+        assert_eq!(before.patch_to(&after), BlobPatch { name: None, bytes: Some(vec![4]) });
+        assert_eq!(before.patch_to(&after).apply(&before), after);
+    }
+}
@@ -0,0 +1,588 @@
+/*
+
+A persistent store of Users, keyed by username.
+
+Users are saved to a JSON lines file, one user per line. Every change rewrites the whole file atomically: the new
+contents go to a temporary file next to it, which is synced and then renamed over the old one, so a crash leaves
+either the old file or the new one and never a half-written mix. The directory is synced after the rename too,
+since until it is the rename itself can be lost in a crash.
+
+Every account state change is also appended to an audit log next to the store, `<file>.audit`, one JSON object per
+line. The audit log is only ever appended to, never rewritten.
+
+Several processes can share one store file. Each change takes an exclusive lock on a `<file>.lock` file, reloads
+the users from disk so it sees changes other writers made, applies the change and saves, all before releasing the
+lock. Two writers therefore can't lose each other's updates.
+
+*/
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::json::{self, JsonError};
+use crate::lifecycle::{AuditEntry, LifecycleError, SignInPolicy, Transition};
+use crate::password::{HashParams, PasswordError, PasswordHash, PasswordPolicy};
+use crate::user::User;
+use crate::validation::{Email, Username};
+
+#[derive(Debug)]
+pub enum StoreError {
+    NotFound(String),
+    DuplicateUsername(Username),
+    DuplicateEmail(Email),
+    Corrupt { line: usize, error: JsonError },
+    // A record that can't be written out as JSON.
+    Unwritable(JsonError),
+    Lifecycle(LifecycleError),
+    Password(PasswordError),
+    // The password was wrong, the user has no password, or there is no such user. They aren't told apart on purpose.
+    WrongPassword,
+    Io(io::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::NotFound(username) => write!(f, "no user named {:?}", username),
+            StoreError::DuplicateUsername(username) => write!(f, "username {:?} is already taken", username.as_str()),
+            StoreError::DuplicateEmail(email) => write!(f, "email {:?} is already in use", email.as_str()),
+            StoreError::Corrupt { line, error } => write!(f, "corrupt record on line {}: {}", line, error),
+            StoreError::Unwritable(e) => write!(f, "can't write record: {}", e),
+            StoreError::Lifecycle(e) => write!(f, "{}", e),
+            StoreError::Password(e) => write!(f, "{}", e),
+            StoreError::WrongPassword => write!(f, "wrong username or password"),
+            StoreError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+impl From<LifecycleError> for StoreError {
+    fn from(e: LifecycleError) -> StoreError {
+        StoreError::Lifecycle(e)
+    }
+}
+
+impl From<PasswordError> for StoreError {
+    fn from(e: PasswordError) -> StoreError {
+        StoreError::Password(e)
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(e: io::Error) -> StoreError {
+        StoreError::Io(e)
+    }
+}
+
+pub struct UserStore {
+    path: PathBuf,
+    users: BTreeMap<Username, User>,
+}
+
+// with_suffix appends to a file name, e.g. `users.jsonl` becomes `users.jsonl.lock`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// sync_parent syncs the directory holding `path`, which makes a rename into it durable. Only Unix lets a directory
+// be opened and synced like this.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl UserStore {
+    // open loads the store at `path`. A missing file is an empty store; it is created by the first change.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<UserStore, StoreError> {
+        let mut store = UserStore {
+            path: path.as_ref().to_path_buf(),
+            users: BTreeMap::new(),
+        };
+        store.reload()?;
+        Ok(store)
+    }
+
+    // reload replaces the in-memory users with what is currently on disk.
+    pub fn reload(&mut self) -> Result<(), StoreError> {
+        self.users.clear();
+
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let corrupt = |error| StoreError::Corrupt { line: i + 1, error };
+            let user = json::parse_object(&line)
+                .and_then(|object| User::from_json(&object))
+                .map_err(corrupt)?;
+            self.users.insert(user.username.clone(), user);
+        }
+
+        Ok(())
+    }
+
+    // save writes `users` to a temporary file and renames it over the store file.
+    fn save(&self, users: &BTreeMap<Username, User>) -> Result<(), StoreError> {
+        let tmp = with_suffix(&self.path, ".tmp");
+        let mut file = File::create(&tmp)?;
+        for user in users.values() {
+            writeln!(file, "{}", user.to_json().map_err(StoreError::Unwritable)?)?;
+        }
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        sync_parent(&self.path)?;
+        Ok(())
+    }
+
+    // audit appends an entry to the audit log.
+    fn audit(&self, entry: &AuditEntry) -> Result<(), StoreError> {
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(with_suffix(&self.path, ".audit"))?;
+        writeln!(log, "{}", entry.to_json().map_err(StoreError::Unwritable)?)?;
+        log.sync_all()?;
+        Ok(())
+    }
+
+    // locked runs `change` with the store file locked and freshly reloaded, then saves the result. `change` works on
+    // a copy of the users, which only replaces the in-memory users once it is saved, so a failed change or save
+    // leaves them as they are on disk.
+    fn locked<T, F>(&mut self, change: F) -> Result<T, StoreError>
+    where
+        F: FnOnce(&mut BTreeMap<Username, User>) -> Result<T, StoreError>,
+    {
+        self.locked_then(change, |_, _| Ok(()))
+    }
+
+    // locked_then is locked with a `then` step, which runs after a successful save and before the lock is released.
+    fn locked_then<T, F, G>(&mut self, change: F, then: G) -> Result<T, StoreError>
+    where
+        F: FnOnce(&mut BTreeMap<Username, User>) -> Result<T, StoreError>,
+        G: FnOnce(&UserStore, &T) -> Result<(), StoreError>,
+    {
+        let lock = File::create(with_suffix(&self.path, ".lock"))?;
+        lock.lock()?;
+
+        self.reload()?;
+        let mut users = self.users.clone();
+        let value = change(&mut users)?;
+        self.save(&users)?;
+        self.users = users;
+        then(self, &value)?;
+
+        lock.unlock()?;
+        Ok(value)
+    }
+
+    // check_unique rejects `user` if another user (anyone but `replacing`) has the same username or email. Both are
+    // normalized to lower case when parsed, so a plain comparison is case-insensitive.
+    fn check_unique(
+        users: &BTreeMap<Username, User>,
+        user: &User,
+        replacing: Option<&Username>,
+    ) -> Result<(), StoreError> {
+        let others = users.values().filter(|other| Some(&other.username) != replacing);
+        for other in others {
+            if other.username == user.username {
+                return Err(StoreError::DuplicateUsername(user.username.clone()));
+            }
+            if other.email == user.email {
+                return Err(StoreError::DuplicateEmail(user.email.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn create(&mut self, user: User) -> Result<(), StoreError> {
+        self.locked(|users| {
+            UserStore::check_unique(users, &user, None)?;
+            users.insert(user.username.clone(), user);
+            Ok(())
+        })
+    }
+
+    // key normalizes a username for lookup. A string that isn't a valid username can't belong to any user.
+    fn key(username: &str) -> Result<Username, StoreError> {
+        Username::parse(username).map_err(|_| StoreError::NotFound(username.to_string()))
+    }
+
+    pub fn get(&self, username: &str) -> Option<&User> {
+        self.users.get(&UserStore::key(username).ok()?)
+    }
+
+    pub fn users(&self) -> impl Iterator<Item = &User> {
+        self.users.values()
+    }
+
+    // update applies `change` to a copy of the user and stores the result, returning the updated user. Changing the
+    // username moves the user to its new key, subject to the same uniqueness checks as `create`.
+    pub fn update<F>(&mut self, username: &str, change: F) -> Result<User, StoreError>
+    where
+        F: FnOnce(&mut User),
+    {
+        self.try_update(username, |user| {
+            change(user);
+            Ok(None)
+        })
+    }
+
+    // try_update is update for changes that can fail or change the account state. Nothing is saved if `change`
+    // fails; an AuditEntry it returns is written to the audit log along with the updated user, while the store is
+    // still locked, so the log is in the same order as the changes.
+    fn try_update<F>(&mut self, username: &str, change: F) -> Result<User, StoreError>
+    where
+        F: FnOnce(&mut User) -> Result<Option<AuditEntry>, StoreError>,
+    {
+        let key = UserStore::key(username)?;
+        let audit = |store: &UserStore, (_, entry): &(User, Option<AuditEntry>)| match entry {
+            Some(entry) => store.audit(entry),
+            None => Ok(()),
+        };
+        let (user, _) = self.locked_then(
+            |users| {
+                let mut user = users
+                    .get(&key)
+                    .cloned()
+                    .ok_or_else(|| StoreError::NotFound(username.to_string()))?;
+                let entry = change(&mut user)?;
+
+                UserStore::check_unique(users, &user, Some(&key))?;
+                users.remove(&key);
+                users.insert(user.username.clone(), user.clone());
+                Ok((user, entry))
+            },
+            audit,
+        )?;
+        Ok(user)
+    }
+
+    // sign_in records a successful sign-in for an active account.
+    pub fn sign_in(&mut self, username: &str, now: u64) -> Result<User, StoreError> {
+        self.try_update(username, |user| {
+            user.sign_in(now)?;
+            Ok(None)
+        })
+    }
+
+    // fail_sign_in records a failed sign-in, locking the account once `policy` allows no more attempts.
+    pub fn fail_sign_in(&mut self, username: &str, now: u64, policy: &SignInPolicy) -> Result<User, StoreError> {
+        self.try_update(username, |user| Ok(user.fail_sign_in(now, policy)?))
+    }
+
+    // transition changes the account state, e.g. `store.transition("alice", Transition::Deactivate, now)`.
+    pub fn transition(&mut self, username: &str, transition: Transition, now: u64) -> Result<User, StoreError> {
+        self.try_update(username, |user| Ok(Some(user.transition(transition, now)?)))
+    }
+
+    // set_password checks `password` against `policy` and stores its hash.
+    pub fn set_password(
+        &mut self,
+        username: &str,
+        password: &str,
+        policy: &PasswordPolicy,
+        params: &HashParams,
+    ) -> Result<User, StoreError> {
+        let hash = policy.hash(password, params)?;
+        self.update(username, |user| user.password_hash = Some(hash))
+    }
+
+    // authenticate checks a password and records the sign-in, successful or not. A wrong password counts towards
+    // locking the account under `policy`. A correct password hashed with weaker parameters than `params` is
+    // rehashed with `params` on the way in.
+    //
+    // The result gives nothing about an account away to someone without its password. Unknown users, users with
+    // no password, and locked or deactivated accounts all get WrongPassword for a wrong password, and only the
+    // right password learns that an account can't sign in. How long it takes can give an account away, though: a
+    // wrong password for an existing account saves the store and may append to the audit log, which an unknown
+    // user doesn't, and a stored hash made with other parameters than `params` costs more or less to check than
+    // the dummy hash unknown users are checked against. Callers that need to hide which accounts exist have to
+    // limit how often authenticate can be tried.
+    pub fn authenticate(
+        &mut self,
+        username: &str,
+        password: &str,
+        now: u64,
+        policy: &SignInPolicy,
+        params: &HashParams,
+    ) -> Result<User, StoreError> {
+        let mut verified = false;
+        let user = self.try_update(username, |user| {
+            let hash = match &user.password_hash {
+                Some(hash) if hash.verify(password) => hash,
+                Some(_) => return UserStore::wrong_password(user, now, policy),
+                None => {
+                    PasswordHash::dummy(params).verify(password);
+                    return UserStore::wrong_password(user, now, policy);
+                }
+            };
+
+            let rehash = hash.needs_rehash(params);
+            user.sign_in(now)?;
+            if rehash {
+                user.password_hash = Some(PasswordHash::new(password, params)?);
+            }
+            verified = true;
+            Ok(None)
+        });
+
+        match user {
+            Ok(user) if verified => Ok(user),
+            Ok(_) => Err(StoreError::WrongPassword),
+            Err(StoreError::NotFound(_)) => {
+                PasswordHash::dummy(params).verify(password);
+                Err(StoreError::WrongPassword)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // wrong_password counts a failed sign-in against an active account. An account that isn't active is left as
+    // it is, and its state isn't reported.
+    fn wrong_password(user: &mut User, now: u64, policy: &SignInPolicy) -> Result<Option<AuditEntry>, StoreError> {
+        match user.fail_sign_in(now, policy) {
+            Err(LifecycleError::NotActive(_)) => Ok(None),
+            result => Ok(result?),
+        }
+    }
+
+    pub fn delete(&mut self, username: &str) -> Result<User, StoreError> {
+        let key = UserStore::key(username)?;
+        self.locked(|users| {
+            users
+                .remove(&key)
+                .ok_or_else(|| StoreError::NotFound(username.to_string()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::AccountState;
+    use crate::user::build_user;
+    use std::env;
+    use std::process;
+    use std::sync::mpsc;
+    use std::thread;
+
+    // store_path returns a fresh store file in its own temporary directory.
+    fn store_path(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("structs-store-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("users.jsonl")
+    }
+
+    fn user(username: &str) -> User {
+        build_user(
+            Email::parse(&format!("{}@example.com", username)).unwrap(),
+            Username::parse(username).unwrap(),
+        )
+    }
+
+    #[test]
+    fn reopening_reloads_the_same_users() {
+        let path = store_path("reload");
+        let mut store = UserStore::open(&path).unwrap();
+        store.create(user("alice")).unwrap();
+        store.create(user("bob")).unwrap();
+        store.update("bob", |bob| bob.sign_in_count = 7).unwrap();
+        store.transition("alice", Transition::Deactivate, 100).unwrap();
+        store.create(user("carol")).unwrap();
+        store.delete("carol").unwrap();
+
+        let reopened = UserStore::open(&path).unwrap();
+        let saved: Vec<&User> = store.users().collect();
+        let loaded: Vec<&User> = reopened.users().collect();
+        assert_eq!(saved, loaded);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(reopened.get("bob").unwrap().sign_in_count, 7);
+        assert!(reopened.get("carol").is_none());
+        assert!(!with_suffix(&path, ".tmp").exists());
+    }
+
+    #[test]
+    fn uniqueness_holds_across_reopening() {
+        let path = store_path("unique");
+        UserStore::open(&path).unwrap().create(user("alice")).unwrap();
+
+        let mut store = UserStore::open(&path).unwrap();
+        assert!(matches!(
+            store.create(user("ALICE")),
+            Err(StoreError::DuplicateUsername(_))
+        ));
+        let mut other = user("alice2");
+        other.email = Email::parse("Alice@Example.com").unwrap();
+        assert!(matches!(store.create(other), Err(StoreError::DuplicateEmail(_))));
+    }
+
+    #[test]
+    fn a_writer_waits_for_the_lock() {
+        let path = store_path("lock");
+        let mut first = UserStore::open(&path).unwrap();
+        first.create(user("alice")).unwrap();
+
+        let (started, waiting) = mpsc::channel();
+        let (done, finished) = mpsc::channel();
+        let mut writer = None;
+        first
+            .update("alice", |alice| {
+                // The first store holds the lock for as long as this closure runs. A second store trying to write
+                // now has to wait for it.
+                let path = path.clone();
+                let started = started.clone();
+                let done = done.clone();
+                writer = Some(thread::spawn(move || {
+                    let mut second = UserStore::open(&path).unwrap();
+                    started.send(()).unwrap();
+                    second.create(user("bob")).unwrap();
+                    done.send(()).unwrap();
+                }));
+                waiting.recv().unwrap();
+                assert!(finished.try_recv().is_err(), "second writer didn't wait for the lock");
+                alice.sign_in_count = 42;
+            })
+            .unwrap();
+        writer.unwrap().join().unwrap();
+        finished.recv().unwrap();
+
+        // Neither writer lost the other's change.
+        let store = UserStore::open(&path).unwrap();
+        assert_eq!(store.get("alice").unwrap().sign_in_count, 42);
+        assert!(store.get("bob").is_some());
+    }
+
+    #[test]
+    fn concurrent_writers_lose_no_updates() {
+        let path = store_path("concurrent");
+        UserStore::open(&path).unwrap().create(user("counter")).unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|i| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let mut store = UserStore::open(&path).unwrap();
+                    store.create(user(&format!("user{}", i))).unwrap();
+                    for _ in 0..5 {
+                        store.update("counter", |counter| counter.sign_in_count += 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let store = UserStore::open(&path).unwrap();
+        assert_eq!(store.users().count(), 9);
+        // build_user starts the count at 1.
+        assert_eq!(store.get("counter").unwrap().sign_in_count, 1 + 8 * 5);
+    }
+
+    #[test]
+    fn a_failed_save_changes_nothing() {
+        let path = store_path("failed-save");
+        let mut store = UserStore::open(&path).unwrap();
+        store.create(user("alice")).unwrap();
+
+        // The temporary file can't be created while a directory is in its way.
+        fs::create_dir(with_suffix(&path, ".tmp")).unwrap();
+        assert!(matches!(
+            store.update("alice", |alice| alice.sign_in_count = 42),
+            Err(StoreError::Io(_))
+        ));
+        assert!(matches!(store.create(user("bob")), Err(StoreError::Io(_))));
+        assert!(matches!(
+            store.transition("alice", Transition::Deactivate, 10),
+            Err(StoreError::Io(_))
+        ));
+        let alice = store.get("alice").unwrap();
+        assert_eq!((alice.sign_in_count, alice.state), (1, AccountState::Active));
+        assert!(store.get("bob").is_none());
+        assert!(!with_suffix(&path, ".audit").exists());
+
+        fs::remove_dir(with_suffix(&path, ".tmp")).unwrap();
+        store.update("alice", |alice| alice.sign_in_count = 42).unwrap();
+        assert_eq!(UserStore::open(&path).unwrap().get("alice").unwrap().sign_in_count, 42);
+    }
+
+    #[test]
+    fn a_corrupt_line_is_reported() {
+        let path = store_path("corrupt");
+        UserStore::open(&path).unwrap().create(user("alice")).unwrap();
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"username\": \"bob\"\n");
+        fs::write(&path, contents).unwrap();
+
+        assert!(matches!(
+            UserStore::open(&path),
+            Err(StoreError::Corrupt { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn authenticate_gives_nothing_away_without_the_password() {
+        let params = HashParams { iterations: 1 };
+        let policy = SignInPolicy { max_failed_attempts: 2 };
+        let path = store_path("authenticate");
+        let mut store = UserStore::open(&path).unwrap();
+        let mut alice = user("alice");
+        alice.password_hash = Some(PasswordHash::new("right password 1", &params).unwrap());
+        store.create(alice).unwrap();
+        store.create(user("nopassword")).unwrap();
+
+        for username in &["alice", "nobody", "nopassword", "not a username", "alice"] {
+            assert!(
+                matches!(store.authenticate(username, "wrong", 10, &policy, &params), Err(StoreError::WrongPassword)),
+                "{}",
+                username
+            );
+        }
+        assert_eq!(store.get("alice").unwrap().state, AccountState::Locked);
+
+        // Once locked, a wrong password still only hears that it's wrong, and isn't counted again.
+        assert!(matches!(
+            store.authenticate("alice", "wrong", 20, &policy, &params),
+            Err(StoreError::WrongPassword)
+        ));
+        assert_eq!(store.get("alice").unwrap().failed_sign_ins, 2);
+        store.transition("nopassword", Transition::Deactivate, 30).unwrap();
+        assert!(matches!(
+            store.authenticate("nopassword", "", 40, &policy, &params),
+            Err(StoreError::WrongPassword)
+        ));
+
+        // The right password is told why it can't sign in.
+        assert!(matches!(
+            store.authenticate("alice", "right password 1", 50, &policy, &params),
+            Err(StoreError::Lifecycle(LifecycleError::NotActive(AccountState::Locked)))
+        ));
+        store.transition("alice", Transition::Unlock, 60).unwrap();
+        let alice = store.authenticate("alice", "right password 1", 70, &policy, &params).unwrap();
+        assert_eq!(alice.last_sign_in, Some(70));
+    }
+}
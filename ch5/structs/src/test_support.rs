@@ -0,0 +1,22 @@
+/*
+
+Helpers shared by the tests in this crate.
+
+*/
+
+// Random is a small linear congruential generator. Every test seeds its own, so a failing case fails the same way
+// each time it runs.
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 33) as usize
+    }
+
+    // string returns up to `max_len` chars, each picked from `pool`.
+    pub fn string(&mut self, pool: &[char], max_len: usize) -> String {
+        let len = self.next() % (max_len + 1);
+        (0..len).map(|_| pool[self.next() % pool.len()]).collect()
+    }
+}
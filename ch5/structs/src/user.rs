@@ -0,0 +1,108 @@
+use std::convert::TryFrom;
+
+use crate::json::{JsonError, Object, Value};
+use crate::lifecycle::{self, AccountState};
+use crate::password::PasswordHash;
+use crate::patch::Patch;
+use crate::validation::{Email, Username};
+
+// Deriving Patch defines UserDiff and UserPatch along with User. See `src/patch.rs`.
+#[derive(Debug, Clone, PartialEq, Eq, Patch)]
+pub struct User {
+    pub username: Username, // Username and Email each own a String, so the User instance "owns" its username and email fields. We could use &str (string slice) to share this, instead. This would require the use of lifetimes in Rust to share that data in the User struct.
+    pub email: Email, // Username and Email can only be made by validating a string first, so a User can't hold an empty or malformed one.
+    pub sign_in_count: u64,
+    pub state: AccountState, // An enum rather than `active: bool`, since an account can be locked or deactivated. See `src/lifecycle.rs`.
+    pub last_sign_in: Option<u64>, // Seconds since the Unix epoch, or None if the user has never signed in.
+    pub failed_sign_ins: u32,
+    pub password_hash: Option<PasswordHash>, // Only a salted hash is kept, never the password itself. See `src/password.rs`.
+}
+
+pub fn build_user(email: Email, username: Username) -> User {
+    User {
+        email, // when field names are the same as parameter names, we can reuse the parameter without duplicating.
+        username,
+        state: AccountState::Active,
+        sign_in_count: 1,
+        last_sign_in: None,
+        failed_sign_ins: 0,
+        password_hash: None,
+    }
+}
+
+impl User {
+    // to_json converts a User into the record written to the account store.
+    pub fn to_json(&self) -> Result<Object, JsonError> {
+        Ok(Object::new()
+            .with("username", self.username.as_str())
+            .with("email", self.email.as_str())
+            .with("sign_in_count", Value::try_from(self.sign_in_count)?)
+            .with("state", self.state.to_string())
+            .with("last_sign_in", self.last_sign_in.map(Value::try_from).transpose()?)
+            .with("failed_sign_ins", self.failed_sign_ins)
+            .with("password_hash", self.password_hash.as_ref().map(ToString::to_string)))
+    }
+
+    // from_json reads a User back out of an account store record.
+    pub fn from_json(object: &Object) -> Result<User, JsonError> {
+        Ok(User {
+            username: object.parsed("username")?,
+            email: object.parsed("email")?,
+            sign_in_count: object.unsigned("sign_in_count")?,
+            state: lifecycle::state_from_json(object)?,
+            last_sign_in: object.optional_unsigned("last_sign_in")?,
+            failed_sign_ins: object.optional_unsigned("failed_sign_ins")?.unwrap_or(0),
+            password_hash: match object.optional_string("password_hash")? {
+                Some(_) => Some(object.parsed("password_hash")?),
+                None => None,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn user() -> User {
+        build_user(
+            Email::parse("alice@example.com").unwrap(),
+            Username::parse("alice").unwrap(),
+        )
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut alice = user();
+        alice.sign_in_count = i64::MAX as u64;
+        alice.last_sign_in = Some(1_700_000_000);
+        alice.failed_sign_ins = u32::MAX;
+
+        let text = alice.to_json().unwrap().to_string();
+        assert_eq!(User::from_json(&json::parse_object(&text).unwrap()), Ok(alice));
+    }
+
+    #[test]
+    fn counts_that_dont_fit_are_errors() {
+        let mut alice = user();
+        alice.sign_in_count = u64::MAX;
+        assert!(alice.to_json().is_err());
+
+        alice.sign_in_count = 1;
+        alice.last_sign_in = Some(u64::MAX);
+        assert!(alice.to_json().is_err());
+
+        let record = user().to_json().unwrap();
+        for &(field, value) in &[("sign_in_count", -1), ("last_sign_in", -5), ("failed_sign_ins", 1 << 32)] {
+            let object = record.fields().fold(Object::new(), |object, (key, original)| {
+                object.with(key, if key == field { Value::Number(value) } else { original.clone() })
+            });
+            assert!(
+                matches!(User::from_json(&object), Err(JsonError::InvalidField { field: ref f, .. }) if f == field),
+                "{}",
+                object
+            );
+        }
+    }
+}
@@ -0,0 +1,431 @@
+/*
+
+Validated usernames and emails.
+
+`build_user(email: String, username: String)` used to accept any String at all, including "" and "not an email".
+Username and Email are newtypes: structs with a single private field. The only way to get one is through `parse`,
+which checks the input first, so holding a Username or an Email is proof that the value was validated. User stores
+these types instead of raw Strings, which means a User can only ever be built from validated values.
+
+Both types normalize their input to lower case, so "Alice" and "alice" are the same username, and
+"Alice@Example.COM" and "alice@example.com" are the same email address.
+
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::json::Value;
+
+pub const USERNAME_MIN_LEN: usize = 3;
+pub const USERNAME_MAX_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Username(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsernameError {
+    TooShort { len: usize },
+    TooLong { len: usize },
+    MustStartWithLetter,
+    InvalidChar { c: char, position: usize },
+}
+
+impl fmt::Display for UsernameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UsernameError::TooShort { len } => write!(
+                f,
+                "username is {} chars long, but must be at least {}",
+                len, USERNAME_MIN_LEN
+            ),
+            UsernameError::TooLong { len } => write!(
+                f,
+                "username is {} chars long, but must be at most {}",
+                len, USERNAME_MAX_LEN
+            ),
+            UsernameError::MustStartWithLetter => write!(f, "username must start with a letter"),
+            UsernameError::InvalidChar { c, position } => write!(
+                f,
+                "username contains {:?} at position {}; only letters, digits, '_', '-' and '.' are allowed",
+                c, position
+            ),
+        }
+    }
+}
+
+impl Error for UsernameError {}
+
+impl Username {
+    // parse accepts 3 to 32 ASCII letters, digits, '_', '-' and '.', starting with a letter.
+    pub fn parse(s: &str) -> Result<Username, UsernameError> {
+        let len = s.chars().count();
+        if len < USERNAME_MIN_LEN {
+            return Err(UsernameError::TooShort { len });
+        }
+        if len > USERNAME_MAX_LEN {
+            return Err(UsernameError::TooLong { len });
+        }
+
+        for (position, c) in s.chars().enumerate() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.') {
+                return Err(UsernameError::InvalidChar { c, position });
+            }
+        }
+        if !s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(UsernameError::MustStartWithLetter);
+        }
+
+        Ok(Username(s.to_ascii_lowercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Username {
+    type Err = UsernameError;
+
+    fn from_str(s: &str) -> Result<Username, UsernameError> {
+        Username::parse(s)
+    }
+}
+
+impl From<Username> for Value {
+    fn from(username: Username) -> Value {
+        Value::String(username.0)
+    }
+}
+
+impl fmt::Display for Username {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub const EMAIL_MAX_LEN: usize = 254;
+pub const LOCAL_PART_MAX_LEN: usize = 64;
+pub const DOMAIN_LABEL_MAX_LEN: usize = 63;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Email(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmailError {
+    Empty,
+    TooLong { len: usize },
+    MissingAt,
+    EmptyLocalPart,
+    LocalPartTooLong { len: usize },
+    InvalidLocalChar { c: char },
+    MisplacedDot,
+    EmptyDomain,
+    SingleLabelDomain,
+    EmptyDomainLabel,
+    DomainLabelTooLong { label: String },
+    InvalidDomainChar { c: char },
+    MisplacedHyphen { label: String },
+    NumericTopLevelDomain,
+}
+
+impl fmt::Display for EmailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmailError::Empty => write!(f, "email is empty"),
+            EmailError::TooLong { len } => {
+                write!(f, "email is {} chars long, but must be at most {}", len, EMAIL_MAX_LEN)
+            }
+            EmailError::MissingAt => write!(f, "email has no '@'"),
+            EmailError::EmptyLocalPart => write!(f, "email has nothing before the '@'"),
+            EmailError::LocalPartTooLong { len } => write!(
+                f,
+                "the part before the '@' is {} chars long, but must be at most {}",
+                len, LOCAL_PART_MAX_LEN
+            ),
+            EmailError::InvalidLocalChar { c } => write!(f, "{:?} is not allowed before the '@'", c),
+            EmailError::MisplacedDot => {
+                write!(f, "the part before the '@' can't start or end with '.' or contain \"..\"")
+            }
+            EmailError::EmptyDomain => write!(f, "email has nothing after the '@'"),
+            EmailError::SingleLabelDomain => write!(f, "email domain needs at least one '.'"),
+            EmailError::EmptyDomainLabel => write!(f, "email domain can't start or end with '.' or contain \"..\""),
+            EmailError::DomainLabelTooLong { label } => write!(
+                f,
+                "domain label {:?} is longer than {} chars",
+                label, DOMAIN_LABEL_MAX_LEN
+            ),
+            EmailError::InvalidDomainChar { c } => write!(f, "{:?} is not allowed in the domain", c),
+            EmailError::MisplacedHyphen { label } => {
+                write!(f, "domain label {:?} can't start or end with '-'", label)
+            }
+            EmailError::NumericTopLevelDomain => write!(f, "the last part of the domain can't be all digits"),
+        }
+    }
+}
+
+impl Error for EmailError {}
+
+// is_atext reports whether `c` may appear in the local part of an address, per RFC 5322's `atext`.
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~-".contains(c)
+}
+
+fn validate_local_part(local: &str) -> Result<(), EmailError> {
+    if local.is_empty() {
+        return Err(EmailError::EmptyLocalPart);
+    }
+    if local.len() > LOCAL_PART_MAX_LEN {
+        return Err(EmailError::LocalPartTooLong { len: local.len() });
+    }
+    if let Some(c) = local.chars().find(|&c| !(is_atext(c) || c == '.')) {
+        return Err(EmailError::InvalidLocalChar { c });
+    }
+    if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+        return Err(EmailError::MisplacedDot);
+    }
+    Ok(())
+}
+
+fn validate_domain(domain: &str) -> Result<(), EmailError> {
+    if domain.is_empty() {
+        return Err(EmailError::EmptyDomain);
+    }
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 {
+        return Err(EmailError::SingleLabelDomain);
+    }
+
+    for label in &labels {
+        if label.is_empty() {
+            return Err(EmailError::EmptyDomainLabel);
+        }
+        if label.len() > DOMAIN_LABEL_MAX_LEN {
+            return Err(EmailError::DomainLabelTooLong { label: label.to_string() });
+        }
+        if let Some(c) = label.chars().find(|&c| !(c.is_ascii_alphanumeric() || c == '-')) {
+            return Err(EmailError::InvalidDomainChar { c });
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(EmailError::MisplacedHyphen { label: label.to_string() });
+        }
+    }
+
+    if labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit()) {
+        return Err(EmailError::NumericTopLevelDomain);
+    }
+    Ok(())
+}
+
+impl Email {
+    // parse accepts a "lite" subset of RFC 5322 addresses: a dot-atom local part, an '@', and a domain name of at
+    // least two labels. Quoted local parts, comments and IP address literals are rejected.
+    pub fn parse(s: &str) -> Result<Email, EmailError> {
+        if s.is_empty() {
+            return Err(EmailError::Empty);
+        }
+        let len = s.chars().count();
+        if len > EMAIL_MAX_LEN {
+            return Err(EmailError::TooLong { len });
+        }
+
+        let at = s.rfind('@').ok_or(EmailError::MissingAt)?;
+        let (local, domain) = (&s[..at], &s[at + 1..]);
+        validate_local_part(local)?;
+        validate_domain(domain)?;
+
+        Ok(Email(s.to_ascii_lowercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn domain(&self) -> &str {
+        let at = self.0.rfind('@').expect("a parsed email always has an '@'");
+        &self.0[at + 1..]
+    }
+}
+
+impl FromStr for Email {
+    type Err = EmailError;
+
+    fn from_str(s: &str) -> Result<Email, EmailError> {
+        Email::parse(s)
+    }
+}
+
+impl From<Email> for Value {
+    fn from(email: Email) -> Value {
+        Value::String(email.0)
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::Random;
+
+    // is_valid_username restates the username rules independently of Username::parse.
+    fn is_valid_username(s: &str) -> bool {
+        let len = s.chars().count();
+        (USERNAME_MIN_LEN..=USERNAME_MAX_LEN).contains(&len)
+            && s.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && s.chars().all(|c| c.is_ascii_alphanumeric() || "_-.".contains(c))
+    }
+
+    #[test]
+    fn username_accepts_exactly_the_valid_names() {
+        let mut random = Random(37);
+        let pool: Vec<char> = "aZq09_-. @éK".chars().collect();
+        for _ in 0..20_000 {
+            let s = random.string(&pool, USERNAME_MAX_LEN + 4);
+            match Username::parse(&s) {
+                Ok(username) => {
+                    assert!(is_valid_username(&s), "{:?} was accepted", s);
+                    assert_eq!(username.as_str(), s.to_ascii_lowercase());
+                    // Parsing a parsed username gives it back unchanged.
+                    assert_eq!(Username::parse(&username.to_string()), Ok(username.clone()));
+                    assert_eq!(username.to_string().parse::<Username>(), Ok(username));
+                }
+                Err(_) => assert!(!is_valid_username(&s), "{:?} was rejected", s),
+            }
+        }
+    }
+
+    #[test]
+    fn username_rejects_empty_and_overlong_input() {
+        assert_eq!(Username::parse(""), Err(UsernameError::TooShort { len: 0 }));
+        assert_eq!(Username::parse("ab"), Err(UsernameError::TooShort { len: 2 }));
+        assert!(Username::parse(&"a".repeat(USERNAME_MAX_LEN)).is_ok());
+        assert_eq!(
+            Username::parse(&"a".repeat(USERNAME_MAX_LEN + 1)),
+            Err(UsernameError::TooLong { len: 33 })
+        );
+        // Length is counted in chars, so multi-byte chars are too long or invalid, never split.
+        assert_eq!(Username::parse(&"é".repeat(40)), Err(UsernameError::TooLong { len: 40 }));
+        assert_eq!(
+            Username::parse("abé"),
+            Err(UsernameError::InvalidChar { c: 'é', position: 2 })
+        );
+        assert_eq!(Username::parse("1abc"), Err(UsernameError::MustStartWithLetter));
+    }
+
+    // valid_email builds a random address from the allowed parts.
+    fn valid_email(random: &mut Random) -> String {
+        let atext: Vec<char> = "abcXYZ0189!#$%&'*+/=?^_`{|}~-".chars().collect();
+        let label: Vec<char> = "abcXYZ0189-".chars().collect();
+
+        let words = 1 + random.next() % 3;
+        let local: Vec<String> = (0..words)
+            .map(|_| {
+                let len = 1 + random.next() % 8;
+                (0..len).map(|_| atext[random.next() % atext.len()]).collect()
+            })
+            .collect();
+
+        let labels = 2 + random.next() % 3;
+        let domain: Vec<String> = (0..labels)
+            .map(|i| {
+                let len = 1 + random.next() % 10;
+                let mut label: String = (0..len).map(|_| label[random.next() % label.len()]).collect();
+                // Labels can't start or end with '-', and the last can't be all digits.
+                label = label.trim_matches('-').to_string();
+                if label.is_empty() || (i == labels - 1 && label.chars().all(|c| c.is_ascii_digit())) {
+                    label.push('x');
+                }
+                label
+            })
+            .collect();
+
+        format!("{}@{}", local.join("."), domain.join("."))
+    }
+
+    #[test]
+    fn email_round_trips() {
+        let mut random = Random(38);
+        for _ in 0..10_000 {
+            let s = valid_email(&mut random);
+            let email = Email::parse(&s).unwrap_or_else(|e| panic!("{:?}: {}", s, e));
+            assert_eq!(email.as_str(), s.to_ascii_lowercase());
+            assert_eq!(Email::parse(&email.to_string()), Ok(email.clone()));
+            assert_eq!(email.domain(), s[s.rfind('@').unwrap() + 1..].to_ascii_lowercase());
+        }
+    }
+
+    #[test]
+    fn email_fuzz_never_accepts_garbage() {
+        let mut random = Random(39);
+        let pool: Vec<char> = "a0.@-_ \"é[]".chars().collect();
+        for _ in 0..20_000 {
+            let s = random.string(&pool, 20);
+            if let Ok(email) = Email::parse(&s) {
+                let (local, domain) = s.split_at(s.rfind('@').unwrap());
+                assert!(!local.is_empty() && domain.len() > 1, "{:?}", s);
+                assert!(s.is_ascii() && !s.contains(' ') && !s.contains(".."), "{:?}", s);
+                assert_eq!(Email::parse(&email.to_string()), Ok(email));
+            }
+        }
+    }
+
+    #[test]
+    fn email_rejects_empty_and_overlong_input() {
+        assert_eq!(Email::parse(""), Err(EmailError::Empty));
+
+        let local = "a".repeat(LOCAL_PART_MAX_LEN);
+        assert!(Email::parse(&format!("{}@example.com", local)).is_ok());
+        assert_eq!(
+            Email::parse(&format!("{}a@example.com", local)),
+            Err(EmailError::LocalPartTooLong { len: 65 })
+        );
+
+        let label = "b".repeat(DOMAIN_LABEL_MAX_LEN + 1);
+        assert_eq!(
+            Email::parse(&format!("a@{}.com", label)),
+            Err(EmailError::DomainLabelTooLong { label })
+        );
+
+        // 64 + 1 + 189 = 254 chars is the longest allowed.
+        let domain = format!("{}.{}.{}.com", "c".repeat(60), "d".repeat(60), "e".repeat(63));
+        let longest = format!("{}@{}", local, domain);
+        assert_eq!(longest.len(), EMAIL_MAX_LEN);
+        assert!(Email::parse(&longest).is_ok());
+        assert_eq!(
+            Email::parse(&format!("{}@f{}", local, domain)),
+            Err(EmailError::TooLong { len: 255 })
+        );
+    }
+
+    #[test]
+    fn email_rejects_malformed_addresses() {
+        let cases = [
+            ("alice", EmailError::MissingAt),
+            ("@example.com", EmailError::EmptyLocalPart),
+            ("alice@", EmailError::EmptyDomain),
+            ("alice@localhost", EmailError::SingleLabelDomain),
+            (".alice@example.com", EmailError::MisplacedDot),
+            ("al..ice@example.com", EmailError::MisplacedDot),
+            ("al ice@example.com", EmailError::InvalidLocalChar { c: ' ' }),
+            ("alice@example..com", EmailError::EmptyDomainLabel),
+            ("alice@exa_mple.com", EmailError::InvalidDomainChar { c: '_' }),
+            (
+                "alice@-example.com",
+                EmailError::MisplacedHyphen {
+                    label: String::from("-example"),
+                },
+            ),
+            ("alice@example.123", EmailError::NumericTopLevelDomain),
+        ];
+        for (input, error) in cases.iter() {
+            assert_eq!(Email::parse(input).as_ref(), Err(error), "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,534 @@
+/*
+
+CIDR networks: a block of addresses written as an address and a prefix length, e.g. "10.0.0.0/8" or
+"2001:db8::/32". The prefix length is how many leading bits every address in the network shares; the rest are the
+host bits.
+
+Parsing is strict, because a rule like "10.0.0.1/8" in a firewall config is more likely a typo than a way to write
+10.0.0.0/8, so host bits that aren't zero are an error. Network::containing is there for when masking off the host
+bits is what's wanted.
+
+Both families share the same arithmetic: an address is turned into a number (32 bits for IPv4, 128 for IPv6) and
+back. Networks and addresses of different families never contain each other, so "::ffff:10.0.0.1" isn't in
+10.0.0.0/8.
+
+Networks sort by family, then address, then prefix length, so a network comes before its subnets.
+
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ip::{IpAddr, IpAddrKind, ParseIpError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Network {
+    kind: IpAddrKind,
+    // The network address as a number, with every host bit zero.
+    bits: u128,
+    prefix: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CidrError {
+    MissingPrefix,
+    InvalidPrefix(String),
+    PrefixTooLong { prefix: u32, max: u8 },
+    Address(ParseIpError),
+    ZoneNotAllowed,
+    // The address had host bits set; `network` is what it would be with them cleared.
+    HostBitsSet { network: Network },
+    // A subnet's prefix must be at least as long as its network's.
+    SubnetTooLarge { prefix: u8, new_prefix: u8 },
+}
+
+impl fmt::Display for CidrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CidrError::MissingPrefix => write!(f, "network has no \"/\" and prefix length"),
+            CidrError::InvalidPrefix(prefix) => write!(f, "prefix length {:?} is not a number", prefix),
+            CidrError::PrefixTooLong { prefix, max } => {
+                write!(f, "prefix length {} is longer than the {} bits of the address", prefix, max)
+            }
+            CidrError::Address(e) => write!(f, "invalid network address: {}", e),
+            CidrError::ZoneNotAllowed => write!(f, "a network address can't have a zone ID"),
+            CidrError::HostBitsSet { network } => write!(f, "host bits are set; did you mean {}?", network),
+            CidrError::SubnetTooLarge { prefix, new_prefix } => write!(
+                f,
+                "a /{} network can't be split into larger /{} subnets",
+                prefix, new_prefix
+            ),
+        }
+    }
+}
+
+impl Error for CidrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CidrError::Address(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIpError> for CidrError {
+    fn from(e: ParseIpError) -> CidrError {
+        CidrError::Address(e)
+    }
+}
+
+// width is the number of bits in an address of the `kind` family.
+fn width(kind: IpAddrKind) -> u8 {
+    match kind {
+        IpAddrKind::V4 => 32,
+        IpAddrKind::V6 => 128,
+    }
+}
+
+// mask has the leading `prefix` bits of a `kind` address set.
+fn mask(kind: IpAddrKind, prefix: u8) -> u128 {
+    if prefix == 0 {
+        return 0;
+    }
+    (!0u128 << (128 - prefix as u32)) >> (128 - width(kind) as u32)
+}
+
+fn to_bits(addr: &IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(a, b, c, d) => u32::from_be_bytes([*a, *b, *c, *d]) as u128,
+        IpAddr::V6 { segments, .. } => segments
+            .iter()
+            .fold(0, |bits, &segment| (bits << 16) | segment as u128),
+    }
+}
+
+fn from_bits(kind: IpAddrKind, bits: u128) -> IpAddr {
+    match kind {
+        IpAddrKind::V4 => {
+            let [a, b, c, d] = (bits as u32).to_be_bytes();
+            IpAddr::V4(a, b, c, d)
+        }
+        IpAddrKind::V6 => {
+            let mut segments = [0; 8];
+            for (i, segment) in segments.iter_mut().enumerate() {
+                *segment = (bits >> (16 * (7 - i))) as u16;
+            }
+            IpAddr::V6 { segments, zone: None }
+        }
+    }
+}
+
+impl Network {
+    // new makes the network `addr`/`prefix`, which must have no host bits set.
+    pub fn new(addr: &IpAddr, prefix: u8) -> Result<Network, CidrError> {
+        let network = Network::containing(addr, prefix)?;
+        if network.bits != to_bits(addr) {
+            return Err(CidrError::HostBitsSet { network });
+        }
+        Ok(network)
+    }
+
+    // containing makes the `prefix` long network that `addr` is in, clearing its host bits.
+    pub fn containing(addr: &IpAddr, prefix: u8) -> Result<Network, CidrError> {
+        let kind = addr.kind();
+        if prefix > width(kind) {
+            return Err(CidrError::PrefixTooLong {
+                prefix: prefix as u32,
+                max: width(kind),
+            });
+        }
+        if addr.zone().is_some() {
+            return Err(CidrError::ZoneNotAllowed);
+        }
+        Ok(Network {
+            kind,
+            bits: to_bits(addr) & mask(kind, prefix),
+            prefix,
+        })
+    }
+
+    pub fn kind(&self) -> IpAddrKind {
+        self.kind
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    // network is the first address in the network.
+    pub fn network(&self) -> IpAddr {
+        from_bits(self.kind, self.bits)
+    }
+
+    // broadcast is the last address in the network. IPv6 has no broadcast, but the last address is still useful.
+    pub fn broadcast(&self) -> IpAddr {
+        from_bits(self.kind, self.last())
+    }
+
+    pub fn netmask(&self) -> IpAddr {
+        from_bits(self.kind, mask(self.kind, self.prefix))
+    }
+
+    fn last(&self) -> u128 {
+        self.bits | (mask(self.kind, width(self.kind)) & !mask(self.kind, self.prefix))
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        addr.kind() == self.kind && to_bits(addr) & mask(self.kind, self.prefix) == self.bits
+    }
+
+    // contains_network is true when every address in `other` is in this network.
+    pub fn contains_network(&self, other: &Network) -> bool {
+        other.kind == self.kind && other.prefix >= self.prefix && other.bits & mask(self.kind, self.prefix) == self.bits
+    }
+
+    // hosts iterates over the addresses that can be given to hosts. For IPv4 that leaves out the network and
+    // broadcast addresses, except in a /31, where both are hosts (RFC 3021), and a /32. IPv6 has no broadcast, so
+    // every address is a host.
+    pub fn hosts(&self) -> Hosts {
+        let (mut first, mut last) = (self.bits, self.last());
+        if self.kind == IpAddrKind::V4 && self.prefix < 31 {
+            first += 1;
+            last -= 1;
+        }
+        Hosts {
+            kind: self.kind,
+            walk: Walk::new(first, last, Some(1)),
+        }
+    }
+
+    // subnets iterates over the `new_prefix` long networks that this network splits into, in order.
+    pub fn subnets(&self, new_prefix: u8) -> Result<Subnets, CidrError> {
+        if new_prefix > width(self.kind) {
+            return Err(CidrError::PrefixTooLong {
+                prefix: new_prefix as u32,
+                max: width(self.kind),
+            });
+        }
+        if new_prefix < self.prefix {
+            return Err(CidrError::SubnetTooLarge {
+                prefix: self.prefix,
+                new_prefix,
+            });
+        }
+        // A step of 2^128 would overflow, but then there's only one subnet anyway.
+        let step = 1u128.checked_shl((width(self.kind) - new_prefix) as u32);
+        Ok(Subnets {
+            kind: self.kind,
+            prefix: new_prefix,
+            walk: Walk::new(self.bits, self.last(), step),
+        })
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.network(), self.prefix)
+    }
+}
+
+impl FromStr for Network {
+    type Err = CidrError;
+
+    fn from_str(s: &str) -> Result<Network, CidrError> {
+        let (addr, prefix) = s.split_once('/').ok_or(CidrError::MissingPrefix)?;
+        let addr: IpAddr = addr.parse()?;
+        // Only plain decimal digits, so "+8" and " 8" aren't accepted, and no leading zeros, so neither is "08".
+        let leading_zero = prefix.len() > 1 && prefix.starts_with('0');
+        if prefix.is_empty() || leading_zero || !prefix.chars().all(|c| c.is_ascii_digit()) {
+            return Err(CidrError::InvalidPrefix(prefix.to_string()));
+        }
+        let prefix: u32 = prefix.parse().map_err(|_| CidrError::InvalidPrefix(prefix.to_string()))?;
+        if prefix > width(addr.kind()) as u32 {
+            return Err(CidrError::PrefixTooLong {
+                prefix,
+                max: width(addr.kind()),
+            });
+        }
+        Network::new(&addr, prefix as u8)
+    }
+}
+
+// Walk counts from `next` to `last` inclusive, `step` at a time. A step of None means there's no next value.
+struct Walk {
+    next: Option<u128>,
+    last: u128,
+    step: Option<u128>,
+}
+
+impl Walk {
+    fn new(first: u128, last: u128, step: Option<u128>) -> Walk {
+        Walk {
+            next: Some(first),
+            last,
+            step,
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<u128> {
+        let current = self.next.filter(|&next| next <= self.last)?;
+        self.next = self.step.and_then(|step| current.checked_add(step));
+        Some(current)
+    }
+}
+
+pub struct Hosts {
+    kind: IpAddrKind,
+    walk: Walk,
+}
+
+impl Iterator for Hosts {
+    type Item = IpAddr;
+
+    fn next(&mut self) -> Option<IpAddr> {
+        let kind = self.kind;
+        self.walk.next().map(|bits| from_bits(kind, bits))
+    }
+}
+
+pub struct Subnets {
+    kind: IpAddrKind,
+    prefix: u8,
+    walk: Walk,
+}
+
+impl Iterator for Subnets {
+    type Item = Network;
+
+    fn next(&mut self) -> Option<Network> {
+        let (kind, prefix) = (self.kind, self.prefix);
+        self.walk.next().map(|bits| Network { kind, bits, prefix })
+    }
+}
+
+// covering appends the fewest networks that cover exactly `first` to `last` inclusive. Each is as large as it can
+// be while starting on a multiple of its own size and not running past `last`.
+fn covering(kind: IpAddrKind, mut first: u128, last: u128, networks: &mut Vec<Network>) {
+    let bits = width(kind) as u32;
+    loop {
+        let aligned = first.trailing_zeros().min(bits);
+        // The largest power of two no bigger than the number of addresses left, which is (last - first) + 1.
+        let fits = match (last - first).checked_add(1) {
+            Some(left) => 127 - left.leading_zeros(),
+            None => 128,
+        };
+        let host_bits = aligned.min(fits);
+        networks.push(Network {
+            kind,
+            bits: first,
+            prefix: (bits - host_bits) as u8,
+        });
+
+        match 1u128.checked_shl(host_bits).and_then(|size| first.checked_add(size)) {
+            Some(next) if next <= last => first = next,
+            _ => return,
+        }
+    }
+}
+
+// collapse returns the fewest networks that cover exactly the addresses in `networks`, merging networks that
+// overlap or sit next to each other. The result is sorted.
+pub fn collapse(networks: &[Network]) -> Vec<Network> {
+    let mut ranges: Vec<(IpAddrKind, u128, u128)> = networks
+        .iter()
+        .map(|network| (network.kind, network.bits, network.last()))
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(IpAddrKind, u128, u128)> = Vec::with_capacity(ranges.len());
+    for (kind, first, last) in ranges {
+        match merged.last_mut() {
+            Some((merged_kind, _, merged_last))
+                if *merged_kind == kind && merged_last.checked_add(1).is_none_or(|next| first <= next) =>
+            {
+                *merged_last = (*merged_last).max(last);
+            }
+            _ => merged.push((kind, first, last)),
+        }
+    }
+
+    let mut collapsed = Vec::new();
+    for (kind, first, last) in merged {
+        covering(kind, first, last, &mut collapsed);
+    }
+    collapsed
+}
+
+// summarize returns the fewest networks that cover exactly the addresses in `addresses`, e.g. 10.0.0.0 to
+// 10.0.0.3 and 10.0.0.4 become 10.0.0.0/30 and 10.0.0.4/32. Zone IDs are ignored.
+pub fn summarize(addresses: &[IpAddr]) -> Vec<Network> {
+    let networks: Vec<Network> = addresses
+        .iter()
+        .map(|addr| Network {
+            kind: addr.kind(),
+            bits: to_bits(addr),
+            prefix: width(addr.kind()),
+        })
+        .collect();
+    collapse(&networks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> Network {
+        s.parse().unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn nets(networks: &[&str]) -> Vec<Network> {
+        networks.iter().map(|s| net(s)).collect()
+    }
+
+    #[test]
+    fn parsing() {
+        for &s in &["10.0.0.0/8", "0.0.0.0/0", "192.168.1.7/32", "2001:db8::/32", "::/0", "::1/128"] {
+            assert_eq!(net(s).to_string(), s);
+        }
+        assert_eq!(net("10.0.0.0/8").prefix(), 8);
+        assert_eq!(net("2001:DB8:0::/32").to_string(), "2001:db8::/32");
+
+        let err = |s: &str| s.parse::<Network>().unwrap_err();
+        assert_eq!(err("10.0.0.1/8"), CidrError::HostBitsSet { network: net("10.0.0.0/8") });
+        assert_eq!(err("10.0.0.1/8").to_string(), "host bits are set; did you mean 10.0.0.0/8?");
+        assert_eq!(err("2001:db8::1/32"), CidrError::HostBitsSet { network: net("2001:db8::/32") });
+        assert_eq!(err("fe80::%eth0/64"), CidrError::ZoneNotAllowed);
+        assert_eq!(err("10.0.0.0/33"), CidrError::PrefixTooLong { prefix: 33, max: 32 });
+        assert_eq!(err("::/129"), CidrError::PrefixTooLong { prefix: 129, max: 128 });
+        assert_eq!(err("10.0.0.0"), CidrError::MissingPrefix);
+        for &prefix in &["", "x", "8x", "+8", " 8", "-1", "08", "00", "99999999999"] {
+            assert_eq!(
+                err(&format!("10.0.0.0/{}", prefix)),
+                CidrError::InvalidPrefix(prefix.to_string()),
+                "{:?}",
+                prefix
+            );
+        }
+        assert!(matches!(err("10.0.0/8"), CidrError::Address(_)));
+        assert!(matches!(err("10.0.0.0/8/8"), CidrError::InvalidPrefix(_)));
+    }
+
+    #[test]
+    fn addresses_in_a_network() {
+        let lan = net("192.168.1.0/24");
+        assert_eq!(lan.network(), addr("192.168.1.0"));
+        assert_eq!(lan.broadcast(), addr("192.168.1.255"));
+        assert_eq!(lan.netmask(), addr("255.255.255.0"));
+        assert!(lan.contains(&addr("192.168.1.77")));
+        assert!(!lan.contains(&addr("192.168.2.0")));
+        assert!(!lan.contains(&addr("::ffff:192.168.1.77")));
+
+        let everything = net("0.0.0.0/0");
+        assert_eq!(everything.broadcast(), addr("255.255.255.255"));
+        assert_eq!(everything.netmask(), addr("0.0.0.0"));
+        assert!(everything.contains_network(&lan));
+        assert!(!lan.contains_network(&everything));
+
+        let v6 = net("2001:db8::/32");
+        assert_eq!(v6.broadcast(), addr("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff"));
+        assert_eq!(v6.netmask(), addr("ffff:ffff::"));
+        assert_eq!(net("::/0").broadcast(), addr("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"));
+
+        let host = net("10.1.2.3/32");
+        assert_eq!((host.network(), host.broadcast()), (addr("10.1.2.3"), addr("10.1.2.3")));
+        assert_eq!(host.netmask(), addr("255.255.255.255"));
+
+        assert_eq!(Network::containing(&addr("10.1.2.3"), 8), Ok(net("10.0.0.0/8")));
+        assert_eq!(
+            Network::new(&addr("10.1.2.3"), 8),
+            Err(CidrError::HostBitsSet { network: net("10.0.0.0/8") })
+        );
+    }
+
+    #[test]
+    fn hosts() {
+        let hosts = |s: &str| -> Vec<String> { net(s).hosts().map(|host| host.to_string()).collect() };
+        assert_eq!(hosts("10.0.0.0/30"), vec!["10.0.0.1", "10.0.0.2"]);
+        assert_eq!(hosts("10.0.0.0/31"), vec!["10.0.0.0", "10.0.0.1"]);
+        assert_eq!(hosts("10.0.0.7/32"), vec!["10.0.0.7"]);
+        assert_eq!(hosts("2001:db8::1/128"), vec!["2001:db8::1"]);
+        assert_eq!(hosts("2001:db8::/127"), vec!["2001:db8::", "2001:db8::1"]);
+        assert_eq!(net("10.0.0.0/24").hosts().count(), 254);
+
+        // Too many to list, but they start and end in the right places.
+        let mut all = net("0.0.0.0/0").hosts();
+        assert_eq!(all.next(), Some(addr("0.0.0.1")));
+        assert_eq!(all.next(), Some(addr("0.0.0.2")));
+        assert_eq!(net("::/0").hosts().next(), Some(addr("::")));
+        // The last addresses of each family don't overflow.
+        assert_eq!(hosts("255.255.255.252/30"), vec!["255.255.255.253", "255.255.255.254"]);
+        let last = "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff";
+        assert_eq!(hosts(&format!("{}/128", last)), vec![last]);
+    }
+
+    #[test]
+    fn subnets() {
+        let subnets = |s: &str, prefix: u8| -> Vec<String> {
+            net(s).subnets(prefix).unwrap().map(|subnet| subnet.to_string()).collect()
+        };
+        assert_eq!(
+            subnets("10.0.0.0/24", 26),
+            vec!["10.0.0.0/26", "10.0.0.64/26", "10.0.0.128/26", "10.0.0.192/26"]
+        );
+        assert_eq!(subnets("10.0.0.0/24", 24), vec!["10.0.0.0/24"]);
+        assert_eq!(subnets("10.0.0.0/31", 32), vec!["10.0.0.0/32", "10.0.0.1/32"]);
+        assert_eq!(subnets("255.255.255.0/24", 25), vec!["255.255.255.0/25", "255.255.255.128/25"]);
+        assert_eq!(subnets("2001:db8::/32", 34).len(), 4);
+        assert_eq!(net("10.0.0.0/8").subnets(24).unwrap().count(), 65_536);
+
+        // A step of 2^128 doesn't fit in a u128, but ::/0 is its own only /0 subnet.
+        assert_eq!(subnets("::/0", 0), vec!["::/0"]);
+        assert_eq!(subnets("::/0", 1), vec!["::/1", "8000::/1"]);
+        assert_eq!(subnets("0.0.0.0/0", 0), vec!["0.0.0.0/0"]);
+
+        assert_eq!(
+            net("10.0.0.0/24").subnets(23).err(),
+            Some(CidrError::SubnetTooLarge { prefix: 24, new_prefix: 23 })
+        );
+        assert_eq!(
+            net("10.0.0.0/24").subnets(33).err(),
+            Some(CidrError::PrefixTooLong { prefix: 33, max: 32 })
+        );
+    }
+
+    #[test]
+    fn collapsing() {
+        let collapsed = |networks: &[&str]| -> Vec<String> {
+            collapse(&nets(networks)).iter().map(|network| network.to_string()).collect()
+        };
+        assert!(collapse(&[]).is_empty());
+        // Adjacent halves merge, and so do their neighbours once they line up.
+        assert_eq!(collapsed(&["10.0.0.128/25", "10.0.0.0/25"]), vec!["10.0.0.0/24"]);
+        assert_eq!(collapsed(&["10.0.0.0/24", "10.0.1.0/25", "10.0.1.128/25"]), vec!["10.0.0.0/23"]);
+        // Adjacent networks that don't start on a boundary of the merged size stay apart.
+        assert_eq!(collapsed(&["10.0.1.0/24", "10.0.2.0/24"]), vec!["10.0.1.0/24", "10.0.2.0/24"]);
+        // Overlapping and duplicate networks are covered once.
+        assert_eq!(collapsed(&["10.0.0.0/24", "10.0.0.64/26", "10.0.0.0/24"]), vec!["10.0.0.0/24"]);
+        assert_eq!(collapsed(&["10.0.0.0/8", "10.1.0.0/16", "11.0.0.0/8"]), vec!["10.0.0.0/7"]);
+        // Families never merge, and IPv4 sorts first.
+        assert_eq!(
+            collapsed(&["::/1", "10.0.0.0/8", "8000::/1", "0.0.0.0/32", "11.0.0.0/8"]),
+            vec!["0.0.0.0/32", "10.0.0.0/7", "::/0"]
+        );
+        assert_eq!(collapsed(&["0.0.0.0/1", "128.0.0.0/1"]), vec!["0.0.0.0/0"]);
+
+        let summarized = |addresses: &[&str]| -> Vec<String> {
+            let addresses: Vec<IpAddr> = addresses.iter().map(|s| addr(s)).collect();
+            summarize(&addresses).iter().map(|network| network.to_string()).collect()
+        };
+        assert_eq!(
+            summarized(&["10.0.0.4", "10.0.0.0", "10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.1"]),
+            vec!["10.0.0.0/30", "10.0.0.4/32"]
+        );
+        assert_eq!(summarized(&["fe80::1%eth0", "fe80::", "10.0.0.9"]), vec!["10.0.0.9/32", "fe80::/127"]);
+    }
+}
@@ -0,0 +1,402 @@
+/*
+
+IP addresses, parsed and validated.
+
+The chapter's `IpAddr::V6(String)` would hold any string at all. Here both variants hold the address itself: four
+octets for V4, and eight 16 bit groups for V6, plus the optional zone ID that picks a network interface for
+link-local addresses (`fe80::1%eth0`). IpAddrKind is back to naming the family without any data, since the address
+lives in IpAddr.
+
+Parsing follows RFC 4291 for IPv6 text, with a few rules made stricter than some parsers:
+
+* An IPv4 octet can't have a leading zero, so "010" isn't silently read as 10 when some tools would read it as
+  octal 8.
+* "::" stands for one or more groups of zeros, and may appear at most once.
+* The last 32 bits of an IPv6 address may be written as an embedded IPv4 address, e.g. "::ffff:192.0.2.1", but
+  nowhere else.
+* A zone ID follows a '%' and can't be empty.
+
+Display writes the canonical form from RFC 5952: lower case hex without leading zeros, and the longest run of two
+or more zero groups (the first, if there's a tie) shortened to "::". IPv4-mapped addresses are written with their
+IPv4 part in dotted form, e.g. "::ffff:192.0.2.1". Parsing and then displaying an address gives the same text as
+std::net does, apart from the zone ID, which std::net::IpAddr has no place for.
+
+*/
+
+use std::error::Error;
+use std::fmt;
+use std::net;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IpAddrKind {
+    V4,
+    V6,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IpAddr {
+    V4(u8, u8, u8, u8),
+    V6 { segments: [u16; 8], zone: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseIpError {
+    Empty,
+    Ipv4OctetCount { count: usize },
+    Ipv4InvalidOctet(String),
+    Ipv4OctetTooLarge(String),
+    Ipv4LeadingZero(String),
+    Ipv6GroupCount { count: usize },
+    // "::" stands for at least one group, so at most 7 can be written next to it.
+    Ipv6TooManyGroups { count: usize },
+    Ipv6MultipleCompressions,
+    Ipv6InvalidGroup(String),
+    // A ':' at the start or end that isn't part of a "::", or an empty group between two colons.
+    Ipv6EmptyGroup,
+    Ipv6MisplacedIpv4,
+    Ipv6EmptyZone,
+}
+
+impl fmt::Display for ParseIpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseIpError::Empty => write!(f, "address is empty"),
+            ParseIpError::Ipv4OctetCount { count } => {
+                write!(f, "an IPv4 address has 4 octets, but {} were given", count)
+            }
+            ParseIpError::Ipv4InvalidOctet(octet) => write!(f, "IPv4 octet {:?} is not a number", octet),
+            ParseIpError::Ipv4OctetTooLarge(octet) => write!(f, "IPv4 octet {} is larger than 255", octet),
+            ParseIpError::Ipv4LeadingZero(octet) => {
+                write!(f, "IPv4 octet {:?} has a leading zero, which is ambiguous", octet)
+            }
+            ParseIpError::Ipv6GroupCount { count } => {
+                write!(f, "an IPv6 address has 8 groups, but {} were given", count)
+            }
+            ParseIpError::Ipv6TooManyGroups { count } => {
+                write!(f, "\"::\" leaves room for at most 7 groups, but {} were given", count)
+            }
+            ParseIpError::Ipv6MultipleCompressions => write!(f, "\"::\" may appear only once"),
+            ParseIpError::Ipv6InvalidGroup(group) => {
+                write!(f, "IPv6 group {:?} is not 1 to 4 hexadecimal digits", group)
+            }
+            ParseIpError::Ipv6EmptyGroup => write!(f, "IPv6 address has an empty group"),
+            ParseIpError::Ipv6MisplacedIpv4 => {
+                write!(f, "an embedded IPv4 address can only be the last part of an IPv6 address")
+            }
+            ParseIpError::Ipv6EmptyZone => write!(f, "zone ID after '%' is empty"),
+        }
+    }
+}
+
+impl Error for ParseIpError {}
+
+impl IpAddr {
+    pub fn kind(&self) -> IpAddrKind {
+        match self {
+            IpAddr::V4(..) => IpAddrKind::V4,
+            IpAddr::V6 { .. } => IpAddrKind::V6,
+        }
+    }
+
+    pub fn zone(&self) -> Option<&str> {
+        match self {
+            IpAddr::V6 { zone, .. } => zone.as_deref(),
+            IpAddr::V4(..) => None,
+        }
+    }
+
+    pub fn is_loopback(&self) -> bool {
+        match self {
+            IpAddr::V4(a, ..) => *a == 127,
+            IpAddr::V6 { segments, .. } => *segments == [0, 0, 0, 0, 0, 0, 0, 1],
+        }
+    }
+}
+
+fn parse_octet(octet: &str) -> Result<u8, ParseIpError> {
+    if octet.is_empty() || !octet.chars().all(|c| c.is_ascii_digit()) {
+        return Err(ParseIpError::Ipv4InvalidOctet(octet.to_string()));
+    }
+    if octet.len() > 1 && octet.starts_with('0') {
+        return Err(ParseIpError::Ipv4LeadingZero(octet.to_string()));
+    }
+    // At most three digits, so the u16 can't overflow.
+    match octet.parse::<u16>() {
+        Ok(value) if octet.len() <= 3 && value <= 255 => Ok(value as u8),
+        _ => Err(ParseIpError::Ipv4OctetTooLarge(octet.to_string())),
+    }
+}
+
+fn parse_ipv4(s: &str) -> Result<[u8; 4], ParseIpError> {
+    let octets: Vec<&str> = s.split('.').collect();
+    if octets.len() != 4 {
+        return Err(ParseIpError::Ipv4OctetCount { count: octets.len() });
+    }
+
+    let mut parsed = [0; 4];
+    for (octet, text) in parsed.iter_mut().zip(octets) {
+        *octet = parse_octet(text)?;
+    }
+    Ok(parsed)
+}
+
+// parse_groups parses the colon separated groups on one side of a "::". Only the last part of the whole address,
+// which `last` says this is, may be an embedded IPv4 address.
+fn parse_groups(s: &str, last: bool) -> Result<Vec<u16>, ParseIpError> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let parts: Vec<&str> = s.split(':').collect();
+    let mut groups = Vec::with_capacity(parts.len() + 1);
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() {
+            return Err(ParseIpError::Ipv6EmptyGroup);
+        }
+        if part.contains('.') {
+            if !last || i != parts.len() - 1 {
+                return Err(ParseIpError::Ipv6MisplacedIpv4);
+            }
+            let [a, b, c, d] = parse_ipv4(part)?;
+            groups.push(u16::from_be_bytes([a, b]));
+            groups.push(u16::from_be_bytes([c, d]));
+            continue;
+        }
+        if part.len() > 4 || !part.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ParseIpError::Ipv6InvalidGroup(part.to_string()));
+        }
+        groups.push(u16::from_str_radix(part, 16).map_err(|_| ParseIpError::Ipv6InvalidGroup(part.to_string()))?);
+    }
+    Ok(groups)
+}
+
+fn parse_ipv6(s: &str) -> Result<IpAddr, ParseIpError> {
+    let (address, zone) = match s.split_once('%') {
+        Some((_, "")) => return Err(ParseIpError::Ipv6EmptyZone),
+        Some((address, zone)) => (address, Some(zone.to_string())),
+        None => (s, None),
+    };
+
+    let mut segments = [0; 8];
+    match address.find("::") {
+        // ":::" is two overlapping "::"s.
+        Some(at) if address.rfind("::") != Some(at) => return Err(ParseIpError::Ipv6MultipleCompressions),
+        Some(at) => {
+            let head = parse_groups(&address[..at], false)?;
+            let tail = parse_groups(&address[at + 2..], true)?;
+            if head.len() + tail.len() > 7 {
+                return Err(ParseIpError::Ipv6TooManyGroups {
+                    count: head.len() + tail.len(),
+                });
+            }
+            segments[..head.len()].copy_from_slice(&head);
+            segments[8 - tail.len()..].copy_from_slice(&tail);
+        }
+        None => {
+            let groups = parse_groups(address, true)?;
+            if groups.len() != 8 {
+                return Err(ParseIpError::Ipv6GroupCount { count: groups.len() });
+            }
+            segments.copy_from_slice(&groups);
+        }
+    }
+
+    Ok(IpAddr::V6 { segments, zone })
+}
+
+impl FromStr for IpAddr {
+    type Err = ParseIpError;
+
+    fn from_str(s: &str) -> Result<IpAddr, ParseIpError> {
+        if s.is_empty() {
+            return Err(ParseIpError::Empty);
+        }
+        if s.contains(':') {
+            return parse_ipv6(s);
+        }
+        let [a, b, c, d] = parse_ipv4(s)?;
+        Ok(IpAddr::V4(a, b, c, d))
+    }
+}
+
+// longest_zero_run returns where the longest run of two or more zero groups starts and how long it is. RFC 5952
+// says to shorten the first one when two runs are equally long.
+fn longest_zero_run(segments: &[u16; 8]) -> Option<(usize, usize)> {
+    let mut best: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i < segments.len() {
+        let len = segments[i..].iter().take_while(|&&group| group == 0).count();
+        if len >= 2 && best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((i, len));
+        }
+        i += len.max(1);
+    }
+    best
+}
+
+fn write_groups(f: &mut fmt::Formatter, groups: &[u16]) -> fmt::Result {
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            f.write_str(":")?;
+        }
+        write!(f, "{:x}", group)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for IpAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (segments, zone) = match self {
+            IpAddr::V4(a, b, c, d) => return write!(f, "{}.{}.{}.{}", a, b, c, d),
+            IpAddr::V6 { segments, zone } => (segments, zone),
+        };
+
+        if segments[..6] == [0, 0, 0, 0, 0, 0xffff] {
+            let [a, b] = segments[6].to_be_bytes();
+            let [c, d] = segments[7].to_be_bytes();
+            write!(f, "::ffff:{}.{}.{}.{}", a, b, c, d)?;
+        } else if let Some((start, len)) = longest_zero_run(segments) {
+            write_groups(f, &segments[..start])?;
+            f.write_str("::")?;
+            write_groups(f, &segments[start + len..])?;
+        } else {
+            write_groups(f, segments)?;
+        }
+
+        if let Some(zone) = zone {
+            write!(f, "%{}", zone)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<net::IpAddr> for IpAddr {
+    fn from(addr: net::IpAddr) -> IpAddr {
+        match addr {
+            net::IpAddr::V4(v4) => {
+                let [a, b, c, d] = v4.octets();
+                IpAddr::V4(a, b, c, d)
+            }
+            net::IpAddr::V6(v6) => IpAddr::V6 {
+                segments: v6.segments(),
+                zone: None,
+            },
+        }
+    }
+}
+
+// Converting to std::net::IpAddr drops the zone ID, which it has no place for.
+impl From<IpAddr> for net::IpAddr {
+    fn from(addr: IpAddr) -> net::IpAddr {
+        match addr {
+            IpAddr::V4(a, b, c, d) => net::IpAddr::V4(net::Ipv4Addr::new(a, b, c, d)),
+            IpAddr::V6 { segments: s, .. } => {
+                net::IpAddr::V6(net::Ipv6Addr::new(s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cases lists addresses and what parsing each gives: the canonical RFC 5952 text it displays as, or the error.
+    fn cases() -> Vec<(&'static str, Result<&'static str, ParseIpError>)> {
+        vec![
+            // IPv4.
+            ("1.2.3.4", Ok("1.2.3.4")),
+            ("0.0.0.0", Ok("0.0.0.0")),
+            ("255.255.255.255", Ok("255.255.255.255")),
+            ("", Err(ParseIpError::Empty)),
+            ("1.2.3", Err(ParseIpError::Ipv4OctetCount { count: 3 })),
+            ("1.2.3.4.5", Err(ParseIpError::Ipv4OctetCount { count: 5 })),
+            ("1234", Err(ParseIpError::Ipv4OctetCount { count: 1 })),
+            ("1..3.4", Err(ParseIpError::Ipv4InvalidOctet(String::new()))),
+            ("a.b.c.d", Err(ParseIpError::Ipv4InvalidOctet(String::from("a")))),
+            ("+1.2.3.4", Err(ParseIpError::Ipv4InvalidOctet(String::from("+1")))),
+            ("256.1.1.1", Err(ParseIpError::Ipv4OctetTooLarge(String::from("256")))),
+            ("1.2.3.0255", Err(ParseIpError::Ipv4LeadingZero(String::from("0255")))),
+            ("01.2.3.4", Err(ParseIpError::Ipv4LeadingZero(String::from("01")))),
+            // IPv6, shortened to its longest run of zeros.
+            ("::", Ok("::")),
+            ("1::", Ok("1::")),
+            ("::1", Ok("::1")),
+            ("0:0:0:0:0:0:0:1", Ok("::1")),
+            ("2001:DB8:0:0:0:0:0:1", Ok("2001:db8::1")),
+            ("2001:0db8:0000:0000:0001:0000:0000:0000", Ok("2001:db8:0:0:1::")),
+            // The first of two equally long runs is the one shortened.
+            ("2001:db8:0:0:1:0:0:1", Ok("2001:db8::1:0:0:1")),
+            // A single zero group is never shortened.
+            ("2001:db8:0:1:1:1:1:1", Ok("2001:db8:0:1:1:1:1:1")),
+            ("1:2:3:4:5:6:7::", Ok("1:2:3:4:5:6:7:0")),
+            ("::2:3:4:5:6:7:8", Ok("0:2:3:4:5:6:7:8")),
+            ("fe80::1%eth0", Ok("fe80::1%eth0")),
+            // Embedded IPv4.
+            ("::ffff:1.2.3.4", Ok("::ffff:1.2.3.4")),
+            ("0:0:0:0:0:ffff:102:304", Ok("::ffff:1.2.3.4")),
+            ("::ffff:0:1.2.3.4", Ok("::ffff:0:102:304")),
+            ("64:ff9b::192.0.2.33", Ok("64:ff9b::c000:221")),
+            ("::ffff:1.2.3", Err(ParseIpError::Ipv4OctetCount { count: 3 })),
+            ("::ffff:1.2.3.256", Err(ParseIpError::Ipv4OctetTooLarge(String::from("256")))),
+            ("::ffff:01.2.3.4", Err(ParseIpError::Ipv4LeadingZero(String::from("01")))),
+            ("1.2.3.4::", Err(ParseIpError::Ipv6MisplacedIpv4)),
+            ("::1.2.3.4:5", Err(ParseIpError::Ipv6MisplacedIpv4)),
+            // Malformed IPv6.
+            ("1::2::3", Err(ParseIpError::Ipv6MultipleCompressions)),
+            (":::", Err(ParseIpError::Ipv6MultipleCompressions)),
+            ("12345::", Err(ParseIpError::Ipv6InvalidGroup(String::from("12345")))),
+            ("g::", Err(ParseIpError::Ipv6InvalidGroup(String::from("g")))),
+            ("::-1", Err(ParseIpError::Ipv6InvalidGroup(String::from("-1")))),
+            ("1:2:3:4:5:6:7", Err(ParseIpError::Ipv6GroupCount { count: 7 })),
+            ("1:2:3:4:5:6:7:8:9", Err(ParseIpError::Ipv6GroupCount { count: 9 })),
+            ("1::2:3:4:5:6:7:8", Err(ParseIpError::Ipv6TooManyGroups { count: 8 })),
+            (":1::", Err(ParseIpError::Ipv6EmptyGroup)),
+            ("1:", Err(ParseIpError::Ipv6EmptyGroup)),
+            (":1:2:3:4:5:6:7", Err(ParseIpError::Ipv6EmptyGroup)),
+            ("fe80::1%", Err(ParseIpError::Ipv6EmptyZone)),
+        ]
+    }
+
+    #[test]
+    fn addresses() {
+        for (input, expected) in cases() {
+            let parsed = input.parse::<IpAddr>();
+            match expected {
+                Ok(canonical) => {
+                    let addr = parsed.unwrap_or_else(|e| panic!("{:?}: {}", input, e));
+                    assert_eq!(addr.to_string(), canonical, "{:?}", input);
+                    // The canonical form is a fixed point.
+                    assert_eq!(canonical.parse::<IpAddr>().as_ref(), Ok(&addr), "{:?}", canonical);
+                }
+                Err(error) => assert_eq!(parsed, Err(error), "{:?}", input),
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_form_matches_std() {
+        for (input, expected) in cases() {
+            let canonical = match expected {
+                Ok(canonical) if !canonical.contains('%') => canonical,
+                _ => continue,
+            };
+            let std_addr: net::IpAddr = canonical.parse().unwrap();
+            assert_eq!(std_addr.to_string(), canonical, "{:?}", input);
+            assert_eq!(IpAddr::from(std_addr), input.parse().unwrap());
+            assert_eq!(net::IpAddr::from(input.parse::<IpAddr>().unwrap()), std_addr);
+        }
+    }
+
+    #[test]
+    fn kinds_and_zones() {
+        let v4: IpAddr = "127.0.0.1".parse().unwrap();
+        let v6: IpAddr = "fe80::1%eth0".parse().unwrap();
+        assert_eq!((v4.kind(), v4.zone(), v4.is_loopback()), (IpAddrKind::V4, None, true));
+        assert_eq!((v6.kind(), v6.zone(), v6.is_loopback()), (IpAddrKind::V6, Some("eth0"), false));
+        assert!("::1".parse::<IpAddr>().unwrap().is_loopback());
+        // Converting to std::net drops the zone.
+        assert_eq!(net::IpAddr::from(v6).to_string(), "fe80::1");
+    }
+}
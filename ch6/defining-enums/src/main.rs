@@ -36,35 +36,95 @@ Why enums over structs? If there are multiple variants that are to be treated li
 */
 
 /*
-Enums also allow us to encode additional data into them directly.
+Enums also allow us to encode additional data into them directly, and each variant of an enum can contain different
+data. The ip module goes one step further than `V6(String)`: both variants hold the address itself, parsed and
+validated from text, and IpAddrKind names the family.
 */
-#[derive(Debug)]
-enum IpAddrKind {
-    V4(String),
-    V6(String),
-}
-
-/*
-Each variant of an enum can contain different data.
-*/
-#[derive(Debug)]
-enum IpAddr {
-    V4(u8, u8, u8, u8),
-    V6(String),
-}
+use defining_enums::cidr::{self, Network};
+use defining_enums::ip::{IpAddr, IpAddrKind};
 
 fn main() {
-    let home = IpAddrKind::V4(String::from("127.0.0.1"));
-    let loopback = IpAddrKind::V6(String::from("::1"));
-
-    route(home);
-    route(loopback);
-
-    let home2 = IpAddr::V4(127, 0, 0, 1);
-    let loopback2 = IpAddr::V6(String::from("::1"));
-
-    route2(home2);
-    route2(loopback2);
+    let home: IpAddr = "127.0.0.1".parse().unwrap();
+    let loopback: IpAddr = "::1".parse().unwrap();
+
+    route(home.kind());
+    route(loopback.kind());
+
+    route2(home);
+    route2(loopback);
+
+    // Valid addresses print in their canonical form; invalid ones print why they were rejected.
+    let table = [
+        "192.168.1.1",
+        "0.0.0.0",
+        "2001:0DB8:0000:0000:0000:0000:0000:0001",
+        "2001:db8:0:0:1:0:0:1",
+        "2001:db8::1:0:0:0",
+        "2001:db8:0:1:1:1:1:1",
+        "::",
+        "::ffff:192.0.2.1",
+        "64:ff9b::192.0.2.1",
+        "fe80::1%eth0",
+        "",
+        "1.2.3",
+        "1.2.3.4.5",
+        "256.1.1.1",
+        "01.2.3.4",
+        "1.2.3.x",
+        "1:2:3:4:5:6:7",
+        "1::2::3",
+        ":::",
+        ":1::2",
+        "1:2:3:4:5:6:7::8",
+        "12345::",
+        "::192.0.2.1:1",
+        "fe80::1%",
+    ];
+    for text in table.iter() {
+        match text.parse::<IpAddr>() {
+            Ok(addr) => println!("{:45} {:?} {}", format!("{:?}", text), addr.kind(), addr),
+            Err(e) => println!("{:45} error: {}", format!("{:?}", text), e),
+        }
+    }
+
+    // Conversion to std::net::IpAddr drops the zone ID.
+    let link_local: IpAddr = "fe80::1%eth0".parse().unwrap();
+    let std_addr = std::net::IpAddr::from(link_local);
+    println!("std: {} back: {}", std_addr, IpAddr::from(std_addr));
+
+    // Networks are parsed strictly, so a typo in a firewall rule is caught rather than silently widened.
+    for text in ["10.0.0.0/8", "2001:db8::/32", "10.0.0.1/8", "10.0.0.0/33", "192.168.1.0", "fe80::%eth0/64"].iter() {
+        match text.parse::<Network>() {
+            Ok(network) => println!(
+                "{:20} network {} broadcast {} netmask {}",
+                text,
+                network.network(),
+                network.broadcast(),
+                network.netmask()
+            ),
+            Err(e) => println!("{:20} error: {}", text, e),
+        }
+    }
+
+    let allowed: Network = "10.0.0.0/8".parse().unwrap();
+    for text in ["10.1.2.3", "11.0.0.1", "::ffff:10.1.2.3"].iter() {
+        let addr: IpAddr = text.parse().unwrap();
+        println!("{} in {}: {}", addr, allowed, allowed.contains(&addr));
+    }
+
+    let point_to_point: Network = "192.168.1.0/30".parse().unwrap();
+    let hosts: Vec<String> = point_to_point.hosts().map(|host| host.to_string()).collect();
+    println!("hosts of {}: {}", point_to_point, hosts.join(", "));
+
+    let subnets: Vec<String> = allowed.subnets(10).unwrap().map(|subnet| subnet.to_string()).collect();
+    println!("{} split into /10s: {}", allowed, subnets.join(", "));
+
+    let addresses: Vec<IpAddr> = ["10.0.0.0", "10.0.0.1", "10.0.0.2", "10.0.0.3", "10.0.0.4", "2001:db8::", "2001:db8::1"]
+        .iter()
+        .map(|text| text.parse().unwrap())
+        .collect();
+    let summary: Vec<String> = cidr::summarize(&addresses).iter().map(|network| network.to_string()).collect();
+    println!("summarized: {}", summary.join(", "));
 }
 
 fn route(ip_kind: IpAddrKind) {